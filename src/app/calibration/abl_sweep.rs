@@ -0,0 +1,107 @@
+use eframe::{
+    egui::{self, Layout, Ui},
+    emath::Align,
+    epaint::Color32,
+};
+use egui_plot::{Line, MarkerShape, Plot, Points};
+
+use crate::app::PGenApp;
+
+pub fn draw_abl_sweep_ui(app: &mut PGenApp, ui: &mut Ui) {
+    let pgen_connected = app.state.connected_state.connected;
+    let abl_started = app.cal_state.abl_sweep.started;
+
+    let can_start = pgen_connected
+        && app.cal_state.spotread_started
+        && !app.processing
+        && !app.cal_state.measurement_in_progress();
+
+    ui.horizontal(|ui| {
+        ui.heading("ABL sweep");
+
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            if abl_started {
+                ui.add_enabled_ui(!app.cal_state.abl_sweep.stop_requested, |ui| {
+                    if ui.button("Stop").clicked() {
+                        app.cal_state.abl_sweep.stop_requested = true;
+                    }
+                });
+            } else {
+                ui.add_enabled_ui(can_start, |ui| {
+                    if ui.button("Measure window sizes").clicked() {
+                        let current_size = app.state.pattern_config.preset_size;
+                        app.cal_state.abl_sweep.start(current_size);
+                        app.calibration_send_measure_abl_step();
+                    }
+                });
+            }
+
+            ui.add_enabled_ui(!abl_started, |ui| {
+                ui.add(
+                    egui::DragValue::new(&mut app.cal_state.abl_sweep.level)
+                        .speed(0.01)
+                        .range(0.0..=1.0)
+                        .max_decimals(3),
+                );
+                ui.label("Level");
+            });
+        });
+    });
+
+    let abl_sweep = &app.cal_state.abl_sweep;
+    if abl_sweep.results.is_empty() {
+        return;
+    }
+
+    if let Some(report) = abl_sweep.report() {
+        ui.indent("cal_abl_report_indent", |ui| {
+            egui::Grid::new("cal_abl_report_grid")
+                .spacing([16.0, 4.0])
+                .show(ui, |ui| {
+                    ui.label(format!(
+                        "Peak: {:.4} nits ({})",
+                        report.peak_y, report.peak_size
+                    ));
+                    if let Some(full_field_y) = report.full_field_y {
+                        ui.label(format!("Full field: {full_field_y:.4} nits"));
+                    }
+                    if let Some(drop) = report.full_field_drop() {
+                        ui.label(format!("ABL drop: {:.2} %", drop * 100.0));
+                    }
+                    ui.end_row();
+                });
+        });
+    }
+
+    let dark_mode = ui.global_style().visuals.dark_mode;
+    let lum_color = if dark_mode {
+        Color32::YELLOW
+    } else {
+        Color32::from_rgb(255, 153, 0)
+    };
+
+    let points = abl_sweep.curve();
+    let line = Line::new("ABL curve", points.clone())
+        .color(lum_color)
+        .highlight(true);
+    let markers = Points::new("ABL points", points)
+        .shape(MarkerShape::Circle)
+        .radius(2.5)
+        .color(lum_color)
+        .highlight(true);
+
+    Plot::new("abl_sweep_plot")
+        .view_aspect(2.0)
+        .allow_scroll(false)
+        .clamp_grid(true)
+        .show_background(false)
+        .include_x(0.0)
+        .include_x(100.0)
+        .include_y(0.0)
+        .x_axis_label("Window %")
+        .y_axis_label("nits")
+        .show(ui, |plot_ui| {
+            plot_ui.line(line);
+            plot_ui.points(markers);
+        });
+}
@@ -2,7 +2,8 @@ use std::sync::OnceLock;
 
 use ecolor::gamma_u8_from_linear_f32;
 use eframe::{
-    egui::{Color32, Spinner, Ui},
+    egui::{self, Color32, Layout, Spinner, Ui},
+    emath::Align,
     epaint::{ColorImage, Pos2, Rect, Stroke, Vec2},
 };
 use egui_plot::{MarkerShape, Plot, PlotImage, PlotPoint, PlotPoints, Points, Polygon};
@@ -16,9 +17,11 @@ use ndarray::{
     Array,
     parallel::prelude::{IntoParallelRefIterator, ParallelIterator},
 };
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, Display, EnumIter, IntoEnumIterator};
 use tokio::sync::mpsc::Sender;
 
-use crate::{app::PGenAppUpdate, utils::normalize_float_rgb_components};
+use crate::{app::PGenAppUpdate, calibration::xy_to_uv, utils::normalize_float_rgb_components};
 
 use super::{CalibrationState, ReadingResult};
 
@@ -31,6 +34,8 @@ static CIE_1931_DIAGRAM_POINTS: OnceLock<Vec<SpectralLocusPoint>> = OnceLock::ne
 // Calculated from locis coordinates
 const XY_TOP_LEFT: Vec2 = Vec2::new(0.00364, 0.83409);
 const XY_BOTTOM_RIGHT: Vec2 = Vec2::new(0.73469, 0.00477);
+const UV_TOP_LEFT: Vec2 = Vec2::new(0.00138, 0.58676);
+const UV_BOTTOM_RIGHT: Vec2 = Vec2::new(0.62337, 0.01584);
 
 #[derive(Debug, Clone, Copy)]
 pub struct SpectralLocusPoint {
@@ -39,28 +44,78 @@ pub struct SpectralLocusPoint {
     y: f64,
 }
 
+#[derive(
+    Display, AsRefStr, Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, EnumIter,
+)]
+pub enum CieDiagram {
+    #[default]
+    #[strum(to_string = "CIE 1931 xy")]
+    Xy,
+    #[strum(to_string = "CIE 1976 u'v'")]
+    Uv,
+}
+
 pub fn draw_cie_diagram_plot(
     ui: &mut Ui,
     cal_state: &mut CalibrationState,
     results: &[ReadingResult],
+    app_tx: &Sender<PGenAppUpdate>,
 ) {
     ui.horizontal(|ui| {
-        ui.heading("Chromaticity xy");
+        ui.heading(format!(
+            "Chromaticity {}",
+            cal_state.cie_diagram.axes_label()
+        ));
         ui.checkbox(&mut cal_state.show_cie_diagram, "Show");
+
+        if cal_state.show_cie_diagram {
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                egui::ComboBox::from_id_salt(egui::Id::new("cie_diagram_kind"))
+                    .selected_text(cal_state.cie_diagram.as_ref())
+                    .show_ui(ui, |ui| {
+                        ui.set_min_width(115.0);
+                        for kind in CieDiagram::iter() {
+                            ui.selectable_value(&mut cal_state.cie_diagram, kind, kind.as_ref());
+                        }
+                    });
+
+                if cal_state.cie_diagram == CieDiagram::Uv {
+                    ui.add_enabled(
+                        cal_state.show_cie_tolerance,
+                        egui::DragValue::new(&mut cal_state.cie_tolerance)
+                            .prefix("Δu'v' ")
+                            .max_decimals(4)
+                            .speed(0.0001)
+                            .range(0.0005..=0.05),
+                    );
+                    ui.checkbox(&mut cal_state.show_cie_tolerance, "Tolerance");
+                }
+            });
+        }
     });
 
     if cal_state.show_cie_diagram {
-        draw_diagram(ui, cal_state, results);
+        draw_diagram(ui, cal_state, results, app_tx);
     }
 }
 
-fn draw_diagram(ui: &mut Ui, cal_state: &mut CalibrationState, results: &[ReadingResult]) {
-    if let (Some(texture), Some(locis_points)) = (
-        cal_state.cie_texture.as_ref(),
-        CIE_1931_DIAGRAM_POINTS.get(),
-    ) {
+fn draw_diagram(
+    ui: &mut Ui,
+    cal_state: &mut CalibrationState,
+    results: &[ReadingResult],
+    app_tx: &Sender<PGenAppUpdate>,
+) {
+    let kind = cal_state.cie_diagram;
+    cal_state.request_cie_texture(kind, app_tx);
+
+    if let (Some(texture), Some(locis_points)) =
+        (cal_state.cie_texture(kind), CIE_1931_DIAGRAM_POINTS.get())
+    {
         let dark_mode = ui.global_style().visuals.dark_mode;
-        let locis_points: Vec<_> = locis_points.iter().map(|e| [e.x, e.y]).collect();
+        let locis_points: Vec<_> = locis_points
+            .iter()
+            .map(|e| kind.coords_from_xy([e.x, e.y]))
+            .collect();
 
         let curve_stroke_colour = if dark_mode {
             Color32::from_rgba_unmultiplied(255, 255, 255, 64)
@@ -71,13 +126,14 @@ fn draw_diagram(ui: &mut Ui, cal_state: &mut CalibrationState, results: &[Readin
             .fill_color(Color32::TRANSPARENT)
             .stroke(Stroke::new(4.0, curve_stroke_colour));
 
-        let img_size = Vec2::new(XY_BOTTOM_RIGHT.x, XY_TOP_LEFT.y);
+        let (top_left, bottom_right) = kind.bounds();
+        let img_size = Vec2::new(bottom_right.x, top_left.y);
         let img_center = img_size / 2.0;
         let center_point = PlotPoint::new(img_center.x, img_center.y);
         let image =
             PlotImage::new("Image", texture.id(), center_point, img_size).uv(Rect::from_two_pos(
-                Pos2::new(0.0, 1.0 - XY_TOP_LEFT.y),
-                Pos2::new(XY_BOTTOM_RIGHT.x, 1.0),
+                Pos2::new(0.0, 1.0 - top_left.y),
+                Pos2::new(bottom_right.x, 1.0),
             ));
 
         let triangle_colour = if dark_mode {
@@ -86,14 +142,36 @@ fn draw_diagram(ui: &mut Ui, cal_state: &mut CalibrationState, results: &[Readin
             Color32::GRAY
         };
         let target_csp = cal_state.target_csp.to_kolor();
-        let target_primaries = target_csp.primaries().values().map(|xy| xy).to_vec();
+        let target_primaries = target_csp
+            .primaries()
+            .values()
+            .map(|xy| kind.coords_from_xy(xy))
+            .to_vec();
         let target_gamut_triangle = Polygon::new("Target Gamut", target_primaries)
             .stroke(Stroke::new(2.0, triangle_colour))
             .fill_color(Color32::TRANSPARENT);
 
+        let target_rgb_to_xyz = cal_state.target_rgb_to_xyz_conv();
+        let measured_gamut_colour = if dark_mode {
+            Color32::from_rgb(255, 179, 102)
+        } else {
+            Color32::from_rgb(204, 102, 0)
+        };
+        let measured_gamut_poly = cal_state.gamut_report.report().map(|report| {
+            let points: Vec<_> = report
+                .measured_xy
+                .iter()
+                .map(|xy| kind.coords_from_xy(*xy))
+                .collect();
+
+            Polygon::new("Measured Gamut", points)
+                .stroke(Stroke::new(2.0, measured_gamut_colour))
+                .fill_color(Color32::TRANSPARENT)
+        });
+
         let target_eotf = cal_state.eotf;
         let results_points = results.iter().map(|res| {
-            let coords = [res.xyy[0], res.xyy[1]];
+            let coords = kind.coords_from_xy([res.xyy[0], res.xyy[1]]);
             // OETF from assumed target
             let rgb_gamma = target_eotf.convert_vec(res.rgb, true);
             let rgb_gamma = normalize_float_rgb_components(rgb_gamma);
@@ -105,10 +183,17 @@ fn draw_diagram(ui: &mut Ui, cal_state: &mut CalibrationState, results: &[Readin
             )
         });
 
-        let target_rgb_to_xyz = cal_state.target_rgb_to_xyz_conv();
+        let comparison_points: Vec<[f64; 2]> = cal_state
+            .comparison_results
+            .iter()
+            .map(|res| kind.coords_from_xy([res.xyy[0], res.xyy[1]]))
+            .collect();
+
+        let tolerance = (kind == CieDiagram::Uv && cal_state.show_cie_tolerance)
+            .then_some(cal_state.cie_tolerance);
         let results_targets = results
             .iter()
-            .map(|res| create_target_box_for_result(res, target_rgb_to_xyz));
+            .map(|res| create_target_shape_for_result(res, target_rgb_to_xyz, kind, tolerance));
 
         let target_box_colour = if dark_mode {
             Color32::GRAY
@@ -126,20 +211,31 @@ fn draw_diagram(ui: &mut Ui, cal_state: &mut CalibrationState, results: &[Readin
                 plot_ui.image(image);
                 plot_ui.polygon(curve_poly);
                 plot_ui.polygon(target_gamut_triangle);
+                if let Some(measured_gamut_poly) = measured_gamut_poly {
+                    plot_ui.polygon(measured_gamut_poly);
+                }
 
-                for (center, xy_target) in results_targets {
-                    let poly = xy_target
+                for (center, target_shape) in results_targets {
+                    let poly = target_shape
                         .stroke(Stroke::new(2.0, target_box_colour))
                         .fill_color(Color32::TRANSPARENT);
+                    plot_ui.polygon(poly);
+
                     let center_cross = Points::new("Center", center)
                         .radius(12.0)
                         .color(Color32::BLACK)
                         .shape(MarkerShape::Cross);
-
-                    plot_ui.polygon(poly);
                     plot_ui.points(center_cross);
                 }
 
+                if !comparison_points.is_empty() {
+                    let before = Points::new("Result before", comparison_points)
+                        .radius(6.0)
+                        .color(Color32::GRAY)
+                        .filled(false);
+                    plot_ui.points(before);
+                }
+
                 for (res_coords, measured_colour) in results_points {
                     let point_out = Points::new("Result out", res_coords)
                         .radius(8.0)
@@ -157,34 +253,55 @@ fn draw_diagram(ui: &mut Ui, cal_state: &mut CalibrationState, results: &[Readin
     }
 }
 
-pub fn compute_cie_chromaticity_diagram_worker(app_tx: Sender<PGenAppUpdate>) {
-    tokio::task::spawn(async move {
-        let locis = CIE_1931_DIAGRAM_POINTS.get_or_init(|| {
-            CIE_1931_2DEG_OBSERVER_DATASET
-                .lines()
-                .map(|line| {
-                    let mut split = line.split(',');
-
-                    let wavelength = split.next().and_then(|e| e.parse().ok()).unwrap();
-                    let x = split.next().and_then(|e| e.parse().ok()).unwrap();
-                    let y = split.next().and_then(|e| e.parse().ok()).unwrap();
-
-                    SpectralLocusPoint {
-                        _wavelength: wavelength,
-                        x,
-                        y,
-                    }
-                })
-                .collect()
-        });
-        let locis_points: Vec<_> = locis.iter().map(|locus| [locus.x, locus.y]).collect();
+fn cie_1931_spectral_locus() -> &'static [SpectralLocusPoint] {
+    CIE_1931_DIAGRAM_POINTS.get_or_init(|| {
+        CIE_1931_2DEG_OBSERVER_DATASET
+            .lines()
+            .map(|line| {
+                let mut split = line.split(',');
+
+                let wavelength = split.next().and_then(|e| e.parse().ok()).unwrap();
+                let x = split.next().and_then(|e| e.parse().ok()).unwrap();
+                let y = split.next().and_then(|e| e.parse().ok()).unwrap();
+
+                SpectralLocusPoint {
+                    _wavelength: wavelength,
+                    x,
+                    y,
+                }
+            })
+            .collect()
+    })
+}
+
+pub(super) fn spectral_locus_coords(kind: CieDiagram) -> Vec<[f64; 2]> {
+    cie_1931_spectral_locus()
+        .iter()
+        .map(|locus| kind.coords_from_xy([locus.x, locus.y]))
+        .collect()
+}
 
-        let img = compute_cie_xy_diagram_image(&locis_points);
-        app_tx.try_send(PGenAppUpdate::CieDiagramReady(img)).ok();
+/// Renders the diagram texture in the background, sent back with `PGenAppUpdate::CieDiagramReady`
+pub(super) fn compute_cie_chromaticity_diagram_worker(
+    app_tx: Sender<PGenAppUpdate>,
+    kind: CieDiagram,
+) {
+    tokio::task::spawn(async move {
+        let locis_points = spectral_locus_coords(kind);
+
+        let res =
+            tokio::task::spawn_blocking(move || compute_cie_diagram_image(kind, &locis_points))
+                .await;
+        if let Ok(img) = res {
+            app_tx
+                .send(PGenAppUpdate::CieDiagramReady(kind, img))
+                .await
+                .ok();
+        }
     });
 }
 
-fn compute_cie_xy_diagram_image(points: &[[f64; 2]]) -> ColorImage {
+fn compute_cie_diagram_image(kind: CieDiagram, points: &[[f64; 2]]) -> ColorImage {
     let resolution = 4096;
 
     let x_points = Array::linspace(0.0, 1.0, resolution);
@@ -205,6 +322,7 @@ fn compute_cie_xy_diagram_image(points: &[[f64; 2]]) -> ColorImage {
                 return Color32::TRANSPARENT;
             }
 
+            let [x, y] = kind.xy_from_coords([x, y]);
             let xyy = [x, y, 1.0].into();
             let xyz = xyY_to_XYZ(xyy, wp);
 
@@ -252,24 +370,81 @@ fn point_in_or_on_convex_polygon(points: &[[f64; 2]], x: f64, y: f64) -> bool {
 }
 
 const TARGET_BOX_LENGTH: f64 = 0.0075;
-fn create_target_box_for_result(
+const TOLERANCE_CIRCLE_POINTS: usize = 64;
+fn create_target_shape_for_result(
     res: &'_ ReadingResult,
     target_rgb_to_xyz: ColorConversion,
+    kind: CieDiagram,
+    tolerance: Option<f64>,
 ) -> ([f64; 2], Polygon<'_>) {
     let xyy = res.ref_xyy_display_space(target_rgb_to_xyz);
+    let [x, y] = kind.coords_from_xy([xyy[0], xyy[1]]);
+
+    // Tolerance circle in u'v', otherwise the target box
+    let poly = match tolerance {
+        Some(radius) => {
+            let circle = (0..TOLERANCE_CIRCLE_POINTS)
+                .map(|i| {
+                    let angle = std::f64::consts::TAU * i as f64 / TOLERANCE_CIRCLE_POINTS as f64;
+                    [x + radius * angle.cos(), y + radius * angle.sin()]
+                })
+                .collect::<Vec<_>>();
 
-    let x = xyy[0];
-    let y = xyy[1];
-
-    let poly = Polygon::new(
-        "Target Box",
-        vec![
-            [x + TARGET_BOX_LENGTH, y - TARGET_BOX_LENGTH],
-            [x - TARGET_BOX_LENGTH, y - TARGET_BOX_LENGTH],
-            [x - TARGET_BOX_LENGTH, y + TARGET_BOX_LENGTH],
-            [x + TARGET_BOX_LENGTH, y + TARGET_BOX_LENGTH],
-        ],
-    );
+            Polygon::new("Target Tolerance", circle)
+        }
+        None => Polygon::new(
+            "Target Box",
+            vec![
+                [x + TARGET_BOX_LENGTH, y - TARGET_BOX_LENGTH],
+                [x - TARGET_BOX_LENGTH, y - TARGET_BOX_LENGTH],
+                [x - TARGET_BOX_LENGTH, y + TARGET_BOX_LENGTH],
+                [x + TARGET_BOX_LENGTH, y + TARGET_BOX_LENGTH],
+            ],
+        ),
+    };
 
     ([x, y], poly)
 }
+
+impl CieDiagram {
+    pub fn coords_from_xy(&self, xy: [f64; 2]) -> [f64; 2] {
+        match self {
+            Self::Xy => xy,
+            Self::Uv => xy_to_uv(xy),
+        }
+    }
+
+    pub fn xy_from_coords(&self, coords: [f64; 2]) -> [f64; 2] {
+        match self {
+            Self::Xy => coords,
+            Self::Uv => {
+                let [u, v] = coords;
+                let denom = 6.0 * u - 16.0 * v + 12.0;
+
+                [9.0 * u / denom, 4.0 * v / denom]
+            }
+        }
+    }
+
+    pub const fn axes_label(&self) -> &'static str {
+        match self {
+            Self::Xy => "xy",
+            Self::Uv => "u'v'",
+        }
+    }
+
+    pub const fn texture_name(&self) -> &'static str {
+        match self {
+            Self::Xy => "cie_xy_diagram_tex",
+            Self::Uv => "cie_uv_diagram_tex",
+        }
+    }
+
+    // Top left and bottom right of the spectral locus
+    const fn bounds(&self) -> (Vec2, Vec2) {
+        match self {
+            Self::Xy => (XY_TOP_LEFT, XY_BOTTOM_RIGHT),
+            Self::Uv => (UV_TOP_LEFT, UV_BOTTOM_RIGHT),
+        }
+    }
+}
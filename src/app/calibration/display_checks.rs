@@ -0,0 +1,110 @@
+use eframe::{
+    egui::{self, Layout, Ui},
+    emath::Align,
+};
+
+use crate::{
+    app::PGenApp,
+    calibration::{DisplayCheckStep, DisplayChecks},
+};
+
+pub fn draw_display_checks_ui(app: &mut PGenApp, ui: &mut Ui) {
+    let pgen_connected = app.state.connected_state.connected;
+    let started = app.cal_state.display_checks.started;
+
+    let can_start = pgen_connected
+        && app.cal_state.spotread_started
+        && !app.processing
+        && !app.cal_state.measurement_in_progress();
+
+    ui.horizontal(|ui| {
+        ui.heading("Additivity & contrast");
+
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            if started {
+                ui.add_enabled_ui(!app.cal_state.display_checks.stop_requested, |ui| {
+                    if ui.button("Stop").clicked() {
+                        app.cal_state.display_checks.stop_requested = true;
+                    }
+                });
+            } else {
+                ui.add_enabled_ui(can_start, |ui| {
+                    if ui.button("Measure").clicked() {
+                        let pattern_cfg = app.state.pattern_config;
+                        app.cal_state.display_checks.start(pattern_cfg);
+                        app.calibration_send_measure_display_check_step();
+                    }
+                });
+            }
+
+            if let Some(step) = app.cal_state.display_checks.next_step().filter(|_| started) {
+                ui.label(format!("Measuring {step}"));
+            }
+        });
+    });
+
+    let display_checks = &app.cal_state.display_checks;
+    if !display_checks.results.is_empty() {
+        ui.indent("cal_display_checks_indent", |ui| {
+            draw_results_grid(ui, display_checks);
+        });
+    }
+}
+
+fn draw_results_grid(ui: &mut Ui, display_checks: &DisplayChecks) {
+    egui::Grid::new("cal_display_checks_grid")
+        .spacing([16.0, 4.0])
+        .striped(true)
+        .show(ui, |ui| {
+            if let Some(additivity) = display_checks.additivity() {
+                let error = additivity.xyz_error() * 100.0;
+
+                ui.label("Additivity error (R+G+B vs W)");
+                ui.label(format!("X {:+.2} %", error.x));
+                ui.label(format!("Y {:+.2} %", error.y));
+                ui.label(format!("Z {:+.2} %", error.z));
+                ui.end_row();
+            }
+
+            let contrasts = [
+                (
+                    "Full field contrast",
+                    display_checks.full_field_contrast(),
+                    DisplayCheckStep::FullFieldWhite,
+                    DisplayCheckStep::FullFieldBlack,
+                ),
+                (
+                    "Window contrast",
+                    display_checks.window_contrast(),
+                    DisplayCheckStep::WindowWhite,
+                    DisplayCheckStep::WindowBlack,
+                ),
+            ];
+            for (label, contrast, white, black) in contrasts {
+                let Some(contrast) = contrast else {
+                    continue;
+                };
+                let luminance = |step: DisplayCheckStep| {
+                    display_checks
+                        .results
+                        .iter()
+                        .find(|(s, _)| *s == step)
+                        .map_or(0.0, |(_, res)| res.xyy[2])
+                };
+
+                ui.label(label);
+                ui.label(format_contrast(contrast));
+                ui.label(format!("White {:.4} nits", luminance(white)));
+                ui.label(format!("Black {:.6} nits", luminance(black)));
+                ui.end_row();
+            }
+        });
+}
+
+fn format_contrast(contrast: f64) -> String {
+    if contrast.is_finite() {
+        format!("{contrast:.0}:1")
+    } else {
+        "∞:1".to_string()
+    }
+}
@@ -0,0 +1,218 @@
+use std::time::Instant;
+
+use eframe::{
+    egui::{self, Layout, Ui},
+    emath::Align,
+    epaint::Color32,
+};
+use egui_plot::{HLine, Line, MarkerShape, Plot, Points};
+use itertools::Itertools;
+
+use crate::{app::PGenApp, calibration::DriftMonitor};
+
+pub fn draw_drift_monitor_ui(app: &mut PGenApp, ui: &mut Ui) {
+    let pgen_connected = app.state.connected_state.connected;
+    let started = app.cal_state.drift_monitor.started;
+
+    let can_start = pgen_connected
+        && app.cal_state.spotread_started
+        && !app.processing
+        && !app.cal_state.measurement_in_progress();
+
+    ui.horizontal(|ui| {
+        ui.heading("Warm-up drift");
+
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            if started {
+                if ui.button("Stop").clicked() {
+                    app.cal_state.drift_monitor.stop();
+                    if app.cal_state.drift_monitor.measuring.is_none() {
+                        app.set_blank();
+                    }
+                }
+            } else {
+                ui.add_enabled_ui(can_start, |ui| {
+                    if ui.button("Start").clicked() {
+                        app.cal_state.drift_monitor.start(Instant::now());
+                    }
+                });
+            }
+        });
+    });
+
+    let drift_monitor = &mut app.cal_state.drift_monitor;
+    ui.indent("cal_drift_indent", |ui| {
+        ui.add_enabled_ui(!started, |ui| {
+            draw_monitor_settings(ui, drift_monitor);
+        });
+        draw_sweep_check_settings(ui, drift_monitor);
+
+        if !drift_monitor.flagged_patches.is_empty() {
+            let flagged = drift_monitor.flagged_patches.iter().join(", ");
+            ui.label(format!("Patches measured while drifting: {flagged}"));
+        }
+
+        if !drift_monitor.samples.is_empty() {
+            draw_status(ui, drift_monitor);
+        }
+    });
+
+    if !drift_monitor.samples.is_empty() {
+        draw_drift_plots(ui, drift_monitor);
+    }
+}
+
+fn draw_monitor_settings(ui: &mut Ui, drift_monitor: &mut DriftMonitor) {
+    egui::Grid::new("cal_drift_settings_grid")
+        .spacing([8.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Level");
+            ui.add(
+                egui::DragValue::new(&mut drift_monitor.level)
+                    .speed(0.01)
+                    .range(0.0..=1.0)
+                    .max_decimals(3),
+            );
+            ui.label("Every");
+            ui.add(
+                egui::DragValue::new(&mut drift_monitor.interval_secs)
+                    .range(5..=3600)
+                    .suffix(" s"),
+            );
+            ui.label("For");
+            ui.add(
+                egui::DragValue::new(&mut drift_monitor.duration_mins)
+                    .range(1..=600)
+                    .suffix(" min"),
+            );
+            ui.end_row();
+
+            ui.label("Stable within");
+            percent_drag_value(ui, &mut drift_monitor.stable_y_tolerance);
+            ui.label("Δu'v'");
+            ui.add(
+                egui::DragValue::new(&mut drift_monitor.stable_uv_tolerance)
+                    .speed(0.0001)
+                    .range(0.0..=0.05)
+                    .max_decimals(4),
+            );
+            ui.label("For");
+            ui.add(
+                egui::DragValue::new(&mut drift_monitor.stable_mins)
+                    .speed(0.5)
+                    .range(0.0..=120.0)
+                    .suffix(" min"),
+            );
+            ui.end_row();
+        });
+}
+
+fn draw_sweep_check_settings(ui: &mut Ui, drift_monitor: &mut DriftMonitor) {
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut drift_monitor.sweep_checks,
+            "Re-measure during patch sweeps, every",
+        );
+        ui.add_enabled_ui(drift_monitor.sweep_checks, |ui| {
+            ui.add(
+                egui::DragValue::new(&mut drift_monitor.sweep_check_patches)
+                    .range(1..=1000)
+                    .suffix(" patches"),
+            );
+            ui.label("Max drift");
+            percent_drag_value(ui, &mut drift_monitor.sweep_max_drift);
+        });
+    });
+}
+
+fn draw_status(ui: &mut Ui, drift_monitor: &DriftMonitor) {
+    let count = drift_monitor.samples.len();
+    let stabilized = match drift_monitor.stabilized_at() {
+        Some(minutes) => format!("Stabilized after {minutes:.1} min"),
+        None => "Not stabilized yet".to_string(),
+    };
+
+    let next_reading = drift_monitor
+        .time_until_next_reading(Instant::now())
+        .map(|wait| format!(", next reading in {} s", wait.as_secs()))
+        .unwrap_or_default();
+
+    ui.label(format!("{count} readings{next_reading}. {stabilized}"));
+}
+
+fn draw_drift_plots(ui: &mut Ui, drift_monitor: &DriftMonitor) {
+    let dark_mode = ui.global_style().visuals.dark_mode;
+    let (lum_colour, uv_colour) = if dark_mode {
+        (Color32::YELLOW, Color32::LIGHT_BLUE)
+    } else {
+        (Color32::from_rgb(255, 153, 0), Color32::DARK_BLUE)
+    };
+    let tolerance_colour = Color32::GRAY;
+
+    let (y_points, uv_points) = drift_monitor.drift_points();
+    let y_tolerance = drift_monitor.stable_y_tolerance * 100.0;
+    let uv_tolerance = drift_monitor.stable_uv_tolerance;
+
+    ui.columns(2, |columns| {
+        columns[0].label("Luminance drift %");
+        Plot::new("drift_luminance_plot")
+            .view_aspect(1.5)
+            .allow_scroll(false)
+            .show_background(false)
+            .x_axis_label("Minutes")
+            .show(&mut columns[0], |plot_ui| {
+                for tolerance in [y_tolerance, -y_tolerance] {
+                    plot_ui.hline(
+                        HLine::new("Tolerance", tolerance)
+                            .color(tolerance_colour)
+                            .style(egui_plot::LineStyle::Dashed { length: 6.0 }),
+                    );
+                }
+                plot_ui.line(Line::new("ΔY %", y_points.clone()).color(lum_colour));
+                plot_ui.points(
+                    Points::new("ΔY % points", y_points)
+                        .shape(MarkerShape::Circle)
+                        .radius(2.0)
+                        .color(lum_colour),
+                );
+            });
+
+        columns[1].label("Chromaticity drift Δu'v'");
+        Plot::new("drift_uv_plot")
+            .view_aspect(1.5)
+            .allow_scroll(false)
+            .show_background(false)
+            .x_axis_label("Minutes")
+            .include_y(0.0)
+            .show(&mut columns[1], |plot_ui| {
+                plot_ui.hline(
+                    HLine::new("Tolerance", uv_tolerance)
+                        .color(tolerance_colour)
+                        .style(egui_plot::LineStyle::Dashed { length: 6.0 }),
+                );
+                plot_ui.line(Line::new("Δu'v'", uv_points.clone()).color(uv_colour));
+                plot_ui.points(
+                    Points::new("Δu'v' points", uv_points)
+                        .shape(MarkerShape::Circle)
+                        .radius(2.0)
+                        .color(uv_colour),
+                );
+            });
+    });
+}
+
+// Edits a fraction as percent
+fn percent_drag_value(ui: &mut Ui, fraction: &mut f64) {
+    let mut percent = *fraction * 100.0;
+    let res = ui.add(
+        egui::DragValue::new(&mut percent)
+            .speed(0.05)
+            .range(0.0..=50.0)
+            .max_decimals(2)
+            .suffix(" %"),
+    );
+
+    if res.changed() {
+        *fraction = percent / 100.0;
+    }
+}
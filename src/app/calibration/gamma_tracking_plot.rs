@@ -43,7 +43,6 @@ pub fn draw_gamma_tracking_plot(
 }
 
 fn draw_plot(ui: &mut Ui, results: &[ReadingResult], cal_state: &CalibrationState) {
-    let min = cal_state.min_normalized();
     let target_eotf = cal_state.eotf;
 
     let dark_mode = ui.global_style().visuals.dark_mode;
@@ -64,52 +63,26 @@ fn draw_plot(ui: &mut Ui, results: &[ReadingResult], cal_state: &CalibrationStat
     };
 
     let is_pq = target_eotf == LuminanceEotf::PQ;
-    let max_pq = is_pq.then(|| target_eotf.oetf(cal_state.max_hdr_mdl / 10_000.0));
     let ref_pq_line = is_pq.then(|| {
         Line::new("Ref PQ line", vec![[0.0, 0.0], [1.0, 1.0]])
             .color(ref_pq_color)
             .style(egui_plot::LineStyle::Dashed { length: 10.0 })
     });
 
-    let precision: u32 = 8;
-    let max = 2_u32.pow(precision);
-    let max_f = max as f64;
-    let ref_points: Vec<[f64; 2]> = (0..max)
-        .filter_map(|i| {
-            let x = i as f64 / max_f;
-            if x > 0.01 {
-                let y = if let Some(max_pq) = max_pq {
-                    x.min(max_pq)
-                } else {
-                    let v_out = target_eotf.value_bpc(min, x, false, false);
-                    target_eotf.gamma_around_zero(x, v_out)
-                };
-
-                Some([x, y])
-            } else {
-                None
-            }
-        })
-        .collect();
+    let ref_points = gamma_ref_points(cal_state);
 
     let ref_line = Line::new("Ref line", ref_points)
         .color(ref_color)
         .highlight(true);
 
-    let lum_points: Vec<[f64; 2]> = results
-        .iter()
-        .filter(|res| res.is_white_stimulus_reading() && res.not_zero_or_one_rgb())
-        .map(|res| {
-            let x = res.target.ref_rgb[0];
-            let y = if is_pq {
-                target_eotf.oetf(res.xyz.y / 10_000.0)
-            } else {
-                res.gamma_around_zero().unwrap()
-            };
+    let lum_points = gamma_measured_points(results, target_eotf);
 
-            [x, y]
-        })
-        .collect();
+    let comparison_points = gamma_measured_points(&cal_state.comparison_results, target_eotf);
+    let comparison_line = (!comparison_points.is_empty()).then(|| {
+        Line::new("Lum line (before)", comparison_points)
+            .color(lum_color.gamma_multiply(0.6))
+            .style(egui_plot::LineStyle::Dashed { length: 6.0 })
+    });
 
     let lum_line = Line::new("Lum line", lum_points.clone())
         .color(lum_color)
@@ -150,7 +123,63 @@ fn draw_plot(ui: &mut Ui, results: &[ReadingResult], cal_state: &CalibrationStat
 
         plot_ui.line(ref_line);
 
+        if let Some(comparison_line) = comparison_line {
+            plot_ui.line(comparison_line);
+        }
+
         plot_ui.line(lum_line);
         plot_ui.points(lum_markers);
     });
 }
+
+// Gamma around the EOTF mean, or PQ code for PQ
+pub(super) fn gamma_ref_points(cal_state: &CalibrationState) -> Vec<[f64; 2]> {
+    let min = cal_state.min_normalized();
+    let target_eotf = cal_state.eotf;
+
+    let is_pq = target_eotf == LuminanceEotf::PQ;
+    let max_pq = is_pq.then(|| target_eotf.oetf(cal_state.max_hdr_mdl / 10_000.0));
+
+    let precision: u32 = 8;
+    let max = 2_u32.pow(precision);
+    let max_f = max as f64;
+    (0..max)
+        .filter_map(|i| {
+            let x = i as f64 / max_f;
+            if x > 0.01 {
+                let y = if let Some(max_pq) = max_pq {
+                    x.min(max_pq)
+                } else {
+                    let v_out = target_eotf.value_bpc(min, x, false, false);
+                    target_eotf.gamma_around_zero(x, v_out)
+                };
+
+                Some([x, y])
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+pub(super) fn gamma_measured_points(
+    results: &[ReadingResult],
+    target_eotf: LuminanceEotf,
+) -> Vec<[f64; 2]> {
+    let is_pq = target_eotf == LuminanceEotf::PQ;
+
+    results
+        .iter()
+        .filter(|res| res.is_white_stimulus_reading() && res.not_zero_or_one_rgb())
+        .map(|res| {
+            let x = res.target.ref_rgb[0];
+            let y = if is_pq {
+                target_eotf.oetf(res.xyz.y / 10_000.0)
+            } else {
+                res.gamma_around_zero().unwrap()
+            };
+
+            [x, y]
+        })
+        .collect()
+}
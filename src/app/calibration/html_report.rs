@@ -0,0 +1,804 @@
+use std::fmt::Write;
+
+use eframe::{egui::Ui, epaint::Color32};
+use kolor_64::{ColorConversion, Vec3};
+use tokio::sync::mpsc::Sender;
+
+use crate::{
+    app::SaveFileType,
+    calibration::{LuminanceEotf, ReadingResult},
+    external::ExternalJobCmd,
+    pgen::{BitDepth, DynamicRange, controller::PGenInfo},
+    utils::{normalize_float_rgb_components, rgb_10b_to_8b},
+};
+
+use super::{
+    CalibrationState, CieDiagram,
+    cie_diagram_plot::spectral_locus_coords,
+    gamma_tracking_plot::{gamma_measured_points, gamma_ref_points},
+    luminance_plot::{luminance_measured_points, luminance_ref_points},
+    rgb_balance_plot::rgb_balance_points,
+};
+
+const PLOT_WIDTH: f64 = 720.0;
+const PLOT_HEIGHT: f64 = 360.0;
+const PLOT_MARGIN: f64 = 48.0;
+
+const REPORT_STYLE: &str = "body{font-family:sans-serif;margin:2em auto;max-width:960px;color:#222}\
+h1,h2{border-bottom:1px solid #ccc;padding-bottom:4px}\
+table{border-collapse:collapse;margin:8px 0}\
+td,th{border:1px solid #ccc;padding:3px 8px;text-align:right}\
+th{background:#f0f0f0}\
+td.label{text-align:left}\
+tr.fail td{background:#fdd}\
+.pass{color:#080;font-weight:bold}\
+.fail{color:#c00;font-weight:bold}\
+.swatch{width:32px;border:1px solid #000}\
+svg{display:block;margin:8px 0}";
+
+pub fn draw_html_report_export_ui(
+    ui: &mut Ui,
+    cal_state: &CalibrationState,
+    pgen_info: Option<&PGenInfo>,
+    pattern_bit_depth: BitDepth,
+    external_tx: &Sender<ExternalJobCmd>,
+) {
+    if ui.button("Export HTML report").clicked() {
+        let report = build_html_report(cal_state, pgen_info, pattern_bit_depth);
+        let cmd = ExternalJobCmd::SaveFile(SaveFileType::HtmlReport, report);
+        external_tx.try_send(cmd).ok();
+    }
+}
+
+/// Patch swatches are drawn from the code values, like the patch list
+pub fn build_html_report(
+    cal_state: &CalibrationState,
+    pgen_info: Option<&PGenInfo>,
+    pattern_bit_depth: BitDepth,
+) -> String {
+    let results = cal_state.internal_gen.results();
+
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<title>Calibration report</title>\n");
+    writeln!(out, "<style>{REPORT_STYLE}</style>").ok();
+    out.push_str("</head>\n<body>\n");
+    writeln!(
+        out,
+        "<h1>Calibration report</h1>\n<p>Generated by {} {}</p>",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )
+    .ok();
+
+    write_device_section(&mut out, pgen_info);
+    write_target_section(&mut out, cal_state);
+
+    if !results.is_empty() {
+        write_summary_section(&mut out, cal_state, &results);
+        write_plots_section(&mut out, cal_state, &results);
+        write_patches_section(&mut out, cal_state, pattern_bit_depth);
+    }
+
+    out.push_str("</body>\n</html>\n");
+
+    out
+}
+
+fn write_device_section(out: &mut String, pgen_info: Option<&PGenInfo>) {
+    out.push_str("<h2>Device</h2>\n");
+
+    let Some(info) = pgen_info else {
+        out.push_str("<p>PGenerator not connected</p>\n");
+        return;
+    };
+
+    let cfg = &info.output_config;
+    let mut rows = vec![
+        ("PGenerator version", escape_html(&info.version)),
+        ("Display mode", info.current_display_mode.to_string()),
+        ("Color format", cfg.format.to_string()),
+        ("Bit depth", cfg.bit_depth.to_string()),
+        ("Quantization range", cfg.quant_range.to_string()),
+        ("Colorimetry", cfg.colorimetry.to_string()),
+        ("Dynamic range", cfg.dynamic_range.to_string()),
+    ];
+
+    match cfg.dynamic_range {
+        DynamicRange::Hdr => {
+            let meta = &cfg.hdr_meta;
+            rows.extend([
+                ("HDR EOTF", meta.eotf.to_string()),
+                ("HDR primaries", meta.primaries.to_string()),
+                ("Max MDL", format!("{} nits", meta.max_mdl)),
+                (
+                    "Min MDL",
+                    format!("{} nits", meta.min_mdl as f64 / 10_000.0),
+                ),
+                ("MaxCLL", format!("{} nits", meta.maxcll)),
+                ("MaxFALL", format!("{} nits", meta.maxfall)),
+            ]);
+        }
+        DynamicRange::Dovi => {
+            rows.push(("DoVi map mode", cfg.dovi_map_mode.to_string()));
+        }
+        DynamicRange::Sdr => {}
+    }
+
+    write_key_value_table(out, &rows);
+}
+
+fn write_target_section(out: &mut String, cal_state: &CalibrationState) {
+    out.push_str("<h2>Target</h2>\n");
+
+    let mut rows = vec![
+        ("Colorspace", cal_state.target_csp.to_string()),
+        ("EOTF", cal_state.eotf.to_string()),
+    ];
+    if cal_state.eotf == LuminanceEotf::PQ {
+        rows.push(("Max HDR MDL", format!("{:.2} nits", cal_state.max_hdr_mdl)));
+    } else {
+        rows.push(("Min Y", format!("{:.6} nits", cal_state.min_y)));
+        rows.push(("Max Y", format!("{:.6} nits", cal_state.max_y)));
+    }
+
+    write_key_value_table(out, &rows);
+}
+
+fn write_summary_section(
+    out: &mut String,
+    cal_state: &CalibrationState,
+    results: &[ReadingResult],
+) {
+    let target_rgb_to_xyz = cal_state.target_rgb_to_xyz_conv();
+
+    out.push_str("<h2>Summary</h2>\n");
+
+    let mut rows = Vec::new();
+    if let Some((min_y, max_y)) = cal_state.internal_gen.minmax_y() {
+        rows.push(("Y Min", format!("{min_y:.6} nits")));
+        rows.push(("Y Max", format!("{max_y:.6} nits")));
+    }
+    rows.push((
+        "Average dE2000",
+        format!(
+            "{:.4}",
+            ReadingResult::results_average_delta_e2000(results, target_rgb_to_xyz)
+        ),
+    ));
+    rows.push((
+        "Average dE2000 w/ lum",
+        format!(
+            "{:.4}",
+            ReadingResult::results_average_delta_e2000_incl_luminance(results, target_rgb_to_xyz)
+        ),
+    ));
+    let avg_gamma = ReadingResult::results_average_gamma(results)
+        .map_or_else(|| "N/A".to_string(), |gamma| format!("{gamma:.4}"));
+    rows.push(("Average gamma", avg_gamma));
+
+    write_key_value_table(out, &rows);
+    write_tolerance_grade(out, cal_state);
+
+    let Some(report) = cal_state.gamut_report.report() else {
+        return;
+    };
+
+    out.push_str("<table>\n<tr><th>Gamut coverage</th><th>xy</th><th>u'v'</th></tr>\n");
+    for coverage in report.coverage.iter() {
+        writeln!(
+            out,
+            "<tr><td class=\"label\">{}</td><td>{:.2} %</td><td>{:.2} %</td></tr>",
+            coverage.colorspace,
+            coverage.xy * 100.0,
+            coverage.uv * 100.0
+        )
+        .ok();
+    }
+    writeln!(
+        out,
+        "<tr><td class=\"label\">Area vs {}</td><td>{:.2} %</td><td>{:.2} %</td></tr>",
+        cal_state.target_csp,
+        report.relative_area_xy * 100.0,
+        report.relative_area_uv * 100.0
+    )
+    .ok();
+    out.push_str("</table>\n");
+
+    if let Some(volume) = report.volume {
+        writeln!(
+            out,
+            "<p>Gamut volume (CIELAB): {:.0} / {:.0} ({:.2} %)</p>",
+            volume.measured,
+            volume.target,
+            volume.relative() * 100.0
+        )
+        .ok();
+    }
+}
+
+fn write_tolerance_grade(out: &mut String, cal_state: &CalibrationState) {
+    let (Some(profile), Some(grade)) = (
+        cal_state.selected_tolerance_profile(),
+        cal_state.tolerance_grade.grade(),
+    ) else {
+        return;
+    };
+
+    let verdict = if grade.passed() {
+        "<span class=\"pass\">PASS</span>"
+    } else {
+        "<span class=\"fail\">FAIL</span>"
+    };
+    writeln!(
+        out,
+        "<p>Tolerance profile {}: {verdict}</p>",
+        escape_html(&profile.name)
+    )
+    .ok();
+
+    if grade.passed() {
+        return;
+    }
+
+    out.push_str("<ul>\n");
+    for failure in grade.failures.iter() {
+        writeln!(out, "<li>{}</li>", escape_html(failure)).ok();
+    }
+    for (i, reasons) in grade.failing_patches.iter() {
+        writeln!(
+            out,
+            "<li>Patch {i}: {}</li>",
+            escape_html(&reasons.join(", "))
+        )
+        .ok();
+    }
+    out.push_str("</ul>\n");
+}
+
+fn write_plots_section(out: &mut String, cal_state: &CalibrationState, results: &[ReadingResult]) {
+    out.push_str("<h2>RGB Balance</h2>\n");
+    out.push_str(&rgb_balance_svg(results));
+
+    out.push_str("<h2>Gamma</h2>\n");
+    out.push_str(&gamma_svg(cal_state, results));
+
+    out.push_str("<h2>Luminance</h2>\n");
+    out.push_str(&luminance_svg(cal_state, results));
+
+    out.push_str("<h2>Chromaticity</h2>\n");
+    out.push_str(&cie_diagram_svg(cal_state, results));
+}
+
+fn write_patches_section(
+    out: &mut String,
+    cal_state: &CalibrationState,
+    pattern_bit_depth: BitDepth,
+) {
+    let target_rgb_to_xyz = cal_state.target_rgb_to_xyz_conv();
+    let tolerance_grade = cal_state.tolerance_grade.grade();
+
+    out.push_str("<h2>Patches</h2>\n<table>\n<tr>");
+    for header in [
+        "#",
+        "Patch",
+        "Red",
+        "Green",
+        "Blue",
+        "Target x",
+        "Target y",
+        "Target Y",
+        "x",
+        "y",
+        "Y",
+        "CCT",
+        "dE2000",
+        "dE2000 w/ lum",
+    ] {
+        write!(out, "<th>{header}</th>").ok();
+    }
+    out.push_str("</tr>\n");
+
+    let patches = cal_state.internal_gen.list.iter().enumerate();
+    for (i, patch, res) in patches.filter_map(|(i, p)| p.result.map(|res| (i, p, res))) {
+        let target_xyy = res.ref_xyy_display_space(target_rgb_to_xyz);
+        let [r, g, b] = rgb_10b_to_8b(pattern_bit_depth as u8, patch.rgb);
+        let swatch = svg_colour(Color32::from_rgb(r, g, b));
+
+        let failures = tolerance_grade.and_then(|grade| grade.patch_failures(i));
+        match failures {
+            Some(failures) => write!(
+                out,
+                "<tr class=\"fail\" title=\"{}\">",
+                escape_html(&failures.join(", "))
+            ),
+            None => write!(out, "<tr>"),
+        }
+        .ok();
+        write!(
+            out,
+            "<td>{i}</td><td class=\"swatch\" style=\"background:{swatch}\"></td>"
+        )
+        .ok();
+        for c in patch.rgb {
+            write!(out, "<td>{c}</td>").ok();
+        }
+        write!(
+            out,
+            "<td>{:.4}</td><td>{:.4}</td><td>{:.4}</td>",
+            target_xyy[0], target_xyy[1], target_xyy[2]
+        )
+        .ok();
+        write!(
+            out,
+            "<td>{:.4}</td><td>{:.4}</td><td>{:.4}</td>",
+            res.xyy[0], res.xyy[1], res.xyy[2]
+        )
+        .ok();
+        writeln!(
+            out,
+            "<td>{:.0} K</td><td>{:.4}</td><td>{:.4}</td></tr>",
+            res.cct,
+            res.delta_e2000(target_rgb_to_xyz).value(),
+            res.delta_e2000_incl_luminance(target_rgb_to_xyz).value()
+        )
+        .ok();
+    }
+
+    out.push_str("</table>\n");
+}
+
+fn write_key_value_table(out: &mut String, rows: &[(&str, String)]) {
+    out.push_str("<table>\n");
+    for (key, value) in rows {
+        writeln!(
+            out,
+            "<tr><th class=\"label\">{key}</th><td class=\"label\">{value}</td></tr>"
+        )
+        .ok();
+    }
+    out.push_str("</table>\n");
+}
+
+fn rgb_balance_svg(results: &[ReadingResult]) -> String {
+    let rgb_points = rgb_balance_points(results);
+    let channel_points =
+        |c: usize| -> Vec<[f64; 2]> { rgb_points.iter().map(|(x, rgb)| [*x, rgb[c]]).collect() };
+
+    let (y_min, y_max) = rgb_points
+        .iter()
+        .flat_map(|(_, rgb)| rgb.to_array())
+        .fold((-0.1_f64, 0.1_f64), |(min, max), v| {
+            (min.min(v), max.max(v))
+        });
+
+    let mut plot = SvgPlot::new((0.0, 1.0), (y_min, y_max));
+    plot.line(&[[0.0, 0.0], [1.0, 0.0]], Color32::BLACK, false);
+    for (c, colour) in [Color32::RED, Color32::from_rgb(0, 204, 0), Color32::BLUE]
+        .into_iter()
+        .enumerate()
+    {
+        let points = channel_points(c);
+        plot.line(&points, colour, false);
+        plot.points(&points, colour, 3.0);
+    }
+
+    plot.finish()
+}
+
+fn gamma_svg(cal_state: &CalibrationState, results: &[ReadingResult]) -> String {
+    let target_eotf = cal_state.eotf;
+    let is_pq = target_eotf == LuminanceEotf::PQ;
+
+    // The plot points are around the mean gamma
+    let offset = if is_pq { 0.0 } else { target_eotf.mean() };
+    let with_offset = |points: Vec<[f64; 2]>| -> Vec<[f64; 2]> {
+        points.into_iter().map(|[x, y]| [x, y + offset]).collect()
+    };
+
+    let ref_points = with_offset(gamma_ref_points(cal_state));
+    let lum_points = with_offset(gamma_measured_points(results, target_eotf));
+
+    let y_range = if is_pq {
+        (0.0, 1.0)
+    } else {
+        ref_points
+            .iter()
+            .chain(lum_points.iter())
+            .fold((offset - 0.1, offset + 0.1), |(min, max), p| {
+                (min.min(p[1]), max.max(p[1]))
+            })
+    };
+
+    let lum_colour = Color32::from_rgb(255, 153, 0);
+    let mut plot = SvgPlot::new((0.0, 1.0), y_range);
+    if is_pq {
+        plot.line(&[[0.0, 0.0], [1.0, 1.0]], Color32::DARK_GRAY, true);
+    }
+    plot.line(&ref_points, Color32::from_rgb(0, 179, 179), false);
+    plot.line(&lum_points, lum_colour, false);
+    plot.points(&lum_points, lum_colour, 3.0);
+
+    plot.finish()
+}
+
+fn luminance_svg(cal_state: &CalibrationState, results: &[ReadingResult]) -> String {
+    let ref_points = luminance_ref_points(cal_state, cal_state.min_normalized());
+    let lum_points = luminance_measured_points(results, cal_state.oetf);
+
+    let y_max = lum_points.iter().fold(1.0_f64, |max, p| max.max(p[1]));
+
+    let lum_colour = Color32::from_rgb(255, 153, 0);
+    let mut plot = SvgPlot::new((0.0, 1.0), (0.0, y_max));
+    plot.line(&ref_points, Color32::DARK_GRAY, true);
+    plot.line(&lum_points, lum_colour, false);
+    plot.points(&lum_points, lum_colour, 3.0);
+
+    plot.finish()
+}
+
+fn cie_diagram_svg(cal_state: &CalibrationState, results: &[ReadingResult]) -> String {
+    let kind = cal_state.cie_diagram;
+    let target_rgb_to_xyz = cal_state.target_rgb_to_xyz_conv();
+
+    let y_max = match kind {
+        CieDiagram::Xy => 0.9,
+        CieDiagram::Uv => 0.6,
+    };
+    let mut plot = SvgPlot::new_with_aspect((0.0, 0.8), (0.0, y_max));
+
+    plot.polygon(
+        &spectral_locus_coords(kind),
+        Color32::GRAY,
+        Some(Color32::from_gray(240)),
+    );
+
+    let target_primaries: Vec<_> = cal_state
+        .target_csp
+        .to_kolor()
+        .primaries()
+        .values()
+        .map(|xy| kind.coords_from_xy(xy))
+        .to_vec();
+    plot.polygon(&target_primaries, Color32::DARK_GRAY, None);
+
+    if let Some(report) = cal_state.gamut_report.report() {
+        let measured: Vec<_> = report
+            .measured_xy
+            .iter()
+            .map(|xy| kind.coords_from_xy(*xy))
+            .collect();
+        plot.polygon(&measured, Color32::from_rgb(204, 102, 0), None);
+    }
+
+    for res in results {
+        let target = target_coords(res, target_rgb_to_xyz, kind);
+        plot.cross(target, Color32::BLACK, 5.0);
+    }
+
+    let target_eotf = cal_state.eotf;
+    for res in results {
+        let coords = kind.coords_from_xy([res.xyy[0], res.xyy[1]]);
+        let rgb_gamma = normalize_float_rgb_components(target_eotf.convert_vec(res.rgb, true));
+
+        plot.points(&[coords], Color32::GRAY, 5.0);
+        plot.points(&[coords], rgb_to_colour32(rgb_gamma), 3.5);
+    }
+
+    plot.finish()
+}
+
+fn target_coords(
+    res: &ReadingResult,
+    target_rgb_to_xyz: ColorConversion,
+    kind: CieDiagram,
+) -> [f64; 2] {
+    let target_xyy = res.ref_xyy_display_space(target_rgb_to_xyz);
+    kind.coords_from_xy([target_xyy[0], target_xyy[1]])
+}
+
+fn rgb_to_colour32(rgb: Vec3) -> Color32 {
+    let rgb = (rgb.clamp(Vec3::ZERO, Vec3::ONE) * 255.0)
+        .round()
+        .to_array()
+        .map(|c| c as u8);
+
+    Color32::from_rgb(rgb[0], rgb[1], rgb[2])
+}
+
+fn svg_colour(colour: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", colour.r(), colour.g(), colour.b())
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Picks a 1, 2 or 5 multiple for about 8 grid lines
+fn grid_step(range: f64) -> f64 {
+    let raw = range / 8.0;
+    let magnitude = 10_f64.powf(raw.log10().floor());
+
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|step| *step >= raw)
+        .unwrap_or(magnitude * 10.0)
+}
+
+/// Minimal line/scatter plot rendered to an inline SVG
+struct SvgPlot {
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+    width: f64,
+    height: f64,
+
+    body: String,
+}
+
+impl SvgPlot {
+    fn new(x_range: (f64, f64), y_range: (f64, f64)) -> Self {
+        Self::with_size(x_range, y_range, PLOT_WIDTH, PLOT_HEIGHT)
+    }
+
+    // Same scale on both axes
+    fn new_with_aspect(x_range: (f64, f64), y_range: (f64, f64)) -> Self {
+        let inner_width = PLOT_WIDTH - PLOT_MARGIN * 2.0;
+        let aspect = (y_range.1 - y_range.0) / (x_range.1 - x_range.0);
+        let height = inner_width * aspect + PLOT_MARGIN * 2.0;
+
+        Self::with_size(x_range, y_range, PLOT_WIDTH, height)
+    }
+
+    fn with_size(x_range: (f64, f64), y_range: (f64, f64), width: f64, height: f64) -> Self {
+        let y_range = if y_range.1 > y_range.0 {
+            y_range
+        } else {
+            (y_range.0 - 0.5, y_range.0 + 0.5)
+        };
+
+        let mut plot = Self {
+            x_range,
+            y_range,
+            width,
+            height,
+            body: String::new(),
+        };
+        plot.draw_grid();
+
+        plot
+    }
+
+    fn map(&self, [x, y]: [f64; 2]) -> (f64, f64) {
+        let inner_w = self.width - PLOT_MARGIN * 2.0;
+        let inner_h = self.height - PLOT_MARGIN * 2.0;
+
+        let px = PLOT_MARGIN + (x - self.x_range.0) / (self.x_range.1 - self.x_range.0) * inner_w;
+        let py = PLOT_MARGIN + (self.y_range.1 - y) / (self.y_range.1 - self.y_range.0) * inner_h;
+
+        (px, py)
+    }
+
+    fn draw_grid(&mut self) {
+        let (x0, y0) = self.map([self.x_range.0, self.y_range.0]);
+        let (x1, y1) = self.map([self.x_range.1, self.y_range.1]);
+
+        let x_step = grid_step(self.x_range.1 - self.x_range.0);
+        let first = (self.x_range.0 / x_step).ceil() as i64;
+        let last = (self.x_range.1 / x_step).floor() as i64;
+        for i in first..=last {
+            let v = i as f64 * x_step;
+            let (px, _) = self.map([v, self.y_range.0]);
+            writeln!(
+                self.body,
+                "<line x1=\"{px:.1}\" y1=\"{y1:.1}\" x2=\"{px:.1}\" y2=\"{y0:.1}\" stroke=\"#ddd\"/>\
+                <text x=\"{px:.1}\" y=\"{:.1}\" font-size=\"11\" text-anchor=\"middle\">{}</text>",
+                y0 + 16.0,
+                format_tick(v, x_step)
+            )
+            .ok();
+        }
+
+        let y_step = grid_step(self.y_range.1 - self.y_range.0);
+        let first = (self.y_range.0 / y_step).ceil() as i64;
+        let last = (self.y_range.1 / y_step).floor() as i64;
+        for i in first..=last {
+            let v = i as f64 * y_step;
+            let (_, py) = self.map([self.x_range.0, v]);
+            writeln!(
+                self.body,
+                "<line x1=\"{x0:.1}\" y1=\"{py:.1}\" x2=\"{x1:.1}\" y2=\"{py:.1}\" stroke=\"#ddd\"/>\
+                <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"11\" text-anchor=\"end\">{}</text>",
+                x0 - 6.0,
+                py + 4.0,
+                format_tick(v, y_step)
+            )
+            .ok();
+        }
+
+        writeln!(
+            self.body,
+            "<rect x=\"{x0:.1}\" y=\"{y1:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"none\" stroke=\"#888\"/>",
+            x1 - x0,
+            y0 - y1
+        )
+        .ok();
+    }
+
+    fn path_data(&self, points: &[[f64; 2]]) -> String {
+        points
+            .iter()
+            .map(|p| {
+                let (x, y) = self.map(*p);
+                format!("{x:.2},{y:.2}")
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn line(&mut self, points: &[[f64; 2]], colour: Color32, dashed: bool) {
+        if points.len() < 2 {
+            return;
+        }
+
+        let dash = if dashed {
+            " stroke-dasharray=\"8,6\""
+        } else {
+            ""
+        };
+        writeln!(
+            self.body,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\"{dash}/>",
+            self.path_data(points),
+            svg_colour(colour)
+        )
+        .ok();
+    }
+
+    fn points(&mut self, points: &[[f64; 2]], colour: Color32, radius: f64) {
+        for p in points {
+            let (x, y) = self.map(*p);
+            writeln!(
+                self.body,
+                "<circle cx=\"{x:.2}\" cy=\"{y:.2}\" r=\"{radius}\" fill=\"{}\"/>",
+                svg_colour(colour)
+            )
+            .ok();
+        }
+    }
+
+    fn cross(&mut self, point: [f64; 2], colour: Color32, size: f64) {
+        let (x, y) = self.map(point);
+        writeln!(
+            self.body,
+            "<path d=\"M{:.2},{:.2}L{:.2},{:.2}M{:.2},{:.2}L{:.2},{:.2}\" stroke=\"{}\"/>",
+            x - size,
+            y - size,
+            x + size,
+            y + size,
+            x - size,
+            y + size,
+            x + size,
+            y - size,
+            svg_colour(colour)
+        )
+        .ok();
+    }
+
+    fn polygon(&mut self, points: &[[f64; 2]], stroke: Color32, fill: Option<Color32>) {
+        let fill = fill.map_or_else(|| "none".to_string(), svg_colour);
+        writeln!(
+            self.body,
+            "<polygon points=\"{}\" fill=\"{fill}\" stroke=\"{}\" stroke-width=\"1.5\"/>",
+            self.path_data(points),
+            svg_colour(stroke)
+        )
+        .ok();
+    }
+
+    fn finish(self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.0} {h:.0}\">\n{}</svg>\n",
+            self.body,
+            w = self.width,
+            h = self.height,
+        )
+    }
+}
+
+fn format_tick(v: f64, step: f64) -> String {
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    format!("{v:.decimals$}")
+}
+
+#[cfg(test)]
+mod tests {
+    use kolor_64::Vec3;
+
+    use crate::{
+        calibration::{CalibrationTarget, ReadingResult, ToleranceProfile},
+        generators::internal::InternalPattern,
+        pgen::{BitDepth, controller::PGenInfo},
+    };
+
+    use super::{CalibrationState, build_html_report};
+
+    #[test]
+    fn report_sections_and_escaping() {
+        let mut cal_state = CalibrationState {
+            tolerance_profiles: vec![ToleranceProfile {
+                name: "<Strict> & co".to_string(),
+                ..Default::default()
+            }],
+            tolerance_profile_idx: Some(0),
+            ..Default::default()
+        };
+        let target = CalibrationTarget {
+            min_y: cal_state.min_y,
+            max_y: cal_state.max_y,
+            colorspace: cal_state.target_csp,
+            eotf: cal_state.eotf,
+            max_hdr_mdl: cal_state.max_hdr_mdl,
+            ..Default::default()
+        };
+
+        // Greyscale and primaries, measured as the target
+        let stimuli = [
+            [0.0, 0.0, 0.0],
+            [0.5, 0.5, 0.5],
+            [1.0, 1.0, 1.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ];
+        cal_state.internal_gen.list = stimuli
+            .into_iter()
+            .map(|rgb| InternalPattern {
+                rgb: rgb.map(|c| (c * 1023.0) as u16),
+                target: None,
+                result: Some(ReadingResult::perfect(target, Vec3::from_array(rgb))),
+            })
+            .collect();
+
+        let results = cal_state.internal_gen.results();
+        cal_state.update_gamut_report(&results);
+        cal_state.update_tolerance_grade();
+
+        let pgen_info = PGenInfo {
+            version: "1.6 \"test\" <build>".to_string(),
+            ..Default::default()
+        };
+        let report = build_html_report(&cal_state, Some(&pgen_info), BitDepth::Ten);
+
+        for heading in [
+            "Device",
+            "Target",
+            "Summary",
+            "RGB Balance",
+            "Gamma",
+            "Luminance",
+            "Chromaticity",
+            "Patches",
+        ] {
+            assert!(report.contains(&format!("<h2>{heading}</h2>")), "{heading}");
+        }
+        assert_eq!(report.matches("<svg").count(), 4);
+        assert_eq!(report.matches("</svg>").count(), 4);
+        assert!(report.contains("Gamut coverage"));
+
+        assert!(report.contains("1.6 &quot;test&quot; &lt;build&gt;"));
+        assert!(report.contains(
+            "Tolerance profile &lt;Strict&gt; &amp; co: <span class=\"pass\">PASS</span>"
+        ));
+        assert!(!report.contains("<Strict>"));
+        assert!(!report.contains("<build>"));
+        // Mid grey swatch from its 511 code value
+        assert!(
+            report.contains("<td>1</td><td class=\"swatch\" style=\"background:#7f7f7f\"></td>")
+        );
+        assert!(report.ends_with("</html>\n"));
+    }
+}
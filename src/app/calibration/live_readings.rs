@@ -0,0 +1,101 @@
+use eframe::{
+    egui::{self, Layout, Ui},
+    emath::Align,
+    epaint::Color32,
+};
+use egui_plot::{Line, MarkerShape, Plot, Points};
+use strum::IntoEnumIterator;
+
+use crate::calibration::LiveMetric;
+
+use super::CalibrationState;
+
+pub fn draw_live_readings_ui(ui: &mut Ui, cal_state: &mut CalibrationState) {
+    ui.horizontal(|ui| {
+        ui.heading("Live readings");
+
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            if ui.button("Clear").clicked() {
+                cal_state.live_readings.clear();
+            }
+
+            ui.add(egui::DragValue::new(&mut cal_state.live_capacity).range(10..=3600));
+            ui.label("Samples");
+
+            egui::ComboBox::from_id_salt(egui::Id::new("cal_live_metric"))
+                .selected_text(cal_state.live_metric.as_ref())
+                .show_ui(ui, |ui| {
+                    for metric in LiveMetric::iter() {
+                        ui.selectable_value(&mut cal_state.live_metric, metric, metric.as_ref());
+                    }
+                });
+        });
+    });
+
+    let live_readings = &cal_state.live_readings;
+    let Some(latest) = live_readings.latest() else {
+        return;
+    };
+
+    ui.indent("cal_live_readings_indent", |ui| {
+        egui::Grid::new("cal_live_readings_grid")
+            .spacing([16.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for label in ["", "Latest", "Min", "Max", "Mean"] {
+                    ui.strong(label);
+                }
+                ui.end_row();
+
+                for metric in LiveMetric::iter() {
+                    let Some(stats) = live_readings.stats(metric) else {
+                        continue;
+                    };
+
+                    ui.label(metric.as_ref());
+                    for v in [latest.value(metric), stats.min, stats.max, stats.mean] {
+                        ui.label(format_metric_value(metric, v));
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+
+    let dark_mode = ui.global_style().visuals.dark_mode;
+    let colour = if dark_mode {
+        Color32::LIGHT_BLUE
+    } else {
+        Color32::DARK_BLUE
+    };
+
+    let metric = cal_state.live_metric;
+    let points = live_readings.points(metric);
+    let line = Line::new(metric.as_ref(), points.clone())
+        .color(colour)
+        .highlight(true);
+    let markers = Points::new("Live points", points)
+        .shape(MarkerShape::Circle)
+        .radius(2.0)
+        .color(colour);
+
+    Plot::new("live_readings_plot")
+        .view_aspect(3.0)
+        .allow_scroll(false)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .show_background(false)
+        .x_axis_label("Seconds")
+        .show(ui, |plot_ui| {
+            plot_ui.line(line);
+            plot_ui.points(markers);
+        });
+}
+
+fn format_metric_value(metric: LiveMetric, v: f64) -> String {
+    match metric {
+        LiveMetric::Y => format!("{v:.4} nits"),
+        LiveMetric::SmallX | LiveMetric::SmallY => format!("{v:.4}"),
+        LiveMetric::Cct => format!("{v:.0} K"),
+        LiveMetric::DeltaE => format!("{v:.4}"),
+    }
+}
@@ -30,7 +30,6 @@ pub fn draw_luminance_plot(
 }
 
 fn draw_plot(ui: &mut Ui, results: &[ReadingResult], min: f64, cal_state: &CalibrationState) {
-    let target_eotf = cal_state.eotf;
     let oetf = cal_state.oetf;
 
     let dark_mode = ui.global_style().visuals.dark_mode;
@@ -45,43 +44,21 @@ fn draw_plot(ui: &mut Ui, results: &[ReadingResult], min: f64, cal_state: &Calib
         Color32::from_rgb(255, 153, 0)
     };
 
-    let nits_scale = (target_eotf == LuminanceEotf::PQ).then(|| 10_000.0 / cal_state.max_hdr_mdl);
-    let precision: u32 = 8;
-    let max = 2_u32.pow(precision);
-    let max_f = max as f64;
-    let ref_points: Vec<[f64; 2]> = (0..max)
-        .map(|i| {
-            let fraction = i as f64 / max_f;
-            let (x, y) = if let Some(nits_scale) = nits_scale {
-                let mut y = target_eotf.value(fraction, oetf);
-                if !oetf {
-                    y *= nits_scale;
-                }
-
-                (fraction, y.min(1.0))
-            } else {
-                (fraction, target_eotf.value_bpc(min, fraction, oetf, false))
-            };
-
-            [x, y]
-        })
-        .collect();
+    let ref_points = luminance_ref_points(cal_state, min);
 
     let ref_line = Line::new("Ref line", ref_points)
         .color(ref_color)
         .highlight(true)
         .style(egui_plot::LineStyle::Dashed { length: 10.0 });
 
-    let lum_points: Vec<[f64; 2]> = results
-        .iter()
-        .filter(|res| res.is_white_stimulus_reading())
-        .map(|res| {
-            let x = res.target.ref_rgb[0];
-            let y = res.luminance(oetf);
+    let lum_points = luminance_measured_points(results, oetf);
 
-            [x, y]
-        })
-        .collect();
+    let comparison_points = luminance_measured_points(&cal_state.comparison_results, oetf);
+    let comparison_line = (!comparison_points.is_empty()).then(|| {
+        Line::new("Lum line (before)", comparison_points)
+            .color(lum_color.gamma_multiply(0.6))
+            .style(egui_plot::LineStyle::Dashed { length: 6.0 })
+    });
 
     let lum_line = Line::new("Lum line", lum_points.clone())
         .color(lum_color)
@@ -100,7 +77,51 @@ fn draw_plot(ui: &mut Ui, results: &[ReadingResult], min: f64, cal_state: &Calib
         .show(ui, |plot_ui| {
             plot_ui.line(ref_line);
 
+            if let Some(comparison_line) = comparison_line {
+                plot_ui.line(comparison_line);
+            }
+
             plot_ui.line(lum_line);
             plot_ui.points(lum_markers);
         });
 }
+
+pub(super) fn luminance_ref_points(cal_state: &CalibrationState, min: f64) -> Vec<[f64; 2]> {
+    let target_eotf = cal_state.eotf;
+    let oetf = cal_state.oetf;
+
+    let nits_scale = (target_eotf == LuminanceEotf::PQ).then(|| 10_000.0 / cal_state.max_hdr_mdl);
+    let precision: u32 = 8;
+    let max = 2_u32.pow(precision);
+    let max_f = max as f64;
+    (0..max)
+        .map(|i| {
+            let fraction = i as f64 / max_f;
+            let (x, y) = if let Some(nits_scale) = nits_scale {
+                let mut y = target_eotf.value(fraction, oetf);
+                if !oetf {
+                    y *= nits_scale;
+                }
+
+                (fraction, y.min(1.0))
+            } else {
+                (fraction, target_eotf.value_bpc(min, fraction, oetf, false))
+            };
+
+            [x, y]
+        })
+        .collect()
+}
+
+pub(super) fn luminance_measured_points(results: &[ReadingResult], oetf: bool) -> Vec<[f64; 2]> {
+    results
+        .iter()
+        .filter(|res| res.is_white_stimulus_reading())
+        .map(|res| {
+            let x = res.target.ref_rgb[0];
+            let y = res.luminance(oetf);
+
+            [x, y]
+        })
+        .collect()
+}
@@ -0,0 +1,66 @@
+use eframe::egui::Ui;
+use tokio::sync::mpsc::Sender;
+
+use crate::{app::SaveFileType, external::ExternalJobCmd};
+
+use super::CalibrationState;
+
+pub fn draw_lut_export_ui(
+    ui: &mut Ui,
+    cal_state: &mut CalibrationState,
+    external_tx: &Sender<ExternalJobCmd>,
+) {
+    let lut_1d = cal_state.luts.lut_1d();
+    let lut_3d = cal_state.luts.lut_3d();
+
+    ui.heading("Calibration LUT");
+    ui.indent("cal_lut_export_indent", |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Greyscale 1D");
+            if let Some(lut_1d) = lut_1d {
+                if ui.button("Export .cal").clicked() {
+                    let cmd =
+                        ExternalJobCmd::SaveFile(SaveFileType::ArgyllCal, lut_1d.to_argyll_cal());
+                    external_tx.try_send(cmd).ok();
+                }
+                if ui.button("Export .cube").clicked() {
+                    let cmd = ExternalJobCmd::SaveFile(SaveFileType::Cube1d, lut_1d.to_cube());
+                    external_tx.try_send(cmd).ok();
+                }
+            } else {
+                ui.label("Measure a greyscale first");
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if let Some(lut_3d) = lut_3d {
+                let size = lut_3d.cube_size();
+                ui.label(format!("3D from {size}x{size}x{size} cube"));
+
+                let exports = [
+                    ("Export .cube", SaveFileType::Cube3d),
+                    ("Export eeColor", SaveFileType::EeColor),
+                    ("Export madVR .3dl", SaveFileType::Lustre3dl),
+                ];
+                let mut export_type = None;
+                ui.add_enabled_ui(!cal_state.exporting_lut, |ui| {
+                    for (label, file_type) in exports {
+                        if ui.button(label).clicked() {
+                            export_type = Some(file_type);
+                        }
+                    }
+                });
+
+                if cal_state.exporting_lut {
+                    ui.spinner();
+                } else if let Some(file_type) = export_type {
+                    let cmd = ExternalJobCmd::ExportLut3d(file_type, Box::new(lut_3d.clone()));
+                    cal_state.exporting_lut = external_tx.try_send(cmd).is_ok();
+                }
+            } else {
+                ui.label("3D");
+                ui.label("Measure a full RGB cube first");
+            }
+        });
+    });
+}
@@ -1,32 +1,61 @@
+use std::time::Instant;
+
 use eframe::{
-    egui::{ScrollArea, TextureOptions, Ui},
-    epaint::{ColorImage, TextureHandle},
+    egui::{Response, RichText, ScrollArea, TextureOptions, Ui},
+    epaint::{Color32, ColorImage, TextureHandle},
 };
 use kolor_64::ColorConversion;
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::Sender;
 
+mod abl_sweep;
 mod cie_diagram_plot;
+mod display_checks;
+mod drift_monitor;
 mod gamma_tracking_plot;
+mod html_report;
+mod live_readings;
 mod luminance_plot;
+mod lut_export;
 mod results_summary;
 mod rgb_balance_plot;
-
-use cie_diagram_plot::draw_cie_diagram_plot;
+mod session_comparison;
+mod tolerance;
+mod uniformity;
+mod white_balance;
+
+use abl_sweep::draw_abl_sweep_ui;
+use cie_diagram_plot::{compute_cie_chromaticity_diagram_worker, draw_cie_diagram_plot};
+use display_checks::draw_display_checks_ui;
+use drift_monitor::draw_drift_monitor_ui;
 use gamma_tracking_plot::draw_gamma_tracking_plot;
+use html_report::draw_html_report_export_ui;
+use live_readings::draw_live_readings_ui;
 use luminance_plot::draw_luminance_plot;
+use lut_export::draw_lut_export_ui;
 use rgb_balance_plot::draw_rgb_balance_plot;
+use session_comparison::draw_session_comparison_ui;
+use tolerance::draw_tolerance_profile_ui;
+use uniformity::draw_uniformity_ui;
+use white_balance::draw_white_balance_ui;
 
 use crate::{
-    calibration::{LuminanceEotf, ReadingResult, TargetColorspace},
+    calibration::{
+        AblSweep, CachedGamutReport, CachedLuts, CachedToleranceGrade, DisplayChecks, DriftMonitor,
+        DriftReadingKind, LiveMetric, LiveReadings, LuminanceEotf, ReadingResult, StatsThresholds,
+        TargetColorspace, TargetLuminanceReadings, ToleranceGrade, ToleranceProfile,
+        UniformityGrid, WhiteBalanceControls,
+    },
     generators::internal::InternalGenerator,
 };
 
-pub use cie_diagram_plot::compute_cie_chromaticity_diagram_worker;
+pub use cie_diagram_plot::CieDiagram;
 use results_summary::draw_results_summary_ui;
 
-use super::PGenApp;
+use super::{PGenApp, PGenAppUpdate};
 
 #[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct CalibrationState {
     pub spotread_started: bool,
     pub spotread_cli_args: Vec<(String, Option<String>)>,
@@ -37,52 +66,167 @@ pub struct CalibrationState {
     pub min_y: f64,
     pub max_y: f64,
     pub max_hdr_mdl: f64,
+    #[serde(skip)]
+    pub target_luminance_readings: TargetLuminanceReadings,
+    /// Measure the target luminance before each patch sweep
+    pub auto_target_luminance: bool,
 
     // Luminance calibration
     pub eotf: LuminanceEotf,
     pub oetf: bool,
 
     pub internal_gen: InternalGenerator,
+    /// Results of a previous session, to compare against
+    #[serde(skip)]
+    pub comparison_results: Vec<ReadingResult>,
 
+    /// Readings of the selected patch when measured on its own
     #[serde(skip)]
-    pub cie_texture: Option<TextureHandle>,
+    pub live_readings: LiveReadings,
+    pub live_capacity: usize,
+    pub live_metric: LiveMetric,
+
+    pub abl_sweep: AblSweep,
+    pub uniformity: UniformityGrid,
+    pub drift_monitor: DriftMonitor,
+    #[serde(skip)]
+    pub display_checks: DisplayChecks,
+    #[serde(skip)]
+    pub exporting_lut: bool,
+    #[serde(skip)]
+    pub gamut_report: CachedGamutReport,
+    #[serde(skip)]
+    pub luts: CachedLuts,
+    /// Measured patches graded against the selected tolerance profile
+    #[serde(skip)]
+    pub tolerance_grade: CachedToleranceGrade,
+
+    #[serde(skip)]
+    pub cie_xy_texture: Option<TextureHandle>,
+    #[serde(skip)]
+    pub cie_uv_texture: Option<TextureHandle>,
+    /// Diagram textures are only rendered once first displayed
+    #[serde(skip)]
+    pub cie_textures_requested: Vec<CieDiagram>,
 
     pub show_rgb_balance_plot: bool,
+    pub show_white_balance: bool,
+    pub white_balance_controls: WhiteBalanceControls,
+    /// Relative channel error considered balanced
+    pub white_balance_tolerance: f64,
     pub show_gamma_plot: bool,
     pub show_luminance_plot: bool,
     pub show_cie_diagram: bool,
     pub show_deviation_percent: bool,
+    pub stats_incl_luminance: bool,
+    pub stats_thresholds: StatsThresholds,
+    pub tolerance_profiles: Vec<ToleranceProfile>,
+    pub tolerance_profile_idx: Option<usize>,
+
+    pub cie_diagram: CieDiagram,
+    pub show_cie_tolerance: bool,
+    // Radius in u'v'
+    pub cie_tolerance: f64,
+
+    // Also set the infoframe primaries when filling HDR metadata from measurements
+    pub hdr_meta_incl_primaries: bool,
 }
 
 pub(crate) fn add_calibration_ui(app: &mut PGenApp, ui: &mut Ui) {
     ScrollArea::vertical().show(ui, |ui| {
         let results = app.cal_state.internal_gen.results();
+        app.cal_state.update_gamut_report(&results);
+        app.cal_state.update_luts(&results);
+        app.cal_state.update_tolerance_grade();
+
+        if !app.cal_state.live_readings.is_empty() {
+            draw_live_readings_ui(ui, &mut app.cal_state);
+            ui.separator();
+        }
 
         if !results.is_empty() {
             draw_results_summary_ui(ui, &mut app.cal_state, &results);
+            ui.add_space(5.0);
+            draw_tolerance_profile_ui(ui, &mut app.cal_state);
+            ui.add_space(5.0);
+            draw_html_report_export_ui(
+                ui,
+                &app.cal_state,
+                app.state.pgen_info.as_ref(),
+                app.state.pattern_config.bit_depth,
+                &app.ctx.external_tx,
+            );
+            ui.separator();
+
+            draw_lut_export_ui(ui, &mut app.cal_state, &app.ctx.external_tx);
             ui.separator();
         }
 
+        draw_display_checks_ui(app, ui);
+        ui.separator();
+
+        draw_session_comparison_ui(ui, &mut app.cal_state, &app.ctx.external_tx);
+        ui.separator();
+
         draw_rgb_balance_plot(ui, &mut app.cal_state, &results);
         ui.separator();
 
+        draw_white_balance_ui(ui, &mut app.cal_state, &results);
+        ui.separator();
+
         draw_gamma_tracking_plot(ui, &results, &mut app.cal_state);
         ui.separator();
 
         draw_luminance_plot(ui, &results, &mut app.cal_state);
         ui.separator();
 
-        draw_cie_diagram_plot(ui, &mut app.cal_state, &results);
+        draw_cie_diagram_plot(ui, &mut app.cal_state, &results, &app.ctx.app_tx);
+        ui.separator();
+
+        draw_abl_sweep_ui(app, ui);
+        ui.separator();
+
+        draw_uniformity_ui(app, ui);
+        ui.separator();
+
+        draw_drift_monitor_ui(app, ui);
         ui.add_space(10.0);
     });
 }
 
 pub(crate) fn handle_spotread_result(app: &mut PGenApp, result: Option<ReadingResult>) {
+    // Can happen before a patch sweep, so it takes priority
+    if app.cal_state.target_luminance_readings.started {
+        handle_target_luminance_result(app, result);
+        return;
+    } else if app.cal_state.abl_sweep.started {
+        handle_abl_sweep_result(app, result);
+        return;
+    } else if app.cal_state.uniformity.measuring {
+        handle_uniformity_result(app, result);
+        return;
+    } else if app.cal_state.display_checks.started {
+        handle_display_checks_result(app, result);
+        return;
+    } else if let Some(kind) = app.cal_state.drift_monitor.measuring {
+        handle_drift_result(app, kind, result);
+        return;
+    }
+
+    let target_rgb_to_xyz = app.cal_state.target_rgb_to_xyz_conv();
     let internal_gen = &mut app.cal_state.internal_gen;
     if let Some(result) = result {
+        if !internal_gen.auto_advance {
+            let delta_e = *result.delta_e2000(target_rgb_to_xyz).value() as f64;
+            app.cal_state
+                .live_readings
+                .push(&result, delta_e, app.cal_state.live_capacity);
+        }
+
         if let Some(patch) = internal_gen.selected_patch_mut() {
             patch.result = Some(result);
         }
+        let measured_idx = internal_gen.selected_idx;
 
         let last_idx = internal_gen.list.len() - 1;
         let can_advance =
@@ -97,12 +241,26 @@ pub(crate) fn handle_spotread_result(app: &mut PGenApp, result: Option<ReadingRe
             *idx += 1;
         }
 
+        let drift_check = internal_gen.auto_advance
+            && measured_idx.is_some_and(|i| app.cal_state.drift_monitor.record_sweep_patch(i));
+
         // Keep going if it wasn't stopped manually
         if internal_gen.started && (can_advance || continuous_selected) {
-            app.calibration_send_measure_selected_patch();
+            if drift_check {
+                app.calibration_send_measure_drift_reading(DriftReadingKind::SweepCheck);
+            } else {
+                app.calibration_send_measure_selected_patch();
+            }
         } else {
+            // Last drift check for the patches measured since the previous one
+            let sweep_done = internal_gen.started && internal_gen.auto_advance;
             internal_gen.started = false;
-            app.set_blank();
+
+            if sweep_done && app.cal_state.drift_monitor.end_sweep() {
+                app.calibration_send_measure_drift_reading(DriftReadingKind::SweepCheck);
+            } else {
+                app.set_blank();
+            }
         }
     } else {
         // Something went wrong and we got no result, stop calibration
@@ -111,23 +269,163 @@ pub(crate) fn handle_spotread_result(app: &mut PGenApp, result: Option<ReadingRe
     }
 }
 
+fn handle_target_luminance_result(app: &mut PGenApp, result: Option<ReadingResult>) {
+    let readings = &mut app.cal_state.target_luminance_readings;
+
+    let Some(result) = result else {
+        readings.stop();
+        app.cal_state.internal_gen.started = false;
+        app.set_blank();
+        return;
+    };
+
+    let then_sweep = readings.then_sweep;
+    if let Some((black, white)) = readings.add_reading(result.xyy[2]) {
+        app.cal_state.set_target_luminance(black, white);
+
+        if then_sweep && app.cal_state.internal_gen.started {
+            app.calibration_send_measure_sweep_start();
+        } else {
+            app.set_blank();
+        }
+    } else {
+        app.calibration_send_measure_target_luminance_step();
+    }
+}
+
+fn handle_abl_sweep_result(app: &mut PGenApp, result: Option<ReadingResult>) {
+    let abl_sweep = &mut app.cal_state.abl_sweep;
+    let size = abl_sweep.next_size();
+
+    if let Some((size, result)) = size.zip(result) {
+        abl_sweep.results.push((size, result));
+
+        if !abl_sweep.stop_requested && app.calibration_send_measure_abl_step() {
+            return;
+        }
+    }
+
+    app.stop_abl_sweep();
+}
+
+fn handle_display_checks_result(app: &mut PGenApp, result: Option<ReadingResult>) {
+    let display_checks = &mut app.cal_state.display_checks;
+    let step = display_checks.next_step();
+
+    if let Some((step, result)) = step.zip(result) {
+        display_checks.results.push((step, result));
+
+        if !display_checks.stop_requested && app.calibration_send_measure_display_check_step() {
+            return;
+        }
+    }
+
+    app.stop_display_checks();
+}
+
+fn handle_uniformity_result(app: &mut PGenApp, result: Option<ReadingResult>) {
+    let uniformity = &mut app.cal_state.uniformity;
+
+    if let Some(result) = result {
+        if uniformity.set_current_result(result) {
+            app.uniformity_show_current_cell();
+        } else {
+            app.stop_uniformity();
+        }
+    } else {
+        // Let the user retry the same cell
+        uniformity.measuring = false;
+    }
+}
+
+fn handle_drift_result(app: &mut PGenApp, kind: DriftReadingKind, result: Option<ReadingResult>) {
+    let drift_monitor = &mut app.cal_state.drift_monitor;
+
+    match (kind, result) {
+        (DriftReadingKind::Monitor, Some(result)) => {
+            // The reference patch stays displayed until the next reading
+            if !drift_monitor.add_sample(Instant::now(), result.xyy) {
+                app.set_blank();
+            }
+        }
+        (DriftReadingKind::SweepCheck, Some(result)) => {
+            drift_monitor.add_sweep_check(result.xyy);
+
+            if app.cal_state.internal_gen.started {
+                app.calibration_send_measure_selected_patch();
+            } else {
+                app.set_blank();
+            }
+        }
+        (DriftReadingKind::Monitor, None) => {
+            drift_monitor.stop();
+            drift_monitor.measuring = None;
+            app.set_blank();
+        }
+        (DriftReadingKind::SweepCheck, None) => {
+            drift_monitor.measuring = None;
+            app.cal_state.internal_gen.started = false;
+            app.set_blank();
+        }
+    }
+}
+
 impl CalibrationState {
     pub fn initial_setup(&mut self) {
         self.spotread_started = false;
         self.internal_gen.started = false;
+        self.abl_sweep.started = false;
+        self.uniformity.started = false;
+        self.uniformity.measuring = false;
+        self.drift_monitor.stop();
+        self.drift_monitor.measuring = None;
 
-        self.min_y = self.min_y.clamp(0.0, 1.0);
+        self.min_y = self.min_y.clamp(0.0, 5.0);
         if self.max_y <= 0.0 {
             self.max_y = 100.0;
         }
     }
 
-    pub fn set_cie_texture(&mut self, ui: &mut Ui, image: ColorImage) {
-        self.cie_texture.get_or_insert_with(|| {
-            ui.load_texture("cie_xy_diagram_tex", image, TextureOptions::NEAREST)
+    pub fn set_cie_texture(&mut self, ui: &mut Ui, kind: CieDiagram, image: ColorImage) {
+        let texture = match kind {
+            CieDiagram::Xy => &mut self.cie_xy_texture,
+            CieDiagram::Uv => &mut self.cie_uv_texture,
+        };
+
+        texture.get_or_insert_with(|| {
+            ui.load_texture(kind.texture_name(), image, TextureOptions::NEAREST)
         });
     }
 
+    /// Starts rendering the diagram texture if it wasn't already
+    pub fn request_cie_texture(&mut self, kind: CieDiagram, app_tx: &Sender<PGenAppUpdate>) {
+        if !self.cie_textures_requested.contains(&kind) {
+            self.cie_textures_requested.push(kind);
+            compute_cie_chromaticity_diagram_worker(app_tx.clone(), kind);
+        }
+    }
+
+    pub fn cie_texture(&self, kind: CieDiagram) -> Option<&TextureHandle> {
+        match kind {
+            CieDiagram::Xy => self.cie_xy_texture.as_ref(),
+            CieDiagram::Uv => self.cie_uv_texture.as_ref(),
+        }
+    }
+
+    /// Only recomputed when the results or target changed
+    pub fn update_gamut_report(&mut self, results: &[ReadingResult]) {
+        let target_rgb_to_xyz = self.target_rgb_to_xyz_conv();
+        self.gamut_report
+            .update(results, self.target_csp, target_rgb_to_xyz);
+    }
+
+    /// Only rebuilt when the results or target changed
+    pub fn update_luts(&mut self, results: &[ReadingResult]) {
+        let target_rgb_to_xyz = self.target_rgb_to_xyz_conv();
+        self.luts
+            .update(results, self.target_csp, target_rgb_to_xyz);
+    }
+
     pub fn target_rgb_to_xyz_conv(&self) -> ColorConversion {
         ColorConversion::new(self.target_csp.to_kolor(), kolor_64::spaces::CIE_XYZ)
     }
@@ -148,11 +446,73 @@ impl CalibrationState {
             });
     }
 
+    /// Uses the measured black and white, clamped to the allowed ranges
+    pub fn set_target_luminance(&mut self, black: f64, white: f64) {
+        self.min_y = black.clamp(0.0, 5.0);
+        self.max_y = white.clamp(25.0, 10_000.0);
+
+        if self.eotf == LuminanceEotf::PQ {
+            self.max_hdr_mdl = white.clamp(400.0, 10_000.0);
+        }
+
+        self.update_patterns_target();
+    }
+
+    /// Any of the measurement workflows is running
+    pub fn measurement_in_progress(&self) -> bool {
+        self.internal_gen.started
+            || self.abl_sweep.started
+            || self.uniformity.started
+            || self.drift_monitor.started
+            || self.display_checks.started
+            || self.target_luminance_readings.started
+    }
+
+    pub fn selected_tolerance_profile(&self) -> Option<&ToleranceProfile> {
+        self.tolerance_profile_idx
+            .and_then(|idx| self.tolerance_profiles.get(idx))
+    }
+
+    /// Only regraded when the patches, profile or target changed
+    pub fn update_tolerance_grade(&mut self) {
+        let target_rgb_to_xyz = self.target_rgb_to_xyz_conv();
+        let patches = self
+            .internal_gen
+            .list
+            .iter()
+            .enumerate()
+            .filter_map(|(i, patch)| patch.result.map(|res| (i, res)));
+        let profile = self
+            .tolerance_profile_idx
+            .and_then(|idx| self.tolerance_profiles.get(idx));
+
+        self.tolerance_grade
+            .update(patches, profile, self.target_csp, target_rgb_to_xyz);
+    }
+
     pub fn min_normalized(&self) -> f64 {
         self.min_y / self.max_y
     }
 }
 
+/// Text colours for good and bad values
+fn pass_fail_colours(ui: &Ui) -> (Color32, Color32) {
+    if ui.global_style().visuals.dark_mode {
+        (Color32::LIGHT_GREEN, Color32::LIGHT_RED)
+    } else {
+        (Color32::DARK_GREEN, Color32::DARK_RED)
+    }
+}
+
+fn verdict_label(ui: &mut Ui, grade: &ToleranceGrade) -> Response {
+    let (pass_colour, fail_colour) = pass_fail_colours(ui);
+    if grade.passed() {
+        ui.label(RichText::new("PASS").strong().color(pass_colour))
+    } else {
+        ui.label(RichText::new("FAIL").strong().color(fail_colour))
+    }
+}
+
 impl Default for CalibrationState {
     fn default() -> Self {
         Self {
@@ -163,17 +523,46 @@ impl Default for CalibrationState {
             min_y: Default::default(),
             max_y: 100.0,
             max_hdr_mdl: 1000.0,
+            target_luminance_readings: Default::default(),
+            auto_target_luminance: false,
             target_csp: Default::default(),
             eotf: LuminanceEotf::Gamma22,
             oetf: true,
 
             internal_gen: Default::default(),
-            cie_texture: Default::default(),
+            comparison_results: Default::default(),
+            live_readings: Default::default(),
+            live_capacity: 120,
+            live_metric: Default::default(),
+            abl_sweep: Default::default(),
+            uniformity: Default::default(),
+            drift_monitor: Default::default(),
+            display_checks: Default::default(),
+            exporting_lut: false,
+            gamut_report: Default::default(),
+            luts: Default::default(),
+            tolerance_grade: Default::default(),
+            cie_xy_texture: Default::default(),
+            cie_uv_texture: Default::default(),
+            cie_textures_requested: Default::default(),
             show_rgb_balance_plot: true,
+            show_white_balance: true,
+            white_balance_controls: Default::default(),
+            white_balance_tolerance: 0.01,
             show_gamma_plot: true,
             show_luminance_plot: true,
             show_cie_diagram: true,
             show_deviation_percent: false,
+            stats_incl_luminance: true,
+            stats_thresholds: Default::default(),
+            tolerance_profiles: ToleranceProfile::defaults(),
+            tolerance_profile_idx: None,
+
+            cie_diagram: Default::default(),
+            show_cie_tolerance: false,
+            cie_tolerance: 0.004,
+
+            hdr_meta_incl_primaries: false,
         }
     }
 }
@@ -1,8 +1,8 @@
 use eframe::egui::{self, Ui};
 
-use crate::calibration::ReadingResult;
+use crate::calibration::{DeltaEStats, GamutReport, ReadingResult, ResultsStats};
 
-use super::CalibrationState;
+use super::{CalibrationState, verdict_label};
 
 pub fn draw_results_summary_ui(
     ui: &mut Ui,
@@ -49,5 +49,157 @@ pub fn draw_results_summary_ui(
                 ui.label(format!("Average gamma: {avg_gamma_str}"));
                 ui.end_row();
             });
+
+        ui.add_space(5.0);
+        draw_results_stats(ui, cal_state, results);
+
+        if let Some(report) = cal_state.gamut_report.report() {
+            ui.add_space(5.0);
+            draw_gamut_report(ui, cal_state, report);
+        }
+    });
+}
+
+fn draw_gamut_report(ui: &mut Ui, cal_state: &CalibrationState, report: &GamutReport) {
+    egui::Grid::new("cal_results_gamut_grid")
+        .spacing([16.0, 4.0])
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Gamut coverage");
+            ui.strong("xy");
+            ui.strong("u'v'");
+            ui.end_row();
+
+            for coverage in report.coverage.iter() {
+                ui.label(coverage.colorspace.as_ref());
+                ui.label(format!("{:.2} %", coverage.xy * 100.0));
+                ui.label(format!("{:.2} %", coverage.uv * 100.0));
+                ui.end_row();
+            }
+
+            ui.label(format!("Area vs {}", cal_state.target_csp));
+            ui.label(format!("{:.2} %", report.relative_area_xy * 100.0));
+            ui.label(format!("{:.2} %", report.relative_area_uv * 100.0));
+            ui.end_row();
+        });
+
+    if let Some(volume) = report.volume {
+        ui.label(format!(
+            "Gamut volume (CIELAB): {:.0} / {:.0} ({:.2} %)",
+            volume.measured,
+            volume.target,
+            volume.relative() * 100.0
+        ));
+    }
+}
+
+fn draw_results_stats(ui: &mut Ui, cal_state: &mut CalibrationState, results: &[ReadingResult]) {
+    let target_rgb_to_xyz = cal_state.target_rgb_to_xyz_conv();
+    let stats =
+        ResultsStats::from_results(results, target_rgb_to_xyz, cal_state.stats_incl_luminance);
+
+    ui.checkbox(
+        &mut cal_state.stats_incl_luminance,
+        "Include luminance in dE statistics",
+    );
+
+    egui::Grid::new("cal_results_stats_grid")
+        .spacing([16.0, 4.0])
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Patches");
+            ui.strong("Count");
+            ui.strong("Average dE");
+            ui.strong("95th pct. dE");
+            ui.strong("Max dE");
+            ui.end_row();
+
+            let rows = stats
+                .delta_e
+                .iter()
+                .map(|all| ("All", all))
+                .chain(stats.groups.iter().map(|(group, s)| (group.as_ref(), s)));
+            for (label, delta_e) in rows {
+                draw_delta_e_row(ui, label, delta_e);
+            }
+        });
+
+    let contrast_str = stats.contrast.map_or("N/A".to_string(), |contrast| {
+        if contrast.is_finite() {
+            format!("{contrast:.0}:1")
+        } else {
+            "∞:1".to_string()
+        }
+    });
+    let cct_str = stats
+        .greyscale_cct
+        .map_or("N/A".to_string(), |cct| format!("{cct:.0}K"));
+    let duv_str = stats
+        .greyscale_duv
+        .map_or("N/A".to_string(), |duv| format!("{duv:+.4}"));
+
+    egui::Grid::new("cal_results_stats_extra_grid")
+        .spacing([16.0, 4.0])
+        .show(ui, |ui| {
+            ui.label(format!("Contrast: {contrast_str}"));
+            ui.label(format!("Greyscale average CCT: {cct_str}"));
+            ui.label(format!("Duv: {duv_str}"));
+            ui.end_row();
+        });
+
+    ui.add_space(5.0);
+    draw_thresholds_verdict(ui, cal_state, &stats);
+}
+
+fn draw_delta_e_row(ui: &mut Ui, label: &str, delta_e: &DeltaEStats) {
+    ui.label(label);
+    ui.label(delta_e.count.to_string());
+    ui.label(format!("{:.4}", delta_e.average));
+    ui.label(format!("{:.4}", delta_e.p95));
+    ui.label(format!("{:.4}", delta_e.max));
+    ui.end_row();
+}
+
+fn draw_thresholds_verdict(ui: &mut Ui, cal_state: &mut CalibrationState, stats: &ResultsStats) {
+    let thresholds = &mut cal_state.stats_thresholds;
+
+    ui.horizontal(|ui| {
+        ui.label("Max average dE");
+        ui.add(
+            egui::DragValue::new(&mut thresholds.max_average_delta_e)
+                .range(0.0..=100.0)
+                .speed(0.1)
+                .max_decimals(2),
+        );
+        ui.label("95th pct.");
+        ui.add(
+            egui::DragValue::new(&mut thresholds.max_p95_delta_e)
+                .range(0.0..=100.0)
+                .speed(0.1)
+                .max_decimals(2),
+        );
+        ui.label("Max");
+        ui.add(
+            egui::DragValue::new(&mut thresholds.max_delta_e)
+                .range(0.0..=100.0)
+                .speed(0.1)
+                .max_decimals(2),
+        );
+        ui.label("Min contrast");
+        ui.add(
+            egui::DragValue::new(&mut thresholds.min_contrast)
+                .range(0.0..=1_000_000.0)
+                .speed(10.0)
+                .suffix(":1"),
+        )
+        .on_hover_text("0 to skip");
+    });
+
+    let grade = stats.grade(thresholds);
+    ui.horizontal(|ui| {
+        verdict_label(ui, &grade);
+        if !grade.passed() {
+            ui.label(grade.failures.join(", "));
+        }
     });
 }
@@ -1,5 +1,5 @@
 use eframe::{egui::Ui, epaint::Color32};
-use egui_plot::{Line, MarkerShape, Plot, Points};
+use egui_plot::{Line, LineStyle, MarkerShape, Plot, Points};
 use kolor_64::Vec3;
 
 use crate::calibration::ReadingResult;
@@ -22,11 +22,11 @@ pub fn draw_rgb_balance_plot(
     });
 
     if cal_state.show_rgb_balance_plot {
-        draw_plot(ui, results);
+        draw_plot(ui, results, &cal_state.comparison_results);
     }
 }
 
-fn draw_plot(ui: &mut Ui, results: &[ReadingResult]) {
+fn draw_plot(ui: &mut Ui, results: &[ReadingResult], comparison_results: &[ReadingResult]) {
     let dark_mode = ui.global_style().visuals.dark_mode;
 
     let ref_points: Vec<[f64; 2]> = (0..255).map(|i| [i as f64 / 255.0, 0.0]).collect();
@@ -36,19 +36,7 @@ fn draw_plot(ui: &mut Ui, results: &[ReadingResult]) {
         Color32::BLACK
     };
     let ref_line = Line::new("Reference", ref_points).color(ref_color);
-    let rgb_points: Vec<(f64, Vec3)> = results
-        .iter()
-        .filter(|res| res.is_white_stimulus_reading() && res.target.ref_rgb.x > 0.01)
-        .map(|res| {
-            let ref_cmp = res.target.ref_rgb[0];
-            let x = (ref_cmp * 1e3).round() / 1e3;
-
-            // Both RGB and min_y are already encoded in display gamma
-            let y = res.gamma_normalized_rgb();
-
-            (x, y - 1.0)
-        })
-        .collect();
+    let rgb_points = rgb_balance_points(results);
 
     let red_points: Vec<[f64; 2]> = rgb_points.iter().map(|(x, rgb)| [*x, rgb[0]]).collect();
     let red_line = Line::new("Red", red_points.clone())
@@ -88,6 +76,26 @@ fn draw_plot(ui: &mut Ui, results: &[ReadingResult]) {
         .color(blue_marker_color)
         .highlight(true);
 
+    let comparison_points = rgb_balance_points(comparison_results);
+    let comparison_lines: Vec<Line> = [
+        ("Red (before)", Color32::RED),
+        ("Green (before)", GREEN_LINE_COLOR),
+        ("Blue (before)", blue_color),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(c, (name, colour))| {
+        let points: Vec<[f64; 2]> = comparison_points
+            .iter()
+            .map(|(x, rgb)| [*x, rgb[c]])
+            .collect();
+
+        Line::new(name, points)
+            .color(colour.gamma_multiply(0.6))
+            .style(LineStyle::Dashed { length: 6.0 })
+    })
+    .collect();
+
     Plot::new("rgb_balance_plot")
         .view_aspect(2.0)
         .allow_scroll(false)
@@ -97,6 +105,10 @@ fn draw_plot(ui: &mut Ui, results: &[ReadingResult]) {
         .show(ui, |plot_ui| {
             plot_ui.line(ref_line);
 
+            for line in comparison_lines {
+                plot_ui.line(line);
+            }
+
             plot_ui.line(red_line);
             plot_ui.points(red_markers);
             plot_ui.line(green_line);
@@ -105,3 +117,19 @@ fn draw_plot(ui: &mut Ui, results: &[ReadingResult]) {
             plot_ui.points(blue_markers);
         });
 }
+
+pub(super) fn rgb_balance_points(results: &[ReadingResult]) -> Vec<(f64, Vec3)> {
+    results
+        .iter()
+        .filter(|res| res.is_white_stimulus_reading() && res.target.ref_rgb.x > 0.01)
+        .map(|res| {
+            let ref_cmp = res.target.ref_rgb[0];
+            let x = (ref_cmp * 1e3).round() / 1e3;
+
+            // Both RGB and min_y are already encoded in display gamma
+            let y = res.gamma_normalized_rgb();
+
+            (x, y - 1.0)
+        })
+        .collect()
+}
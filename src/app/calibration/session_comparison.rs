@@ -0,0 +1,169 @@
+use eframe::egui::{self, Layout, RichText, Ui};
+use egui_extras::{Column, TableBuilder};
+use tokio::sync::mpsc::Sender;
+
+use crate::{
+    app::{ReadFileType, SaveFileType},
+    calibration::{ReadingResult, matching_result, session_to_csv},
+    external::ExternalJobCmd,
+};
+
+use super::{CalibrationState, pass_fail_colours};
+
+const COMPARISON_COLUMNS: &[&str] = &[
+    "#",
+    "Red",
+    "Green",
+    "Blue",
+    "dE before",
+    "dE after",
+    "ΔdE",
+    "CCT before",
+    "CCT after",
+    "Y before",
+    "Y after",
+    "ΔY",
+];
+
+pub fn draw_session_comparison_ui(
+    ui: &mut Ui,
+    cal_state: &mut CalibrationState,
+    external_tx: &Sender<ExternalJobCmd>,
+) {
+    let has_results = cal_state
+        .internal_gen
+        .list
+        .iter()
+        .any(|e| e.result.is_some());
+
+    ui.heading("Sessions");
+    ui.indent("cal_sessions_indent", |ui| {
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(has_results, |ui| {
+                if ui.button("Save session").clicked() {
+                    let patches = cal_state
+                        .internal_gen
+                        .list
+                        .iter()
+                        .filter_map(|e| e.result.map(|res| (e.rgb, res)));
+
+                    match session_to_csv(patches) {
+                        Ok(csv) => {
+                            let cmd = ExternalJobCmd::SaveFile(SaveFileType::Session, csv);
+                            external_tx.try_send(cmd).ok();
+                        }
+                        Err(e) => log::error!("Failed serializing session: {e}"),
+                    }
+                }
+            });
+
+            if ui.button("Load session").clicked() {
+                let cmd = ExternalJobCmd::ReadFile(ReadFileType::Session);
+                external_tx.try_send(cmd).ok();
+            }
+
+            if ui.button("Load comparison session").clicked() {
+                let cmd = ExternalJobCmd::ReadFile(ReadFileType::ComparisonSession);
+                external_tx.try_send(cmd).ok();
+            }
+
+            if !cal_state.comparison_results.is_empty() && ui.button("Clear comparison").clicked() {
+                cal_state.comparison_results.clear();
+            }
+        });
+
+        if !cal_state.comparison_results.is_empty() {
+            ui.label("Comparison session is drawn dashed on the plots");
+            draw_comparison_table(ui, cal_state);
+        }
+    });
+}
+
+fn draw_comparison_table(ui: &mut Ui, cal_state: &CalibrationState) {
+    let target_rgb_to_xyz = cal_state.target_rgb_to_xyz_conv();
+
+    let rows: Vec<(usize, [u16; 3], ReadingResult, ReadingResult)> = cal_state
+        .internal_gen
+        .list
+        .iter()
+        .enumerate()
+        .filter_map(|(i, patch)| {
+            let after = patch.result?;
+            let before = matching_result(&cal_state.comparison_results, &after)?;
+
+            Some((i, patch.rgb, *before, after))
+        })
+        .collect();
+
+    if rows.is_empty() {
+        ui.label("No patches in common with the comparison session");
+        return;
+    }
+
+    let (better_colour, worse_colour) = pass_fail_colours(ui);
+
+    let value_col = Column::auto().at_least(60.0);
+    let cell_layout = Layout::default()
+        .with_main_align(egui::Align::Max)
+        .with_cross_align(egui::Align::Max);
+    TableBuilder::new(ui)
+        .id_salt("cal_session_comparison_table")
+        .striped(true)
+        .cell_layout(cell_layout)
+        .columns(value_col, COMPARISON_COLUMNS.len())
+        .max_scroll_height(300.0)
+        .header(20.0, |mut header| {
+            for label in COMPARISON_COLUMNS.iter().copied() {
+                header.col(|ui| {
+                    ui.strong(label);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(20.0, rows.len(), |mut row| {
+                let (i, rgb, before, after) = rows[row.index()];
+
+                let de_before = *before.delta_e2000(target_rgb_to_xyz).value();
+                let de_after = *after.delta_e2000(target_rgb_to_xyz).value();
+                let de_diff = de_after - de_before;
+                let de_colour = if de_diff <= 0.0 {
+                    better_colour
+                } else {
+                    worse_colour
+                };
+
+                row.col(|ui| {
+                    ui.label(i.to_string());
+                });
+                for c in rgb {
+                    row.col(|ui| {
+                        ui.label(c.to_string());
+                    });
+                }
+                row.col(|ui| {
+                    ui.label(format!("{de_before:.4}"));
+                });
+                row.col(|ui| {
+                    ui.label(format!("{de_after:.4}"));
+                });
+                row.col(|ui| {
+                    ui.label(RichText::new(format!("{de_diff:+.4}")).color(de_colour));
+                });
+                row.col(|ui| {
+                    ui.label(format!("{:.0} K", before.cct));
+                });
+                row.col(|ui| {
+                    ui.label(format!("{:.0} K", after.cct));
+                });
+                row.col(|ui| {
+                    ui.label(format!("{:.4}", before.xyy[2]));
+                });
+                row.col(|ui| {
+                    ui.label(format!("{:.4}", after.xyy[2]));
+                });
+                row.col(|ui| {
+                    ui.label(format!("{:+.4}", after.xyy[2] - before.xyy[2]));
+                });
+            })
+        });
+}
@@ -0,0 +1,129 @@
+use eframe::egui::{self, Ui};
+
+use crate::calibration::ToleranceProfile;
+
+use super::{CalibrationState, verdict_label};
+
+pub fn draw_tolerance_profile_ui(ui: &mut Ui, cal_state: &mut CalibrationState) {
+    ui.heading("Tolerance profile");
+
+    ui.indent("cal_tolerance_profile_indent", |ui| {
+        ui.horizontal(|ui| {
+            let selected_name = cal_state
+                .selected_tolerance_profile()
+                .map_or("None", |profile| profile.name.as_str())
+                .to_owned();
+
+            egui::ComboBox::from_id_salt(egui::Id::new("cal_tolerance_profile"))
+                .width(150.0)
+                .selected_text(selected_name)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut cal_state.tolerance_profile_idx, None, "None");
+                    for (i, profile) in cal_state.tolerance_profiles.iter().enumerate() {
+                        ui.selectable_value(
+                            &mut cal_state.tolerance_profile_idx,
+                            Some(i),
+                            &profile.name,
+                        );
+                    }
+                });
+
+            if ui.button("New").clicked() {
+                let profile = cal_state.selected_tolerance_profile().map_or_else(
+                    ToleranceProfile::default,
+                    |profile| ToleranceProfile {
+                        name: format!("{} copy", profile.name),
+                        ..profile.clone()
+                    },
+                );
+
+                cal_state.tolerance_profiles.push(profile);
+                cal_state.tolerance_profile_idx = Some(cal_state.tolerance_profiles.len() - 1);
+            }
+
+            if let Some(idx) = cal_state.tolerance_profile_idx
+                && ui.button("Delete").clicked()
+            {
+                cal_state.tolerance_profiles.remove(idx);
+                cal_state.tolerance_profile_idx = None;
+            }
+        });
+
+        let Some(profile) = cal_state
+            .tolerance_profile_idx
+            .and_then(|idx| cal_state.tolerance_profiles.get_mut(idx))
+        else {
+            return;
+        };
+
+        draw_profile_settings(ui, profile);
+
+        let Some(grade) = cal_state.tolerance_grade.grade() else {
+            return;
+        };
+
+        ui.add_space(5.0);
+        ui.horizontal(|ui| {
+            verdict_label(ui, grade);
+            if !grade.failing_patches.is_empty() {
+                ui.label(format!("{} failing patches", grade.failing_patches.len()));
+            }
+        });
+        for failure in grade.failures.iter() {
+            ui.label(failure);
+        }
+        for (i, reasons) in grade.failing_patches.iter() {
+            ui.label(format!("Patch {i}: {}", reasons.join(", ")));
+        }
+    });
+}
+
+fn draw_profile_settings(ui: &mut Ui, profile: &mut ToleranceProfile) {
+    egui::Grid::new("cal_tolerance_profile_grid")
+        .spacing([16.0, 4.0])
+        .show(ui, |ui| {
+            ui.label("Name");
+            ui.add(egui::TextEdit::singleline(&mut profile.name).desired_width(150.0));
+            ui.end_row();
+
+            ui.label("dE includes luminance");
+            ui.checkbox(&mut profile.incl_luminance, "");
+            ui.end_row();
+
+            let limits = [
+                ("Max dE near black", &mut profile.max_delta_e_near_black),
+                ("Max dE greyscale", &mut profile.max_delta_e_greyscale),
+                ("Max dE colours", &mut profile.max_delta_e_colours),
+                ("Max gamma deviation", &mut profile.max_gamma_deviation),
+            ];
+            for (label, value) in limits {
+                ui.label(label);
+                ui.add(
+                    egui::DragValue::new(value)
+                        .range(0.0..=100.0)
+                        .speed(0.01)
+                        .max_decimals(2),
+                );
+                ui.end_row();
+            }
+
+            ui.label("Max white Δu'v'");
+            ui.add(
+                egui::DragValue::new(&mut profile.max_white_delta_uv)
+                    .range(0.0..=0.1)
+                    .speed(0.0001)
+                    .max_decimals(4),
+            );
+            ui.end_row();
+
+            ui.label("Min contrast");
+            ui.add(
+                egui::DragValue::new(&mut profile.min_contrast)
+                    .range(0.0..=1_000_000.0)
+                    .speed(10.0)
+                    .suffix(":1"),
+            )
+            .on_hover_text("0 to skip");
+            ui.end_row();
+        });
+}
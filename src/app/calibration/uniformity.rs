@@ -0,0 +1,190 @@
+use eframe::{
+    egui::{self, Align2, FontId, Layout, Sense, Ui},
+    emath::Align,
+    epaint::{Color32, Rect, Stroke, vec2},
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+    app::PGenApp,
+    calibration::{MAX_CUSTOM_GRID_SIZE, UniformityDeviation, UniformityGrid, UniformityGridSize},
+};
+
+// Deviations at which the heat map is fully red
+const MAX_LUMINANCE_DEVIATION: f64 = 0.2;
+const MAX_DELTA_UV: f64 = 0.01;
+
+pub fn draw_uniformity_ui(app: &mut PGenApp, ui: &mut Ui) {
+    let pgen_connected = app.state.connected_state.connected;
+    let started = app.cal_state.uniformity.started;
+
+    let can_start = pgen_connected
+        && app.cal_state.spotread_started
+        && !app.processing
+        && !app.cal_state.measurement_in_progress();
+
+    ui.horizontal(|ui| {
+        ui.heading("Uniformity");
+
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            ui.add_enabled_ui(can_start, |ui| {
+                if ui.button("Start").clicked() {
+                    let pattern_cfg = app.state.pattern_config;
+                    app.cal_state.uniformity.start(pattern_cfg);
+                    app.uniformity_show_current_cell();
+                }
+            });
+
+            ui.add_enabled_ui(!started, |ui| {
+                let uniformity = &mut app.cal_state.uniformity;
+                ui.add(
+                    egui::DragValue::new(&mut uniformity.level)
+                        .speed(0.01)
+                        .range(0.0..=1.0)
+                        .max_decimals(3),
+                );
+                ui.label("Level");
+
+                if uniformity.grid_size == UniformityGridSize::Custom {
+                    ui.add(
+                        egui::DragValue::new(&mut uniformity.custom_rows)
+                            .range(1..=MAX_CUSTOM_GRID_SIZE),
+                    );
+                    ui.label("x");
+                    ui.add(
+                        egui::DragValue::new(&mut uniformity.custom_columns)
+                            .range(1..=MAX_CUSTOM_GRID_SIZE),
+                    );
+                }
+
+                egui::ComboBox::from_id_salt(egui::Id::new("cal_uniformity_grid_size"))
+                    .selected_text(uniformity.grid_size.as_ref())
+                    .show_ui(ui, |ui| {
+                        for grid_size in UniformityGridSize::iter() {
+                            ui.selectable_value(
+                                &mut uniformity.grid_size,
+                                grid_size,
+                                grid_size.as_ref(),
+                            );
+                        }
+                    });
+            });
+        });
+    });
+
+    if started {
+        draw_measure_prompt(app, ui);
+    }
+
+    let uniformity = &app.cal_state.uniformity;
+    if uniformity.results.iter().all(Option::is_none) {
+        return;
+    }
+
+    let deviations = uniformity.deviations();
+    ui.columns(2, |columns| {
+        columns[0].label("Luminance deviation");
+        draw_heat_map(&mut columns[0], uniformity, &deviations, |dev| {
+            let t = dev.luminance.abs() / MAX_LUMINANCE_DEVIATION;
+            (t, format!("{:+.1} %", dev.luminance * 100.0))
+        });
+
+        columns[1].label("Δu'v'");
+        draw_heat_map(&mut columns[1], uniformity, &deviations, |dev| {
+            let t = dev.delta_uv / MAX_DELTA_UV;
+            (t, format!("{:.4}", dev.delta_uv))
+        });
+    });
+}
+
+fn draw_measure_prompt(app: &mut PGenApp, ui: &mut Ui) {
+    let uniformity = &app.cal_state.uniformity;
+    let (column, row) = uniformity.cell_coords(uniformity.current_cell);
+    let measuring = uniformity.measuring;
+
+    ui.horizontal(|ui| {
+        if measuring {
+            ui.label(format!("Measuring row {}, column {}", row + 1, column + 1));
+        } else {
+            ui.label(format!(
+                "Place the probe on the patch at row {}, column {}",
+                row + 1,
+                column + 1
+            ));
+        }
+
+        ui.add_enabled_ui(!measuring && !app.processing, |ui| {
+            if ui.button("Measure").clicked() {
+                app.calibration_send_measure_uniformity_cell();
+            }
+            if ui.button("Stop").clicked() {
+                app.stop_uniformity();
+            }
+        });
+    });
+}
+
+fn draw_heat_map(
+    ui: &mut Ui,
+    uniformity: &UniformityGrid,
+    deviations: &[Option<UniformityDeviation>],
+    cell_value: impl Fn(&UniformityDeviation) -> (f64, String),
+) {
+    let (columns, rows) = uniformity.dimensions();
+
+    let width = ui.available_width();
+    let (rect, _) = ui.allocate_exact_size(vec2(width, width * 9.0 / 16.0), Sense::hover());
+    let cell_size = vec2(rect.width() / columns as f32, rect.height() / rows as f32);
+
+    let dark_mode = ui.global_style().visuals.dark_mode;
+    let text_colour = if dark_mode {
+        Color32::WHITE
+    } else {
+        Color32::BLACK
+    };
+    let (good_colour, bad_colour) = if dark_mode {
+        (Color32::DARK_GREEN, Color32::DARK_RED)
+    } else {
+        (Color32::LIGHT_GREEN, Color32::LIGHT_RED)
+    };
+
+    let painter = ui.painter_at(rect);
+    for (cell, deviation) in deviations.iter().enumerate() {
+        let (column, row) = uniformity.cell_coords(cell);
+        let min = rect.min + vec2(column as f32 * cell_size.x, row as f32 * cell_size.y);
+        let cell_rect = Rect::from_min_size(min, cell_size);
+
+        let (fill, text) = if let Some(deviation) = deviation {
+            let (t, text) = cell_value(deviation);
+            (
+                good_colour.lerp_to_gamma(bad_colour, t.min(1.0) as f32),
+                text,
+            )
+        } else {
+            (Color32::GRAY, "-".to_string())
+        };
+
+        let stroke = if uniformity.started && cell == uniformity.current_cell {
+            Stroke::new(2.0, text_colour)
+        } else {
+            Stroke::new(1.0, Color32::BLACK)
+        };
+
+        painter.rect(
+            cell_rect.shrink(1.0),
+            0.0,
+            fill,
+            stroke,
+            egui::StrokeKind::Inside,
+        );
+        painter.text(
+            cell_rect.center(),
+            Align2::CENTER_CENTER,
+            text,
+            FontId::proportional(12.0),
+            text_colour,
+        );
+    }
+
+    ui.add_space(4.0);
+}
@@ -0,0 +1,122 @@
+use eframe::{
+    egui::{self, Layout, RichText, Ui},
+    emath::Align,
+};
+use strum::IntoEnumIterator;
+
+use crate::calibration::{ReadingResult, WhiteBalanceAdvice, WhiteBalanceControls};
+
+use super::{CalibrationState, pass_fail_colours};
+
+pub fn draw_white_balance_ui(
+    ui: &mut Ui,
+    cal_state: &mut CalibrationState,
+    results: &[ReadingResult],
+) {
+    ui.horizontal(|ui| {
+        ui.heading("White balance");
+        ui.checkbox(&mut cal_state.show_white_balance, "Show");
+
+        if cal_state.show_white_balance {
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                let mut tolerance = cal_state.white_balance_tolerance * 100.0;
+                let res = ui.add(
+                    egui::DragValue::new(&mut tolerance)
+                        .speed(0.05)
+                        .range(0.1..=10.0)
+                        .max_decimals(2)
+                        .suffix(" %"),
+                );
+                if res.changed() {
+                    cal_state.white_balance_tolerance = tolerance / 100.0;
+                }
+                ui.label("Tolerance");
+
+                egui::ComboBox::from_id_salt(egui::Id::new("cal_white_balance_controls"))
+                    .selected_text(cal_state.white_balance_controls.as_ref())
+                    .show_ui(ui, |ui| {
+                        for controls in WhiteBalanceControls::iter() {
+                            ui.selectable_value(
+                                &mut cal_state.white_balance_controls,
+                                controls,
+                                controls.as_ref(),
+                            );
+                        }
+                    });
+            });
+        }
+    });
+
+    if !cal_state.show_white_balance {
+        return;
+    }
+
+    let controls = cal_state.white_balance_controls;
+    let tolerance = cal_state.white_balance_tolerance;
+    let advices: Vec<_> = results
+        .iter()
+        .filter_map(|res| WhiteBalanceAdvice::from_result(res, controls))
+        .collect();
+
+    if advices.is_empty() {
+        ui.label("Measure greyscale patches for white balance guidance");
+        return;
+    }
+
+    let (ok_colour, adjust_colour) = pass_fail_colours(ui);
+
+    // The selected patch gets re-read while adjusting, so it takes priority
+    let selected_advice = cal_state
+        .internal_gen
+        .selected_patch()
+        .and_then(|patch| patch.result)
+        .and_then(|res| WhiteBalanceAdvice::from_result(&res, controls));
+    let (next_step, source) = match selected_advice {
+        Some(advice) => (advice.next_step(tolerance), "selected patch"),
+        None => (
+            WhiteBalanceAdvice::overall_next_step(&advices, tolerance),
+            "all patches",
+        ),
+    };
+
+    ui.indent("cal_white_balance_indent", |ui| {
+        ui.horizontal(|ui| {
+            ui.label(format!("Next step ({source}):"));
+            match next_step {
+                Some(step) => ui.label(
+                    RichText::new(step.to_string())
+                        .strong()
+                        .color(adjust_colour),
+                ),
+                None => ui.label(RichText::new("Balanced").strong().color(ok_colour)),
+            };
+        });
+
+        egui::Grid::new("cal_white_balance_grid")
+            .spacing([16.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for label in ["Stimulus", "Control", "Red", "Green", "Blue"] {
+                    ui.strong(label);
+                }
+                ui.end_row();
+
+                for advice in advices.iter() {
+                    ui.label(format!("{:.1} %", advice.stimulus * 100.0));
+                    ui.label(advice.control.to_string());
+
+                    for adjustment in advice.adjustments {
+                        let colour = if adjustment.abs() > tolerance {
+                            adjust_colour
+                        } else {
+                            ok_colour
+                        };
+                        ui.label(
+                            RichText::new(format!("{:+.2} %", adjustment * 100.0)).color(colour),
+                        );
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+}
@@ -0,0 +1,122 @@
+use eframe::egui::{self, Ui};
+
+use crate::pgen::controller::{PGenInfo, PGenOutputConfig, SavedDevice};
+
+use super::PGenApp;
+
+pub fn add_saved_devices_ui(app: &mut PGenApp, ui: &mut Ui) {
+    let connected = app.state.connected_state.connected;
+    let current_socket = app.state.socket_addr;
+
+    let mut changed = false;
+    let mut switch_to = None;
+    let mut remove_idx = None;
+
+    egui::CollapsingHeader::new("Saved devices")
+        .id_salt("saved_devices_header")
+        .show(ui, |ui| {
+            let is_saved = app.state.saved_device().is_some();
+            if ui
+                .add_enabled(!is_saved, egui::Button::new("Save current device"))
+                .clicked()
+            {
+                let name = format!("PGenerator {}", app.state.saved_devices.len() + 1);
+                app.state.saved_devices.push(SavedDevice {
+                    name,
+                    socket_addr: current_socket,
+                    last_info: app.state.pgen_info.clone(),
+                    preferred_config: None,
+                });
+                changed = true;
+            }
+
+            let current_config = connected
+                .then(|| app.state.pgen_info.as_ref().map(|e| &e.output_config))
+                .flatten();
+
+            egui::Grid::new("saved_devices_grid")
+                .spacing([8.0, 4.0])
+                .show(ui, |ui| {
+                    for (i, device) in app.state.saved_devices.iter_mut().enumerate() {
+                        let is_current = device.socket_addr == current_socket;
+                        let is_active = is_current && connected;
+
+                        let name_res = ui
+                            .add(egui::TextEdit::singleline(&mut device.name).desired_width(120.0));
+                        changed |= name_res.lost_focus();
+
+                        ui.label(device.socket_addr.to_string());
+
+                        let info_str = device
+                            .last_info
+                            .as_ref()
+                            .map_or_else(|| "Never connected".to_string(), last_info_str);
+                        ui.label(info_str);
+
+                        let preferred_str = device
+                            .preferred_config
+                            .as_ref()
+                            .map_or_else(|| "No preferred config".to_string(), output_config_str);
+                        ui.label(preferred_str);
+
+                        if ui
+                            .add_enabled(!is_active && !app.processing, egui::Button::new("Switch"))
+                            .on_hover_text("Connect and apply the preferred config")
+                            .clicked()
+                        {
+                            switch_to = Some(device.socket_addr);
+                        }
+
+                        if ui
+                            .add_enabled(
+                                is_current && current_config.is_some(),
+                                egui::Button::new("Save config"),
+                            )
+                            .on_hover_text("Use the current output config as preferred")
+                            .clicked()
+                        {
+                            device.preferred_config = current_config.cloned();
+                            changed = true;
+                        }
+
+                        if device.preferred_config.is_some() && ui.button("Clear config").clicked()
+                        {
+                            device.preferred_config = None;
+                            changed = true;
+                        }
+
+                        if ui.button("Delete").clicked() {
+                            remove_idx = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+    if let Some(idx) = remove_idx {
+        app.state.saved_devices.remove(idx);
+        changed = true;
+    }
+    if changed {
+        app.update_controller_state();
+    }
+
+    if let Some(socket_addr) = switch_to {
+        app.switch_device(socket_addr);
+    }
+}
+
+fn last_info_str(info: &PGenInfo) -> String {
+    let mode = &info.current_display_mode;
+    format!(
+        "{}, {}x{} {}Hz",
+        info.version, mode.resolution.0, mode.resolution.1, mode.refresh_rate
+    )
+}
+
+fn output_config_str(cfg: &PGenOutputConfig) -> String {
+    format!(
+        "{} {} {} {}",
+        cfg.format, cfg.bit_depth, cfg.quant_range, cfg.dynamic_range
+    )
+}
@@ -12,6 +12,7 @@ use crate::{
     calibration::{TargetColorspace, xyz_to_cct},
     external::ExternalJobCmd,
     generators::internal::PatchListPreset,
+    pgen::ColorFormat,
     utils::rgb_10b_to_8b,
 };
 
@@ -107,6 +108,20 @@ pub fn add_internal_generator_ui(app: &mut PGenApp, ui: &mut Ui) {
             });
         });
 
+        let is_ycbcr_output = app
+            .state
+            .pgen_info
+            .as_ref()
+            .is_some_and(|info| info.output_config.format != ColorFormat::Rgb);
+        ui.add_enabled_ui(is_ycbcr_output, |ui| {
+            ui.checkbox(
+                &mut app.cal_state.internal_gen.show_ycbcr_codes,
+                "Show YCbCr code values",
+            )
+            .on_hover_text("Code values emitted by the PGenerator for the YCbCr output")
+            .on_disabled_hover_text("Output color format is RGB");
+        });
+
         ui.separator();
 
         let avail_height = ui.available_height();
@@ -280,6 +295,29 @@ fn add_target_config_ui(app: &mut PGenApp, ui: &mut Ui) {
         });
     });
 
+    let can_measure = app.state.connected_state.connected
+        && app.cal_state.spotread_started
+        && !app.processing
+        && !app.cal_state.measurement_in_progress();
+    ui.horizontal(|ui| {
+        ui.add_enabled_ui(can_measure, |ui| {
+            if ui
+                .button("Measure black and white")
+                .on_hover_text("Sets the target brightness from the display")
+                .clicked()
+            {
+                app.cal_state.target_luminance_readings.start(false);
+                app.calibration_send_measure_target_luminance_step();
+            }
+        });
+        ui.add_enabled_ui(!cal_started, |ui| {
+            ui.checkbox(
+                &mut app.cal_state.auto_target_luminance,
+                "Before each sweep",
+            );
+        });
+    });
+
     ui.horizontal(|ui| {
         ui.label("Target primaries");
 
@@ -302,18 +340,32 @@ fn add_target_config_ui(app: &mut PGenApp, ui: &mut Ui) {
 
 fn add_patch_list_table(app: &mut PGenApp, ui: &mut Ui, avail_height: f32) {
     let bit_depth = app.state.pattern_config.bit_depth as u8;
+    let limited_range = app.state.pattern_config.limited_range;
+    let ycbcr_encoding = app.shown_ycbcr_encoding();
 
+    let tolerance_grade = app.cal_state.tolerance_grade.grade();
     let internal_gen = &mut app.cal_state.internal_gen;
     let rows = &internal_gen.list;
+    let flagged_patches = &app.cal_state.drift_monitor.flagged_patches;
+    let (flagged_colour, failing_colour) = if ui.global_style().visuals.dark_mode {
+        (Color32::ORANGE, Color32::LIGHT_RED)
+    } else {
+        (Color32::from_rgb(204, 102, 0), Color32::DARK_RED)
+    };
 
     let patch_col = Column::auto().at_least(50.0);
-    TableBuilder::new(ui)
+    let mut table = TableBuilder::new(ui)
         .striped(true)
         .column(Column::auto().at_least(25.0))
         .column(patch_col)
         .column(patch_col)
         .column(patch_col)
-        .column(patch_col)
+        .column(patch_col);
+    if ycbcr_encoding.is_some() {
+        table = table.column(Column::auto().at_least(110.0));
+    }
+
+    table
         .resizable(true)
         .min_scrolled_height(300.0_f32.max(avail_height - 25.0))
         .sense(Sense::click())
@@ -323,6 +375,11 @@ fn add_patch_list_table(app: &mut PGenApp, ui: &mut Ui, avail_height: f32) {
                     ui.strong(label);
                 });
             }
+            if ycbcr_encoding.is_some() {
+                header.col(|ui| {
+                    ui.strong("YCbCr");
+                });
+            }
         })
         .body(|body| {
             body.rows(20.0, rows.len(), |mut row| {
@@ -336,14 +393,26 @@ fn add_patch_list_table(app: &mut PGenApp, ui: &mut Ui, avail_height: f32) {
                 let patch_colour = Color32::from_rgb(rgb_8b[0], rgb_8b[1], rgb_8b[2]);
 
                 row.col(|ui| {
-                    ui.label(i.to_string());
+                    let failures = tolerance_grade.and_then(|grade| grade.patch_failures(i));
+                    if let Some(failures) = failures {
+                        ui.label(RichText::new(i.to_string()).color(failing_colour))
+                            .on_hover_text(failures.join("\n"));
+                    } else if flagged_patches.contains(&i) {
+                        ui.label(RichText::new(i.to_string()).color(flagged_colour))
+                            .on_hover_text("Measured while the display was drifting");
+                    } else {
+                        ui.label(i.to_string());
+                    }
                 });
                 row.col(|ui| {
                     ui.add_space(2.0);
-                    let (rect, _) = ui.allocate_exact_size(
+                    let (rect, res) = ui.allocate_exact_size(
                         vec2(ui.available_width(), ui.available_height() - 2.0),
                         Sense::hover(),
                     );
+                    if let Some(target) = patch.target {
+                        res.on_hover_text(format!("Target {target}"));
+                    }
                     ui.painter().rect(
                         rect,
                         0.0,
@@ -357,6 +426,19 @@ fn add_patch_list_table(app: &mut PGenApp, ui: &mut Ui, avail_height: f32) {
                         ui.label(format!("{c}"));
                     });
                 }
+                if let Some(encoding) = ycbcr_encoding.as_ref() {
+                    let ycc = encoding.encode(rgb_orig, limited_range, bit_depth);
+                    let warnings = ycc.warnings(rgb_orig);
+
+                    row.col(|ui| {
+                        if warnings.is_empty() {
+                            ui.label(ycc.to_string());
+                        } else {
+                            ui.label(RichText::new(ycc.to_string()).color(flagged_colour))
+                                .on_hover_text(warnings.join("\n"));
+                        }
+                    });
+                }
 
                 if row.response().clicked() {
                     if internal_gen.selected_idx.is_some_and(|si| i == si) {
@@ -379,7 +461,7 @@ fn add_patches_info_right_side(app: &mut PGenApp, ui: &mut Ui) {
         pgen_connected
             && app.cal_state.spotread_started
             && !app.processing
-            && !cal_started
+            && !app.cal_state.measurement_in_progress()
             && !internal_gen.list.is_empty()
     };
     let has_selected_patch = app.cal_state.internal_gen.selected_idx.is_some();
@@ -393,8 +475,14 @@ fn add_patches_info_right_side(app: &mut PGenApp, ui: &mut Ui) {
                     internal_gen.auto_advance = true;
                     internal_gen.selected_idx = Some(0);
                 }
+                app.cal_state.drift_monitor.begin_sweep();
 
-                app.calibration_send_measure_selected_patch();
+                if app.cal_state.auto_target_luminance {
+                    app.cal_state.target_luminance_readings.start(true);
+                    app.calibration_send_measure_target_luminance_step();
+                } else {
+                    app.calibration_send_measure_sweep_start();
+                }
             }
             if has_selected_patch {
                 if ui.button("Measure selected patch").clicked() {
@@ -11,20 +11,25 @@ use crate::{
     calibration::ReadingResult,
     external::ExternalJobCmd,
     generators::{GeneratorState, GeneratorType},
-    pgen::controller::{PGenControllerCmd, PGenControllerState},
+    pgen::{
+        controller::{PGenControllerCmd, PGenControllerState},
+        discovery::DiscoveredDevice,
+    },
 };
 
 mod calibration;
+mod devices_ui;
 pub mod eframe_app;
 mod external_generator_ui;
 mod internal_generator_ui;
+mod output_presets_ui;
 pub mod pgen_app;
 pub mod read_file_ops;
 pub mod utils;
 
 pub use pgen_app::PGenApp;
 
-pub use calibration::{CalibrationState, compute_cie_chromaticity_diagram_worker};
+pub use calibration::{CalibrationState, CieDiagram};
 
 #[derive(Debug)]
 pub struct PGenAppContext {
@@ -32,6 +37,8 @@ pub struct PGenAppContext {
 
     pub controller_tx: Sender<PGenControllerCmd>,
     pub external_tx: Sender<ExternalJobCmd>,
+    /// For background jobs started from the UI
+    pub app_tx: Sender<PGenAppUpdate>,
 }
 
 pub enum PGenAppUpdate {
@@ -45,13 +52,28 @@ pub enum PGenAppUpdate {
     DoneProcessing,
     SpotreadStarted(bool),
     SpotreadRes(Option<ReadingResult>),
-    CieDiagramReady(ColorImage),
+    CieDiagramReady(CieDiagram, ColorImage),
     ReadFileResponse(ReadFileType, PathBuf),
+    DiscoveredDevices(Vec<DiscoveredDevice>),
+    Lut3dExported(SaveFileType, Option<String>),
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum ReadFileType {
     PatchList,
+    Session,
+    ComparisonSession,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SaveFileType {
+    ArgyllCal,
+    Cube1d,
+    Cube3d,
+    EeColor,
+    Lustre3dl,
+    HtmlReport,
+    Session,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -82,12 +104,52 @@ impl ReadFileType {
     pub fn title(&self) -> &'static str {
         match self {
             Self::PatchList => "Patch list file",
+            Self::Session => "Measurement session",
+            Self::ComparisonSession => "Comparison session",
         }
     }
 
     pub fn filters(&self) -> &'static [(&'static str, &'static [&'static str])] {
         match self {
             Self::PatchList => &[("CSV", &["csv"]), ("Text", &["txt"])],
+            Self::Session | Self::ComparisonSession => &[("CSV", &["csv"])],
+        }
+    }
+}
+
+impl SaveFileType {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::ArgyllCal => "ArgyllCMS calibration file",
+            Self::Cube1d => "1D LUT file",
+            Self::Cube3d => "3D LUT file",
+            Self::EeColor => "eeColor 3D LUT file",
+            Self::Lustre3dl => "madVR 3D LUT file",
+            Self::HtmlReport => "Calibration report",
+            Self::Session => "Measurement session",
+        }
+    }
+
+    pub fn filters(&self) -> &'static [(&'static str, &'static [&'static str])] {
+        match self {
+            Self::ArgyllCal => &[("ArgyllCMS .cal", &["cal"])],
+            Self::Cube1d | Self::Cube3d => &[("Cube LUT", &["cube"])],
+            Self::EeColor => &[("eeColor LUT", &["txt"])],
+            Self::Lustre3dl => &[("3DL LUT", &["3dl"])],
+            Self::HtmlReport => &[("HTML", &["html"])],
+            Self::Session => &[("CSV", &["csv"])],
+        }
+    }
+
+    pub fn default_file_name(&self) -> &'static str {
+        match self {
+            Self::ArgyllCal => "greyscale.cal",
+            Self::Cube1d => "greyscale_1d.cube",
+            Self::Cube3d => "calibration_3d.cube",
+            Self::EeColor => "calibration_eecolor.txt",
+            Self::Lustre3dl => "calibration_madvr.3dl",
+            Self::HtmlReport => "calibration_report.html",
+            Self::Session => "session.csv",
         }
     }
 }
@@ -0,0 +1,122 @@
+use eframe::egui::{self, Ui};
+
+use crate::pgen::controller::{OutputPreset, PGenControllerCmd};
+
+use super::PGenApp;
+
+pub fn add_output_presets_ui(app: &mut PGenApp, ui: &mut Ui) {
+    let Some(pgen_info) = app.state.pgen_info.as_ref() else {
+        return;
+    };
+
+    let mut changed = false;
+
+    egui::CollapsingHeader::new("Output presets")
+        .id_salt("output_presets_header")
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let selected_name = app
+                    .output_preset_idx
+                    .and_then(|idx| app.state.output_presets.get(idx))
+                    .map_or("None", |preset| preset.name.as_str())
+                    .to_owned();
+
+                egui::ComboBox::from_id_salt(egui::Id::new("output_preset"))
+                    .width(250.0)
+                    .selected_text(selected_name)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut app.output_preset_idx, None, "None");
+                        for (i, preset) in app.state.output_presets.iter().enumerate() {
+                            ui.selectable_value(&mut app.output_preset_idx, Some(i), &preset.name);
+                        }
+                    });
+
+                if ui
+                    .button("Save current")
+                    .on_hover_text("Save the current output config and display mode")
+                    .clicked()
+                {
+                    let cfg = &pgen_info.output_config;
+                    let mode = pgen_info.current_display_mode;
+                    let name = format!(
+                        "{} {} {} {} {}x{}",
+                        cfg.dynamic_range,
+                        cfg.format,
+                        cfg.bit_depth,
+                        cfg.quant_range,
+                        mode.resolution.0,
+                        mode.resolution.1
+                    );
+
+                    app.state
+                        .output_presets
+                        .push(OutputPreset::from_info(name, pgen_info));
+                    app.output_preset_idx = Some(app.state.output_presets.len() - 1);
+                    changed = true;
+                }
+
+                if let Some(idx) = app.output_preset_idx
+                    && ui.button("Delete").clicked()
+                {
+                    app.state.output_presets.remove(idx);
+                    app.output_preset_idx = None;
+                    changed = true;
+                }
+            });
+
+            let Some(preset) = app
+                .output_preset_idx
+                .and_then(|idx| app.state.output_presets.get_mut(idx))
+            else {
+                return;
+            };
+
+            ui.horizontal(|ui| {
+                ui.label("Name");
+                let name_res =
+                    ui.add(egui::TextEdit::singleline(&mut preset.name).desired_width(250.0));
+                changed |= name_res.lost_focus();
+            });
+
+            let diffs = preset.differences(pgen_info);
+            if diffs.is_empty() {
+                ui.label("Device already matches the preset");
+                return;
+            }
+
+            egui::Grid::new("output_preset_diff_grid")
+                .striped(true)
+                .spacing([16.0, 4.0])
+                .show(ui, |ui| {
+                    ui.strong("Setting");
+                    ui.strong("Current");
+                    ui.strong("Preset");
+                    ui.end_row();
+
+                    for diff in diffs.iter() {
+                        ui.label(diff.setting);
+                        ui.label(&diff.current);
+                        ui.label(&diff.new);
+                        ui.end_row();
+                    }
+                });
+
+            let num_commands: usize = diffs.iter().map(|diff| diff.commands.len()).sum();
+            if ui
+                .add_enabled(num_commands > 0, egui::Button::new("Apply preset"))
+                .on_hover_text(format!(
+                    "Sends {num_commands} commands and restarts the PGenerator software"
+                ))
+                .clicked()
+            {
+                app.ctx
+                    .controller_tx
+                    .try_send(PGenControllerCmd::ApplyOutputPreset(preset.clone()))
+                    .ok();
+            }
+        });
+
+    if changed {
+        app.update_controller_state();
+    }
+}
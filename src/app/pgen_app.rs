@@ -1,31 +1,38 @@
 use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
+use std::time::Instant;
 
 use eframe::egui::{self, Context, Sense, Ui};
-use eframe::epaint::{Stroke, Vec2};
+use eframe::epaint::{Color32, Stroke, Vec2};
 use strum::IntoEnumIterator;
 use tokio::sync::mpsc::{Receiver, Sender};
 
 use crate::app::calibration::handle_spotread_result;
-use crate::app::read_file_ops::parse_patch_list_csv_file;
-use crate::calibration::CalibrationTarget;
+use crate::app::read_file_ops::{parse_patch_list_csv_file, parse_session_csv_file};
+use crate::calibration::{CalibrationTarget, DisplayCapability, DriftReadingKind, ExplicitTarget};
 use crate::external::ExternalJobCmd;
 use crate::generators::{GeneratorState, GeneratorType};
 use crate::pgen::commands::{PGenCommand, PGenSetConfCommand};
-use crate::pgen::controller::{PGenControllerCmd, PGenControllerState, PGenInfo, PGenOutputConfig};
-use crate::pgen::pattern_config::{TestPatternPosition, TestPatternSize};
+use crate::pgen::controller::{
+    ConnectionStatus, PGenControllerCmd, PGenControllerState, PGenInfo, PGenOutputConfig,
+};
+use crate::pgen::discovery::DiscoveredDevice;
+use crate::pgen::pattern_config::{PGenPatternConfig, TestPatternPosition, TestPatternSize};
+use crate::pgen::ycbcr::YCbCrEncoding;
 use crate::pgen::{
     BitDepth, ColorFormat, Colorimetry, DoviMapMode, DynamicRange, HdrEotf, Primaries, QuantRange,
 };
 use crate::spotread::SpotreadReadingConfig;
 use crate::utils::{
-    compute_rgb_range, rgb_10b_to_8b, rgb_to_float, round_colour, scale_8b_rgb_to_10b,
-    scale_pattern_config_rgb_values,
+    compute_rgb_range, get_rgb_real_range, pattern_cfg_set_colour_from_float_level, rgb_10b_to_8b,
+    rgb_to_float, round_colour, scale_8b_rgb_to_10b, scale_pattern_config_rgb_values,
 };
 
 use super::calibration::add_calibration_ui;
+use super::devices_ui::add_saved_devices_ui;
 use super::external_generator_ui::add_external_generator_ui;
 use super::internal_generator_ui::add_internal_generator_ui;
+use super::output_presets_ui::add_output_presets_ui;
 use super::utils::is_dragvalue_finished;
 pub use super::{PGenAppContext, PGenAppSavedState, PGenAppUpdate, calibration::CalibrationState};
 use super::{ReadFileType, status_color_active};
@@ -38,6 +45,10 @@ pub struct PGenApp {
     pub generator_state: GeneratorState,
     pub cal_state: CalibrationState,
 
+    pub discovering: bool,
+    pub discovered_devices: Vec<DiscoveredDevice>,
+    pub output_preset_idx: Option<usize>,
+
     pub processing: bool,
     pub requested_close: bool,
     pub allowed_to_close: bool,
@@ -48,11 +59,13 @@ impl PGenApp {
         rx: Receiver<PGenAppUpdate>,
         controller_tx: Sender<PGenControllerCmd>,
         external_tx: Sender<ExternalJobCmd>,
+        app_tx: Sender<PGenAppUpdate>,
     ) -> Self {
         let ctx = PGenAppContext {
             rx,
             controller_tx,
             external_tx,
+            app_tx,
         };
 
         let state: PGenControllerState = Default::default();
@@ -65,6 +78,9 @@ impl PGenApp {
             generator_type: Default::default(),
             generator_state: Default::default(),
             cal_state: Default::default(),
+            discovering: Default::default(),
+            discovered_devices: Default::default(),
+            output_preset_idx: Default::default(),
             processing: Default::default(),
             requested_close: Default::default(),
             allowed_to_close: Default::default(),
@@ -141,15 +157,31 @@ impl PGenApp {
                 PGenAppUpdate::SpotreadRes(result) => {
                     handle_spotread_result(self, result);
                 }
-                PGenAppUpdate::CieDiagramReady(image) => {
-                    self.cal_state.set_cie_texture(ui, image);
+                PGenAppUpdate::CieDiagramReady(kind, image) => {
+                    self.cal_state.set_cie_texture(ui, kind, image);
                 }
                 PGenAppUpdate::ReadFileResponse(file_type, data) => {
                     self.handle_read_file_response(file_type, data);
                 }
+                PGenAppUpdate::DiscoveredDevices(devices) => {
+                    self.discovering = false;
+                    self.discovered_devices = devices;
+                }
+                PGenAppUpdate::Lut3dExported(file_type, contents) => {
+                    self.cal_state.exporting_lut = false;
+
+                    if let Some(contents) = contents {
+                        self.ctx
+                            .external_tx
+                            .try_send(ExternalJobCmd::SaveFile(file_type, contents))
+                            .ok();
+                    }
+                }
             }
         }
 
+        self.check_drift_monitor_schedule(ui.ctx());
+
         let processing =
             self.processing || self.generator_state.listening || self.cal_state.spotread_started;
         if self.requested_close && !processing {
@@ -241,25 +273,48 @@ impl PGenApp {
                     }
                 }
             }
+
+            if ui
+                .add_enabled(!self.discovering, egui::Button::new("Discover"))
+                .on_hover_text("Search the local network for PGenerator devices")
+                .clicked()
+            {
+                self.discovering = true;
+                self.discovered_devices.clear();
+                self.ctx
+                    .controller_tx
+                    .try_send(PGenControllerCmd::DiscoverDevices)
+                    .ok();
+            }
+            if self.discovering {
+                ui.spinner();
+            }
         });
 
+        self.add_discovered_devices(ui);
+        add_saved_devices_ui(self, ui);
+
         ui.add_enabled_ui(!self.processing, |ui| {
             egui::Grid::new("prefs_grid")
                 .spacing([8.0, 4.0])
                 .show(ui, |ui| {
                     ui.label("Status");
 
-                    let status_str = if connected {
-                        "Connected"
-                    } else if let Some(err) = &self.state.connected_state.error {
-                        err.as_str()
-                    } else {
-                        "Not connected"
+                    let status = self.state.connection_status;
+                    let error = self.state.connected_state.error.as_ref();
+                    let status_res = match error {
+                        Some(err) if status == ConnectionStatus::Disconnected => ui.label(err),
+                        _ => ui.label(status.to_string()),
                     };
+                    if let Some(err) = error.filter(|_| status.is_recovering()) {
+                        status_res.on_hover_text(err);
+                    }
 
-                    ui.label(status_str);
-
-                    let status_color = status_color_active(ui, connected);
+                    let status_color = if status.is_recovering() {
+                        Color32::from_rgb(255, 153, 0)
+                    } else {
+                        status_color_active(ui, connected)
+                    };
                     let (res, painter) = ui.allocate_painter(Vec2::new(16.0, 16.0), Sense::hover());
                     painter.circle(res.rect.center(), 8.0, status_color, Stroke::NONE);
 
@@ -270,6 +325,13 @@ impl PGenApp {
                             .ok();
                     }
 
+                    if status.is_recovering() && ui.button("Stop reconnecting").clicked() {
+                        self.ctx
+                            .controller_tx
+                            .try_send(PGenControllerCmd::Disconnect)
+                            .ok();
+                    }
+
                     if connected {
                         if ui.button("Disconnect").clicked() {
                             self.ctx
@@ -307,6 +369,56 @@ impl PGenApp {
         });
     }
 
+    fn add_discovered_devices(&mut self, ui: &mut Ui) {
+        if self.discovered_devices.is_empty() {
+            return;
+        }
+
+        let mut selected_socket = None;
+        egui::Grid::new("discovered_devices_grid")
+            .spacing([8.0, 4.0])
+            .show(ui, |ui| {
+                for device in self.discovered_devices.iter() {
+                    ui.label(device.socket_addr.to_string());
+                    ui.label(format!("Version: {}", device.version));
+
+                    let current = self.state.connected_state.connected
+                        && self.state.socket_addr == device.socket_addr;
+                    if ui
+                        .add_enabled(!current && !self.processing, egui::Button::new("Connect"))
+                        .clicked()
+                    {
+                        selected_socket = Some(device.socket_addr);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(socket_addr) = selected_socket {
+            self.switch_device(socket_addr);
+        }
+    }
+
+    /// Conversion of the patterns, when shown and the output is YCbCr
+    pub(crate) fn shown_ycbcr_encoding(&self) -> Option<YCbCrEncoding> {
+        self.cal_state
+            .internal_gen
+            .show_ycbcr_codes
+            .then_some(self.state.pgen_info.as_ref())
+            .flatten()
+            .and_then(|info| YCbCrEncoding::from_output_config(&info.output_config))
+    }
+
+    pub(crate) fn switch_device(&mut self, socket_addr: SocketAddr) {
+        self.editing_socket = (socket_addr.ip().to_string(), socket_addr.port().to_string());
+        self.state.socket_addr = socket_addr;
+
+        self.ctx
+            .controller_tx
+            .try_send(PGenControllerCmd::SwitchDevice(socket_addr))
+            .ok();
+    }
+
     fn add_output_info(&mut self, ui: &mut Ui) {
         let output_config = self
             .state
@@ -316,15 +428,46 @@ impl PGenApp {
             .flatten();
 
         if let Some(pgen_info) = output_config {
+            let results = self.cal_state.internal_gen.results();
+            let measured = DisplayCapability::from_results(&results);
+
             ui.horizontal(|ui| {
                 Self::add_base_output_config(&self.ctx, pgen_info, ui);
 
                 let output_cfg = &mut pgen_info.output_config;
                 ui.add_enabled_ui(output_cfg.dynamic_range != DynamicRange::Dovi, |ui| {
-                    Self::add_hdr_output_config(&self.ctx, output_cfg, ui);
+                    Self::add_hdr_output_config(
+                        &self.ctx,
+                        output_cfg,
+                        measured,
+                        &mut self.cal_state.hdr_meta_incl_primaries,
+                        ui,
+                    );
                 });
             });
 
+            for (cmd, e) in self.state.set_conf_errors.iter() {
+                let error_colour = status_color_active(ui, false);
+                ui.colored_label(
+                    error_colour,
+                    format!("Failed setting {cmd} to {}: {e}", cmd.value()),
+                );
+            }
+
+            for mismatch in self.state.config_mismatches.iter() {
+                let cmd = mismatch.command;
+                let error_colour = status_color_active(ui, false);
+                ui.colored_label(
+                    error_colour,
+                    format!(
+                        "Device ignored {cmd} = {}, reports {}",
+                        cmd.value(),
+                        mismatch.reported
+                    ),
+                );
+            }
+
+            add_output_presets_ui(self, ui);
             ui.separator();
         }
     }
@@ -527,7 +670,13 @@ impl PGenApp {
         });
     }
 
-    fn add_hdr_output_config(ctx: &PGenAppContext, output_cfg: &mut PGenOutputConfig, ui: &mut Ui) {
+    fn add_hdr_output_config(
+        ctx: &PGenAppContext,
+        output_cfg: &mut PGenOutputConfig,
+        measured: Option<DisplayCapability>,
+        incl_primaries: &mut bool,
+        ui: &mut Ui,
+    ) {
         ui.vertical(|ui| {
             let hdr = &mut output_cfg.hdr_meta;
             ui.heading("HDR metadata / DRM infoframe");
@@ -654,6 +803,31 @@ impl PGenApp {
                             }
                         });
                         ui.end_row();
+
+                        ui.checkbox(incl_primaries, "Primaries");
+                        ui.add_enabled_ui(measured.is_some(), |ui| {
+                            ui.vertical_centered_justified(|ui| {
+                                let res = ui
+                                    .button("Fill from measurements")
+                                    .on_hover_text(
+                                        "Set max/min MDL from the measured peak white and black, \
+                                        then restart the PGenerator",
+                                    )
+                                    .on_disabled_hover_text("Measure 0% and 100% white first");
+
+                                if let Some(measured) = measured.filter(|_| res.clicked()) {
+                                    let commands =
+                                        measured.apply_to_hdr_metadata(hdr, *incl_primaries);
+                                    ctx.controller_tx
+                                        .try_send(PGenControllerCmd::MultipleSetConfCommands(
+                                            commands,
+                                        ))
+                                        .ok();
+                                    restart_pgenerator_sw(ctx);
+                                }
+                            });
+                        });
+                        ui.end_row();
                     });
             });
         });
@@ -792,6 +966,37 @@ impl PGenApp {
                 });
                 ui.end_row();
 
+                if let Some(encoding) = self.shown_ycbcr_encoding() {
+                    let pattern_cfg = &self.state.pattern_config;
+                    let ycc = encoding.encode(
+                        pattern_cfg.patch_colour,
+                        pattern_cfg.limited_range,
+                        old_depth,
+                    );
+                    let warnings = ycc.warnings(pattern_cfg.patch_colour);
+
+                    let format_res = ui.label(format!("{} codes", encoding.format));
+                    if encoding.format == ColorFormat::YCbCr422 {
+                        format_res.on_hover_text(
+                            "Chroma is shared by horizontal pixel pairs, only patch edges are affected",
+                        );
+                    }
+                    ui.label(format!(
+                        "{} {}-bit",
+                        encoding.quant_range, encoding.bit_depth
+                    ));
+                    if warnings.is_empty() {
+                        ui.label(ycc.to_string());
+                    } else {
+                        let warning_colour = status_color_active(ui, false);
+                        ui.colored_label(
+                            warning_colour,
+                            format!("{ycc} ({})", warnings.join(", ")),
+                        );
+                    }
+                    ui.end_row();
+                }
+
                 ui.label("Background colour");
                 ui.centered_and_justified(|ui| {
                     ui.color_edit_button_srgb(&mut bg_rgb);
@@ -918,7 +1123,7 @@ impl PGenApp {
         }
     }
 
-    fn update_controller_state(&self) {
+    pub(crate) fn update_controller_state(&self) {
         self.ctx
             .controller_tx
             .try_send(PGenControllerCmd::UpdateState(self.state.clone()))
@@ -971,44 +1176,232 @@ impl PGenApp {
             let mut pattern_cfg = self.state.pattern_config;
             pattern_cfg.patch_colour = patch.rgb;
 
-            let ref_rgb = rgb_to_float(
-                pattern_cfg.patch_colour,
-                pattern_cfg.limited_range,
-                pattern_cfg.bit_depth as u8,
-            );
-            let ref_rgb = round_colour(ref_rgb);
+            // Only insert patterns if measuring multiple patches with auto advance
+            let insert_patterns = self.cal_state.internal_gen.auto_advance;
+            self.calibration_send_measure_with_target(pattern_cfg, insert_patterns, patch.target);
+        }
+    }
 
-            let target = CalibrationTarget {
-                min_y: self.cal_state.min_y,
-                max_y: self.cal_state.max_y,
-                eotf: self.cal_state.eotf,
-                colorspace: self.cal_state.target_csp,
-                max_hdr_mdl: self.cal_state.max_hdr_mdl,
+    /// Measures the next window size of the ABL sweep, returns false once all sizes are done
+    pub fn calibration_send_measure_abl_step(&mut self) -> bool {
+        let Some(size) = self.cal_state.abl_sweep.next_size() else {
+            return false;
+        };
 
-                ref_rgb,
-            };
+        // The controller uses its own state for the patch size and position
+        self.state.pattern_config.preset_size = size;
+        self.state.set_pattern_size_and_pos_from_resolution();
+        self.update_controller_state();
 
-            let mut config = SpotreadReadingConfig {
-                target,
-                pattern_cfg,
-                pattern_insertion_cfg: self.cal_state.internal_gen.pattern_insertion_cfg,
-            };
+        let mut pattern_cfg = self.state.pattern_config;
+        pattern_cfg_set_colour_from_float_level(&mut pattern_cfg, self.cal_state.abl_sweep.level);
 
-            // Only insert patterns if measuring multiple patches with auto advance
-            if !self.cal_state.internal_gen.auto_advance {
-                config.pattern_insertion_cfg.enabled = false;
-            }
+        let (black, _) = get_rgb_real_range(pattern_cfg.limited_range, pattern_cfg.bit_depth as u8);
+        pattern_cfg.background_colour = [black; 3];
 
-            self.ctx
-                .external_tx
-                .try_send(ExternalJobCmd::SpotreadMeasure(config))
-                .ok();
+        self.calibration_send_measure(pattern_cfg, false);
+
+        true
+    }
+
+    /// Displays the patch of the current uniformity cell, for the probe to be placed
+    pub fn uniformity_show_current_cell(&mut self) {
+        let Some((width, height)) = self
+            .state
+            .pgen_info
+            .as_ref()
+            .map(|info| info.current_display_mode.resolution)
+        else {
+            return;
+        };
+
+        let uniformity = &self.cal_state.uniformity;
+        let (patch_size, position) =
+            uniformity.cell_patch_size_and_position(uniformity.current_cell, width, height);
+
+        let pattern_cfg = &mut self.state.pattern_config;
+        pattern_cfg.patch_size = patch_size;
+        pattern_cfg.position = position;
+        pattern_cfg_set_colour_from_float_level(pattern_cfg, uniformity.level);
+
+        let (black, _) = get_rgb_real_range(pattern_cfg.limited_range, pattern_cfg.bit_depth as u8);
+        pattern_cfg.background_colour = [black; 3];
+
+        self.update_controller_state();
+        self.ctx
+            .controller_tx
+            .try_send(PGenControllerCmd::SendCurrentPattern)
+            .ok();
+    }
+
+    pub fn calibration_send_measure_uniformity_cell(&mut self) {
+        self.cal_state.uniformity.measuring = true;
+        self.calibration_send_measure(self.state.pattern_config, false);
+    }
+
+    /// Ends the uniformity measurements and restores the previous pattern config
+    pub fn stop_uniformity(&mut self) {
+        if let Some(pattern_cfg) = self.cal_state.uniformity.stop() {
+            self.state.pattern_config = pattern_cfg;
+            self.update_controller_state();
         }
+
+        self.set_blank();
+    }
+
+    /// Starts a patch sweep, with the drift reference first when sweep checks are enabled
+    pub fn calibration_send_measure_sweep_start(&mut self) {
+        if self.cal_state.drift_monitor.needs_sweep_reference() {
+            self.calibration_send_measure_drift_reading(DriftReadingKind::SweepCheck);
+        } else {
+            self.calibration_send_measure_selected_patch();
+        }
+    }
+
+    /// Measures the drift reference patch, in the current window
+    pub fn calibration_send_measure_drift_reading(&mut self, kind: DriftReadingKind) {
+        let pattern_cfg = self.pattern_cfg_with_patch_level(self.cal_state.drift_monitor.level);
+
+        self.cal_state.drift_monitor.measuring = Some(kind);
+        self.calibration_send_measure(pattern_cfg, false);
+    }
+
+    /// Measures black or white for the target luminance, in the current window
+    pub fn calibration_send_measure_target_luminance_step(&self) {
+        let level = self.cal_state.target_luminance_readings.next_level();
+        let pattern_cfg = self.pattern_cfg_with_patch_level(level);
+
+        self.calibration_send_measure(pattern_cfg, false);
+    }
+
+    // Current pattern config with a greyscale patch
+    fn pattern_cfg_with_patch_level(&self, level: f64) -> PGenPatternConfig {
+        let mut pattern_cfg = self.state.pattern_config;
+        pattern_cfg_set_colour_from_float_level(&mut pattern_cfg, level);
+        pattern_cfg.background_colour = self.state.pattern_config.background_colour;
+
+        pattern_cfg
+    }
+
+    fn check_drift_monitor_schedule(&mut self, egui_ctx: &Context) {
+        let now = Instant::now();
+        let Some(wait) = self.cal_state.drift_monitor.time_until_next_reading(now) else {
+            return;
+        };
+
+        if !wait.is_zero() {
+            egui_ctx.request_repaint_after(wait);
+        } else if self.cal_state.spotread_started && !self.processing {
+            self.calibration_send_measure_drift_reading(DriftReadingKind::Monitor);
+        }
+    }
+
+    /// Measures the next additivity/contrast patch, returns false once all are done
+    pub fn calibration_send_measure_display_check_step(&mut self) -> bool {
+        let Some(step) = self.cal_state.display_checks.next_step() else {
+            return false;
+        };
+
+        // Windows are relative to the pattern config from before the checks
+        let mut pattern_cfg = self
+            .cal_state
+            .display_checks
+            .previous_pattern_cfg
+            .unwrap_or(self.state.pattern_config);
+        let resolution = self
+            .state
+            .pgen_info
+            .as_ref()
+            .map(|info| info.current_display_mode.resolution);
+        let size_and_pos = resolution
+            .and_then(|(width, height)| step.window().patch_size_and_position(width, height));
+        if let Some((patch_size, position)) = size_and_pos {
+            pattern_cfg.patch_size = patch_size;
+            pattern_cfg.position = position;
+        }
+
+        let (min, real_max) =
+            get_rgb_real_range(pattern_cfg.limited_range, pattern_cfg.bit_depth as u8);
+        let to_code = |c: f64| (c * real_max as f64).round() as u16 + min;
+        pattern_cfg.patch_colour = step.patch_rgb().to_array().map(to_code);
+        pattern_cfg.background_colour = [to_code(step.background()); 3];
+
+        // The controller uses its own state for the patch size and position
+        self.state.pattern_config = pattern_cfg;
+        self.update_controller_state();
+
+        self.calibration_send_measure(pattern_cfg, false);
+
+        true
+    }
+
+    /// Ends the display checks and restores the previous pattern config
+    pub fn stop_display_checks(&mut self) {
+        if let Some(pattern_cfg) = self.cal_state.display_checks.stop() {
+            self.state.pattern_config = pattern_cfg;
+            self.update_controller_state();
+        }
+
+        self.set_blank();
+    }
+
+    fn calibration_send_measure(&self, pattern_cfg: PGenPatternConfig, insert_patterns: bool) {
+        self.calibration_send_measure_with_target(pattern_cfg, insert_patterns, None);
+    }
+
+    fn calibration_send_measure_with_target(
+        &self,
+        pattern_cfg: PGenPatternConfig,
+        insert_patterns: bool,
+        explicit: Option<ExplicitTarget>,
+    ) {
+        let ref_rgb = rgb_to_float(
+            pattern_cfg.patch_colour,
+            pattern_cfg.limited_range,
+            pattern_cfg.bit_depth as u8,
+        );
+        let ref_rgb = round_colour(ref_rgb);
+
+        let target = CalibrationTarget {
+            min_y: self.cal_state.min_y,
+            max_y: self.cal_state.max_y,
+            eotf: self.cal_state.eotf,
+            colorspace: self.cal_state.target_csp,
+            max_hdr_mdl: self.cal_state.max_hdr_mdl,
+
+            ref_rgb,
+            explicit,
+        };
+
+        let mut config = SpotreadReadingConfig {
+            target,
+            pattern_cfg,
+            pattern_insertion_cfg: self.cal_state.internal_gen.pattern_insertion_cfg,
+        };
+        config.pattern_insertion_cfg.enabled &= insert_patterns;
+
+        self.ctx
+            .external_tx
+            .try_send(ExternalJobCmd::SpotreadMeasure(config))
+            .ok();
+    }
+
+    /// Ends the ABL sweep and restores the previous window size
+    pub fn stop_abl_sweep(&mut self) {
+        if let Some(size) = self.cal_state.abl_sweep.stop() {
+            self.state.pattern_config.preset_size = size;
+            self.state.set_pattern_size_and_pos_from_resolution();
+            self.update_controller_state();
+        }
+
+        self.set_blank();
     }
 
     fn handle_read_file_response(&mut self, file_type: ReadFileType, path: PathBuf) {
         match file_type {
             ReadFileType::PatchList => parse_patch_list_csv_file(self, path),
+            ReadFileType::Session => parse_session_csv_file(self, path, false),
+            ReadFileType::ComparisonSession => parse_session_csv_file(self, path, true),
         }
     }
 }
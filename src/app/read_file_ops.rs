@@ -1,44 +1,188 @@
 use std::path::PathBuf;
 
-use serde::Deserialize;
-
-use crate::generators::internal::InternalPattern;
+use crate::{
+    calibration::{ExplicitTarget, session_from_csv},
+    generators::internal::InternalPattern,
+};
 
 use super::PGenApp;
 
-#[derive(Deserialize)]
-struct CsvPatchRecord {
-    _idx: usize,
-    red: u16,
-    green: u16,
-    blue: u16,
-    _label: Option<String>,
-}
+// Index, red, green, blue
+const PATCH_RGB_FIELDS: usize = 4;
+// Kind and its three values
+const PATCH_TARGET_FIELDS: usize = 4;
 
 pub fn parse_patch_list_csv_file(app: &mut PGenApp, path: PathBuf) {
-    let reader = csv::ReaderBuilder::new().has_headers(false).from_path(path);
+    let file = match std::fs::File::open(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            log::error!("Failed loading patch list {}: {e}", path.display());
+            return;
+        }
+    };
 
-    if let Ok(mut rdr) = reader {
-        let pattern_iter = rdr
-            .deserialize::<CsvPatchRecord>()
-            .filter_map(Result::ok)
-            .map(Into::into);
+    let (patterns, errors) = patch_list_from_csv(file);
+    for e in errors.iter() {
+        log::warn!("Patch list {}: {e}", path.display());
+    }
 
-        app.cal_state.internal_gen.list.clear();
-        app.cal_state.internal_gen.list.extend(pattern_iter);
+    app.cal_state.internal_gen.list.clear();
+    app.cal_state.internal_gen.list.extend(patterns);
 
-        log::trace!(
-            "Patch list CSV loaded: {} patches.",
-            app.cal_state.internal_gen.list.len()
-        );
-    }
+    log::trace!(
+        "Patch list CSV loaded: {} patches.",
+        app.cal_state.internal_gen.list.len()
+    );
 }
 
-impl From<CsvPatchRecord> for InternalPattern {
-    fn from(record: CsvPatchRecord) -> Self {
-        Self {
-            rgb: [record.red, record.green, record.blue],
+/// Rows are `index,red,green,blue[,label][,kind,v1,v2,v3]`.
+/// Returns the patches along with the rejected rows and targets.
+fn patch_list_from_csv<R: std::io::Read>(rdr: R) -> (Vec<InternalPattern>, Vec<String>) {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(rdr);
+
+    let mut patterns = Vec::new();
+    let mut errors = Vec::new();
+
+    for record in rdr.records() {
+        // Blank lines are skipped by the reader, report the actual file line
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line());
+                errors.push(format!("line {line} rejected: {e}"));
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |p| p.line());
+
+        let rgb = record
+            .iter()
+            .skip(1)
+            .take(3)
+            .map(|v| v.trim().parse::<u16>().ok())
+            .collect::<Option<Vec<_>>>()
+            .filter(|rgb| rgb.len() == 3);
+        let Some(rgb) = rgb else {
+            errors.push(format!("line {line} rejected: invalid RGB"));
+            continue;
+        };
+
+        // The label is optional, the target is always the last fields
+        let extra = record.len().saturating_sub(PATCH_RGB_FIELDS);
+        let target = if extra >= PATCH_TARGET_FIELDS {
+            let fields: Vec<_> = record
+                .iter()
+                .skip(record.len() - PATCH_TARGET_FIELDS)
+                .collect();
+            let values = fields[1..]
+                .iter()
+                .map(|v| v.trim().parse::<f64>().ok())
+                .collect::<Option<Vec<_>>>();
+
+            let target = values.and_then(|v| ExplicitTarget::parse(fields[0], [v[0], v[1], v[2]]));
+            if target.is_none() {
+                errors.push(format!(
+                    "line {line}: invalid target `{}`, measuring against the stimulus",
+                    fields.join(",")
+                ));
+            }
+
+            target
+        } else {
+            if extra > 1 {
+                errors.push(format!("line {line}: incomplete target ignored"));
+            }
+
+            None
+        };
+
+        patterns.push(InternalPattern {
+            rgb: [rgb[0], rgb[1], rgb[2]],
+            target,
             ..Default::default()
+        });
+    }
+
+    (patterns, errors)
+}
+
+pub fn parse_session_csv_file(app: &mut PGenApp, path: PathBuf, comparison: bool) {
+    let session = std::fs::File::open(&path)
+        .map_err(Into::into)
+        .and_then(session_from_csv);
+
+    match session {
+        Ok(session) => {
+            log::trace!("Session CSV loaded: {} patches.", session.len());
+
+            let cal_state = &mut app.cal_state;
+            if comparison {
+                cal_state.comparison_results = session.into_iter().map(|e| e.1).collect();
+            } else {
+                let internal_gen = &mut cal_state.internal_gen;
+                internal_gen.selected_idx = None;
+                internal_gen.list.clear();
+                internal_gen
+                    .list
+                    .extend(session.into_iter().map(|(rgb, res)| InternalPattern {
+                        rgb,
+                        target: res.target.explicit,
+                        result: Some(res),
+                    }));
+            }
         }
+        Err(e) => log::error!("Failed loading session {}: {e}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calibration::{ExplicitTarget, ExplicitTargetKind};
+
+    use super::patch_list_from_csv;
+
+    #[test]
+    fn parse_patch_list() {
+        let csv = "\
+0,940,940,940,White
+1,64,64,64
+
+2,940,64,64,Red,xyY,0.64,0.33,21.3
+3,64,940,64,XYZ,35.8,71.5,11.9
+4,64,64,940,Blue,RGB,1,2,3
+5,512,a,512
+6,512,512,512,Grey,xyY
+";
+        let (patterns, errors) = patch_list_from_csv(csv.as_bytes());
+
+        assert_eq!(patterns.len(), 6);
+        assert_eq!(patterns[0].rgb, [940, 940, 940]);
+        assert!(patterns[0].target.is_none());
+        assert!(patterns[1].target.is_none());
+        assert_eq!(
+            patterns[2].target,
+            Some(ExplicitTarget {
+                kind: ExplicitTargetKind::XyY,
+                values: [0.64, 0.33, 21.3],
+            })
+        );
+        assert_eq!(
+            patterns[3].target.map(|t| t.kind),
+            Some(ExplicitTargetKind::Xyz)
+        );
+
+        // Unknown kind keeps the patch, bad RGB drops the row
+        assert_eq!(patterns[4].rgb, [64, 64, 940]);
+        assert!(patterns[4].target.is_none());
+        assert_eq!(patterns[5].rgb, [512, 512, 512]);
+
+        assert_eq!(errors.len(), 3);
+        // File lines, counting the blank one
+        assert!(errors[0].starts_with("line 6: invalid target"));
+        assert!(errors[1].starts_with("line 7 rejected"));
+        assert!(errors[2].starts_with("line 8: incomplete target"));
     }
 }
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::pgen::pattern_config::TestPatternSize;
+
+use super::ReadingResult;
+
+/// Measures the same stimulus at every window size, to find the ABL curve of the display
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AblSweep {
+    /// Stimulus level of the window, background is black
+    pub level: f64,
+
+    #[serde(skip)]
+    pub started: bool,
+    /// Stop after the current reading
+    #[serde(skip)]
+    pub stop_requested: bool,
+    #[serde(skip)]
+    pub results: Vec<(TestPatternSize, ReadingResult)>,
+    /// Window size before the sweep started, restored at the end
+    #[serde(skip)]
+    pub previous_size: Option<TestPatternSize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AblReport {
+    pub peak_size: TestPatternSize,
+    pub peak_y: f64,
+    pub full_field_y: Option<f64>,
+}
+
+impl AblSweep {
+    pub fn start(&mut self, current_size: TestPatternSize) {
+        self.started = true;
+        self.stop_requested = false;
+        self.results.clear();
+        self.previous_size = Some(current_size);
+    }
+
+    pub fn next_size(&self) -> Option<TestPatternSize> {
+        TestPatternSize::iter().nth(self.results.len())
+    }
+
+    /// Returns the size to restore
+    pub fn stop(&mut self) -> Option<TestPatternSize> {
+        self.started = false;
+        self.previous_size.take()
+    }
+
+    /// Window area in percent against luminance
+    pub fn curve(&self) -> Vec<[f64; 2]> {
+        self.results
+            .iter()
+            .map(|(size, res)| [size.float() * 100.0, res.xyy[2]])
+            .collect()
+    }
+
+    pub fn report(&self) -> Option<AblReport> {
+        let (peak_size, peak_y) = self
+            .results
+            .iter()
+            .map(|(size, res)| (*size, res.xyy[2]))
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        let full_field_y = self
+            .results
+            .iter()
+            .find(|(size, _)| *size == TestPatternSize::Percent100)
+            .map(|(_, res)| res.xyy[2]);
+
+        Some(AblReport {
+            peak_size,
+            peak_y,
+            full_field_y,
+        })
+    }
+}
+
+impl AblReport {
+    /// Luminance lost at full field relative to the peak
+    pub fn full_field_drop(&self) -> Option<f64> {
+        self.full_field_y
+            .filter(|_| self.peak_y > 0.0)
+            .map(|full_field_y| 1.0 - (full_field_y / self.peak_y))
+    }
+}
+
+impl Default for AblSweep {
+    fn default() -> Self {
+        Self {
+            level: 1.0,
+            started: false,
+            stop_requested: false,
+            results: Default::default(),
+            previous_size: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kolor_64::Vec3;
+
+    use crate::{calibration::ReadingResult, pgen::pattern_config::TestPatternSize};
+
+    use super::AblSweep;
+
+    #[test]
+    fn sweep_all_sizes() {
+        let mut sweep = AblSweep::default();
+        sweep.start(TestPatternSize::Percent10);
+
+        let mut count = 0;
+        while let Some(size) = sweep.next_size() {
+            // OLED-like ABL, brightest at small windows
+            let y = 800.0 - size.float() * 600.0;
+            let res = ReadingResult {
+                xyy: Vec3::new(0.3127, 0.329, y),
+                ..Default::default()
+            };
+            sweep.results.push((size, res));
+            count += 1;
+        }
+        assert_eq!(count, 8);
+        assert_eq!(sweep.stop(), Some(TestPatternSize::Percent10));
+        assert!(!sweep.started);
+
+        let curve = sweep.curve();
+        assert_eq!(curve.first(), Some(&[1.0, 794.0]));
+        assert_eq!(curve.last(), Some(&[100.0, 200.0]));
+
+        let report = sweep.report().unwrap();
+        assert_eq!(report.peak_size, TestPatternSize::Percent1);
+        assert_eq!(report.peak_y, 794.0);
+        assert_eq!(report.full_field_y, Some(200.0));
+        assert!((report.full_field_drop().unwrap() - 0.748).abs() < 1e-3);
+    }
+}
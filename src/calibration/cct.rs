@@ -122,6 +122,26 @@ pub fn xyz_to_cct(xyz: Vec3) -> Option<f64> {
     Some(p)
 }
 
+/// Signed distance to the Planckian locus in CIE 1960 uv, positive above it
+///
+/// Krystek's approximation of the locus, valid from 1000K to 15000K.
+pub fn xyz_to_duv(xyz: Vec3) -> Option<f64> {
+    let cct = xyz_to_cct(xyz)?;
+
+    let denom = xyz.x + 15.0 * xyz.y + 3.0 * xyz.z;
+    let (u, v) = (4.0 * xyz.x / denom, 6.0 * xyz.y / denom);
+
+    let t = cct;
+    let t2 = t * t;
+    let ut = (0.860117757 + 1.54118254e-4 * t + 1.28641212e-7 * t2)
+        / (1.0 + 8.42420235e-4 * t + 7.08145163e-7 * t2);
+    let vt = (0.317398726 + 4.22806245e-5 * t + 4.20481691e-8 * t2)
+        / (1.0 - 2.89741816e-5 * t + 1.61456053e-7 * t2);
+
+    let distance = (u - ut).hypot(v - vt);
+    Some(if v < vt { -distance } else { distance })
+}
+
 #[cfg(test)]
 mod tests {
     use kolor_64::{
@@ -129,7 +149,7 @@ mod tests {
         details::{color::WhitePoint, transform::xyY_to_XYZ},
     };
 
-    use crate::calibration::cct::xyz_to_cct;
+    use crate::calibration::cct::{xyz_to_cct, xyz_to_duv};
 
     #[test]
     fn xyz_d65_to_cct() {
@@ -137,4 +157,15 @@ mod tests {
         let cct = xyz_to_cct(xyz).unwrap();
         assert_eq!(cct, 6503.707184795284);
     }
+
+    #[test]
+    fn xyz_d65_to_duv() {
+        let xyz = xyY_to_XYZ(Vec3::new(0.3127, 0.329, 1.0), WhitePoint::D65);
+        let duv = xyz_to_duv(xyz).unwrap();
+        assert!((duv - 0.0032).abs() < 1e-4);
+
+        // Illuminant A is on the locus
+        let xyz = xyY_to_XYZ(Vec3::new(0.44757, 0.40745, 1.0), WhitePoint::D65);
+        assert!(xyz_to_duv(xyz).unwrap().abs() < 5e-4);
+    }
 }
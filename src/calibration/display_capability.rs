@@ -0,0 +1,184 @@
+use kolor_64::Vec3;
+use strum::IntoEnumIterator;
+
+use crate::pgen::{HdrMetadata, Primaries, commands::PGenSetConfCommand};
+
+use super::ReadingResult;
+
+/// Peak white, black and primaries measured from the internal generator results
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayCapability {
+    /// Nits
+    pub peak_white: f64,
+    /// Nits
+    pub black: f64,
+    /// Closest infoframe primaries to the measured ones
+    pub primaries: Option<Primaries>,
+}
+
+impl DisplayCapability {
+    /// Requires readings of 0% and 100% white
+    pub fn from_results(results: &[ReadingResult]) -> Option<Self> {
+        // Last reading of a stimulus, with the same tolerance as session matching
+        let reading_at = |ref_rgb: Vec3| {
+            results
+                .iter()
+                .rev()
+                .find(|res| (res.target.ref_rgb - ref_rgb).abs().max_element() < 1e-6)
+        };
+
+        let black = reading_at(Vec3::ZERO)?.xyy[2];
+        let peak_white = reading_at(Vec3::ONE)?.xyy[2];
+        if peak_white <= black {
+            return None;
+        }
+
+        let primaries = [Vec3::X, Vec3::Y, Vec3::Z, Vec3::ONE]
+            .into_iter()
+            .map(|ref_rgb| reading_at(ref_rgb).map(|res| [res.xyy[0], res.xyy[1]]))
+            .collect::<Option<Vec<_>>>()
+            .map(|measured| closest_primaries(&measured));
+
+        Some(Self {
+            peak_white,
+            black,
+            primaries,
+        })
+    }
+
+    /// Updates the metadata, returning the commands to send to the PGenerator
+    pub fn apply_to_hdr_metadata(
+        &self,
+        hdr_meta: &mut HdrMetadata,
+        incl_primaries: bool,
+    ) -> Vec<PGenSetConfCommand> {
+        hdr_meta.max_mdl = self.peak_white.round().clamp(0.0, 10_000.0) as u16;
+        // Units of 0.0001 nits
+        hdr_meta.min_mdl = (self.black * 10_000.0).round().clamp(0.0, u16::MAX as f64) as u16;
+
+        let mut commands = vec![
+            PGenSetConfCommand::SetHdrMaxMdl(hdr_meta.max_mdl),
+            PGenSetConfCommand::SetHdrMinMdl(hdr_meta.min_mdl),
+        ];
+
+        if let Some(primaries) = self.primaries.filter(|_| incl_primaries) {
+            hdr_meta.primaries = primaries;
+            commands.push(PGenSetConfCommand::SetHdrPrimaries(primaries));
+        }
+
+        commands
+    }
+}
+
+// Red, green, blue and white xy
+const fn primaries_chromaticities(primaries: Primaries) -> [[f64; 2]; 4] {
+    const D65: [f64; 2] = [0.3127, 0.3290];
+    const P3: [[f64; 2]; 3] = [[0.680, 0.320], [0.265, 0.690], [0.150, 0.060]];
+
+    match primaries {
+        Primaries::Rec709 => [[0.640, 0.330], [0.300, 0.600], [0.150, 0.060], D65],
+        Primaries::Rec2020 => [[0.708, 0.292], [0.170, 0.797], [0.131, 0.046], D65],
+        Primaries::DisplayP3 => [P3[0], P3[1], P3[2], D65],
+        Primaries::DciP3 => [P3[0], P3[1], P3[2], [0.314, 0.351]],
+        Primaries::P3D60 => [P3[0], P3[1], P3[2], [0.32168, 0.33767]],
+    }
+}
+
+fn closest_primaries(measured: &[[f64; 2]]) -> Primaries {
+    let distance = |primaries: &Primaries| -> f64 {
+        primaries_chromaticities(*primaries)
+            .iter()
+            .zip(measured)
+            .map(|(a, b)| (a[0] - b[0]).hypot(a[1] - b[1]))
+            .sum()
+    };
+
+    Primaries::iter()
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use kolor_64::Vec3;
+
+    use crate::{
+        calibration::{CalibrationTarget, ReadingResult},
+        pgen::{HdrMetadata, Primaries, commands::PGenSetConfCommand},
+    };
+
+    use super::DisplayCapability;
+
+    fn reading(ref_rgb: Vec3, xyy: Vec3) -> ReadingResult {
+        ReadingResult {
+            target: CalibrationTarget {
+                ref_rgb,
+                ..Default::default()
+            },
+            xyy,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn hdr_metadata_from_measurements() {
+        let results = vec![
+            reading(Vec3::ZERO, Vec3::new(0.3127, 0.329, 0.0052)),
+            reading(Vec3::splat(0.5), Vec3::new(0.3127, 0.329, 95.0)),
+            reading(Vec3::ONE, Vec3::new(0.3127, 0.329, 742.6)),
+            reading(Vec3::X, Vec3::new(0.675, 0.322, 180.0)),
+            reading(Vec3::Y, Vec3::new(0.27, 0.68, 520.0)),
+            reading(Vec3::Z, Vec3::new(0.151, 0.058, 40.0)),
+        ];
+
+        let capability = DisplayCapability::from_results(&results).unwrap();
+        assert_eq!(capability.primaries, Some(Primaries::DisplayP3));
+
+        let mut hdr_meta = HdrMetadata {
+            primaries: Primaries::Rec2020,
+            ..Default::default()
+        };
+        let commands = capability.apply_to_hdr_metadata(&mut hdr_meta, false);
+        assert_eq!(hdr_meta.max_mdl, 743);
+        assert_eq!(hdr_meta.min_mdl, 52);
+        assert_eq!(hdr_meta.primaries, Primaries::Rec2020);
+        assert_eq!(commands.len(), 2);
+
+        let commands = capability.apply_to_hdr_metadata(&mut hdr_meta, true);
+        assert_eq!(hdr_meta.primaries, Primaries::DisplayP3);
+        assert!(matches!(
+            commands.last(),
+            Some(PGenSetConfCommand::SetHdrPrimaries(Primaries::DisplayP3))
+        ));
+    }
+
+    #[test]
+    fn no_primaries_without_readings() {
+        let results = vec![
+            reading(Vec3::ZERO, Vec3::new(0.3127, 0.329, 0.1)),
+            reading(Vec3::ONE, Vec3::new(0.3127, 0.329, 250.0)),
+        ];
+
+        let capability = DisplayCapability::from_results(&results).unwrap();
+        assert!(capability.primaries.is_none());
+        assert_eq!(capability.peak_white, 250.0);
+        assert_eq!(capability.black, 0.1);
+    }
+
+    #[test]
+    fn requires_black_and_white_readings() {
+        let results = vec![
+            reading(Vec3::splat(0.1), Vec3::new(0.3127, 0.329, 1.2)),
+            reading(Vec3::ONE, Vec3::new(0.3127, 0.329, 250.0)),
+        ];
+        assert!(DisplayCapability::from_results(&results).is_none());
+
+        // Stimulus rounded when read back from a session
+        let results = vec![
+            reading(Vec3::splat(1e-9), Vec3::new(0.3127, 0.329, 0.05)),
+            reading(Vec3::splat(1.0 - 1e-9), Vec3::new(0.3127, 0.329, 250.0)),
+        ];
+        let capability = DisplayCapability::from_results(&results).unwrap();
+        assert_eq!(capability.black, 0.05);
+    }
+}
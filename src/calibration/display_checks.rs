@@ -0,0 +1,254 @@
+use kolor_64::Vec3;
+use strum::{AsRefStr, Display, EnumIter, IntoEnumIterator};
+
+use crate::pgen::pattern_config::PGenPatternConfig;
+
+use super::ReadingResult;
+
+/// Patches measured by the additivity and contrast checks, in order
+#[derive(Display, AsRefStr, Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum DisplayCheckStep {
+    Red,
+    Green,
+    Blue,
+    White,
+    #[strum(to_string = "Full field white")]
+    FullFieldWhite,
+    #[strum(to_string = "Full field black")]
+    FullFieldBlack,
+    #[strum(to_string = "Window white")]
+    WindowWhite,
+    #[strum(to_string = "Window black")]
+    WindowBlack,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckWindow {
+    /// Patch size and position of the pattern config
+    Current,
+    FullField,
+    /// Centre window, the size of one cell of a 4x4 checkerboard.
+    /// Not an ANSI checkerboard, the PGenerator only draws one patch over the background.
+    Centre,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct DisplayChecks {
+    pub started: bool,
+    /// Stop after the current reading
+    pub stop_requested: bool,
+    pub results: Vec<(DisplayCheckStep, ReadingResult)>,
+    /// Pattern config before the checks started, restored at the end
+    pub previous_pattern_cfg: Option<PGenPatternConfig>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Additivity {
+    pub primaries_sum: Vec3,
+    pub white: Vec3,
+}
+
+impl DisplayCheckStep {
+    pub fn patch_rgb(&self) -> Vec3 {
+        match self {
+            Self::Red => Vec3::X,
+            Self::Green => Vec3::Y,
+            Self::Blue => Vec3::Z,
+            Self::White | Self::FullFieldWhite | Self::WindowWhite => Vec3::ONE,
+            Self::FullFieldBlack | Self::WindowBlack => Vec3::ZERO,
+        }
+    }
+
+    /// The black window is surrounded by white
+    pub fn background(&self) -> f64 {
+        if *self == Self::WindowBlack { 1.0 } else { 0.0 }
+    }
+
+    pub fn window(&self) -> CheckWindow {
+        match self {
+            Self::Red | Self::Green | Self::Blue | Self::White => CheckWindow::Current,
+            Self::FullFieldWhite | Self::FullFieldBlack => CheckWindow::FullField,
+            Self::WindowWhite | Self::WindowBlack => CheckWindow::Centre,
+        }
+    }
+}
+
+impl CheckWindow {
+    /// Patch size and position, `None` to keep the current ones
+    pub fn patch_size_and_position(
+        &self,
+        width: u16,
+        height: u16,
+    ) -> Option<((u16, u16), (u16, u16))> {
+        match self {
+            Self::Current => None,
+            Self::FullField => Some(((width, height), (0, 0))),
+            Self::Centre => {
+                let (patch_w, patch_h) = (width / 4, height / 4);
+                let position = ((width - patch_w) / 2, (height - patch_h) / 2);
+
+                Some(((patch_w, patch_h), position))
+            }
+        }
+    }
+}
+
+impl DisplayChecks {
+    pub fn start(&mut self, previous_pattern_cfg: PGenPatternConfig) {
+        self.started = true;
+        self.stop_requested = false;
+        self.results.clear();
+        self.previous_pattern_cfg = Some(previous_pattern_cfg);
+    }
+
+    pub fn next_step(&self) -> Option<DisplayCheckStep> {
+        DisplayCheckStep::iter().nth(self.results.len())
+    }
+
+    /// Returns the pattern config to restore
+    pub fn stop(&mut self) -> Option<PGenPatternConfig> {
+        self.started = false;
+        self.previous_pattern_cfg.take()
+    }
+
+    fn step_result(&self, step: DisplayCheckStep) -> Option<&ReadingResult> {
+        self.results
+            .iter()
+            .find(|(s, _)| *s == step)
+            .map(|(_, res)| res)
+    }
+
+    pub fn additivity(&self) -> Option<Additivity> {
+        let [red, green, blue, white] = [
+            DisplayCheckStep::Red,
+            DisplayCheckStep::Green,
+            DisplayCheckStep::Blue,
+            DisplayCheckStep::White,
+        ]
+        .map(|step| self.step_result(step).map(|res| res.xyz));
+
+        Some(Additivity {
+            primaries_sum: red? + green? + blue?,
+            white: white?,
+        })
+    }
+
+    pub fn full_field_contrast(&self) -> Option<f64> {
+        self.contrast(
+            DisplayCheckStep::FullFieldWhite,
+            DisplayCheckStep::FullFieldBlack,
+        )
+    }
+
+    /// White window on black against black window on white
+    pub fn window_contrast(&self) -> Option<f64> {
+        self.contrast(DisplayCheckStep::WindowWhite, DisplayCheckStep::WindowBlack)
+    }
+
+    // Infinite for a zero black
+    fn contrast(&self, white: DisplayCheckStep, black: DisplayCheckStep) -> Option<f64> {
+        let white = self.step_result(white)?.xyy[2];
+        let black = self.step_result(black)?.xyy[2];
+
+        (white > 0.0).then(|| white / black.max(0.0))
+    }
+}
+
+impl Additivity {
+    /// Relative error of the sum of primaries against white, per XYZ component
+    pub fn xyz_error(&self) -> Vec3 {
+        let white = self.white.to_array();
+        let sum = self.primaries_sum.to_array();
+
+        Vec3::from_array(std::array::from_fn(|i| {
+            if white[i] > 0.0 {
+                (sum[i] - white[i]) / white[i]
+            } else {
+                0.0
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kolor_64::Vec3;
+
+    use crate::{calibration::ReadingResult, pgen::pattern_config::PGenPatternConfig};
+
+    use super::{CheckWindow, DisplayCheckStep, DisplayChecks};
+
+    #[test]
+    fn additivity_and_contrast() {
+        let mut checks = DisplayChecks::default();
+        checks.start(PGenPatternConfig::default());
+
+        while let Some(step) = checks.next_step() {
+            let xyz = match step {
+                DisplayCheckStep::Red => Vec3::new(41.2, 21.3, 1.9),
+                DisplayCheckStep::Green => Vec3::new(35.8, 71.5, 11.9),
+                DisplayCheckStep::Blue => Vec3::new(18.0, 7.2, 95.0),
+                // Slightly less than the sum
+                DisplayCheckStep::White => Vec3::new(93.1, 98.0, 106.7),
+                DisplayCheckStep::FullFieldWhite => Vec3::new(95.0, 100.0, 108.9),
+                DisplayCheckStep::FullFieldBlack => Vec3::new(0.0095, 0.01, 0.0109),
+                DisplayCheckStep::WindowWhite => Vec3::new(95.0, 100.0, 108.9),
+                DisplayCheckStep::WindowBlack => Vec3::new(0.19, 0.2, 0.218),
+            };
+            let res = ReadingResult {
+                xyz,
+                xyy: Vec3::new(0.3127, 0.329, xyz.y),
+                ..Default::default()
+            };
+            checks.results.push((step, res));
+        }
+        assert_eq!(checks.results.len(), 8);
+        assert!(checks.stop().is_some());
+
+        let additivity = checks.additivity().unwrap();
+        assert!((additivity.primaries_sum.y - 100.0).abs() < 1e-9);
+        assert!((additivity.xyz_error().y - 0.0204).abs() < 1e-4);
+
+        assert!((checks.full_field_contrast().unwrap() - 10_000.0).abs() < 1e-6);
+        assert!((checks.window_contrast().unwrap() - 500.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn check_windows() {
+        assert_eq!(DisplayCheckStep::WindowBlack.background(), 1.0);
+        assert_eq!(DisplayCheckStep::WindowBlack.patch_rgb(), Vec3::ZERO);
+
+        assert_eq!(
+            CheckWindow::Centre.patch_size_and_position(1920, 1080),
+            Some(((480, 270), (720, 405)))
+        );
+        assert_eq!(
+            CheckWindow::FullField.patch_size_and_position(3840, 2160),
+            Some(((3840, 2160), (0, 0)))
+        );
+        assert!(
+            CheckWindow::Current
+                .patch_size_and_position(1920, 1080)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn zero_black_contrast() {
+        let reading = |y: f64| ReadingResult {
+            xyy: Vec3::new(0.3127, 0.329, y),
+            ..Default::default()
+        };
+
+        let checks = DisplayChecks {
+            results: vec![
+                (DisplayCheckStep::FullFieldWhite, reading(150.0)),
+                (DisplayCheckStep::FullFieldBlack, reading(0.0)),
+            ],
+            ..Default::default()
+        };
+        assert!(checks.full_field_contrast().unwrap().is_infinite());
+        assert!(checks.window_contrast().is_none());
+        assert!(checks.additivity().is_none());
+    }
+}
@@ -0,0 +1,299 @@
+use std::time::{Duration, Instant};
+
+use kolor_64::Vec3;
+use serde::{Deserialize, Serialize};
+
+use super::xy_to_uv;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftReadingKind {
+    /// Scheduled warm-up reading
+    Monitor,
+    /// White re-measured during a patch sweep
+    SweepCheck,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DriftSample {
+    /// Since the monitoring started
+    pub minutes: f64,
+    pub xyy: Vec3,
+}
+
+/// Measures a reference patch periodically, to follow the display warm-up
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DriftMonitor {
+    /// Stimulus level of the reference patch
+    pub level: f64,
+    pub interval_secs: u64,
+    pub duration_mins: u64,
+
+    /// Fraction of the luminance
+    pub stable_y_tolerance: f64,
+    pub stable_uv_tolerance: f64,
+    /// How long the readings must stay within tolerance
+    pub stable_mins: f64,
+
+    /// Re-measure white during patch sweeps
+    pub sweep_checks: bool,
+    pub sweep_check_patches: usize,
+    /// Fraction of the luminance at the start of the sweep
+    pub sweep_max_drift: f64,
+
+    #[serde(skip)]
+    pub started: bool,
+    #[serde(skip)]
+    pub measuring: Option<DriftReadingKind>,
+    #[serde(skip)]
+    start: Option<Instant>,
+    #[serde(skip)]
+    next_reading: Option<Instant>,
+    #[serde(skip)]
+    pub samples: Vec<DriftSample>,
+
+    #[serde(skip)]
+    sweep_reference: Option<Vec3>,
+    #[serde(skip)]
+    patches_since_check: Vec<usize>,
+    /// Patches measured while the drift exceeded the limit
+    #[serde(skip)]
+    pub flagged_patches: Vec<usize>,
+}
+
+impl DriftMonitor {
+    pub fn start(&mut self, now: Instant) {
+        self.started = true;
+        self.measuring = None;
+        self.start = Some(now);
+        self.next_reading = Some(now);
+        self.samples.clear();
+    }
+
+    /// A reading in progress still gets recorded
+    pub fn stop(&mut self) {
+        self.started = false;
+        self.next_reading = None;
+    }
+
+    /// Zero when a reading is due, `None` when there is nothing to schedule
+    pub fn time_until_next_reading(&self, now: Instant) -> Option<Duration> {
+        self.next_reading
+            .filter(|_| self.started && self.measuring.is_none())
+            .map(|next| next.saturating_duration_since(now))
+    }
+
+    /// Returns false once the monitoring duration is over
+    pub fn add_sample(&mut self, now: Instant, xyy: Vec3) -> bool {
+        self.measuring = None;
+
+        let Some(start) = self.start else {
+            return false;
+        };
+        let minutes = now.duration_since(start).as_secs_f64() / 60.0;
+        self.samples.push(DriftSample { minutes, xyy });
+
+        if !self.started || minutes >= self.duration_mins as f64 {
+            self.stop();
+            return false;
+        }
+
+        self.next_reading = Some(now + Duration::from_secs(self.interval_secs));
+        true
+    }
+
+    /// Luminance drift in percent and Δu'v', relative to the latest reading
+    pub fn drift_points(&self) -> (Vec<[f64; 2]>, Vec<[f64; 2]>) {
+        let Some(latest) = self.samples.last() else {
+            return Default::default();
+        };
+
+        self.samples
+            .iter()
+            .map(|sample| {
+                let (y, uv) = drift(latest.xyy, sample.xyy);
+                ([sample.minutes, y * 100.0], [sample.minutes, uv])
+            })
+            .unzip()
+    }
+
+    /// Minutes after which the readings stayed within tolerance of the latest one
+    pub fn stabilized_at(&self) -> Option<f64> {
+        let latest = self.samples.last()?;
+
+        let first_stable = self
+            .samples
+            .iter()
+            .rposition(|sample| {
+                let (y, uv) = drift(latest.xyy, sample.xyy);
+                y.abs() > self.stable_y_tolerance || uv > self.stable_uv_tolerance
+            })
+            .map_or(0, |i| i + 1);
+        let first_stable = self.samples[first_stable].minutes;
+
+        (latest.minutes - first_stable >= self.stable_mins).then_some(first_stable)
+    }
+
+    pub fn begin_sweep(&mut self) {
+        self.sweep_reference = None;
+        self.patches_since_check.clear();
+        self.flagged_patches.clear();
+    }
+
+    /// White is measured once before the first sweep patch
+    pub fn needs_sweep_reference(&self) -> bool {
+        self.sweep_checks && self.sweep_reference.is_none()
+    }
+
+    /// Records a measured sweep patch, returns true when white should be re-measured
+    pub fn record_sweep_patch(&mut self, idx: usize) -> bool {
+        if !self.sweep_checks {
+            return false;
+        }
+
+        self.patches_since_check.push(idx);
+        self.patches_since_check.len() >= self.sweep_check_patches.max(1)
+    }
+
+    /// Returns true when the last patches still have to be checked
+    pub fn end_sweep(&mut self) -> bool {
+        self.sweep_checks && self.sweep_reference.is_some() && !self.patches_since_check.is_empty()
+    }
+
+    /// The first check is the reference, patches measured before a check
+    /// that exceeds the drift limit get flagged
+    pub fn add_sweep_check(&mut self, xyy: Vec3) {
+        self.measuring = None;
+
+        match self.sweep_reference {
+            Some(reference) => {
+                let (y, _) = drift(reference, xyy);
+                if y.abs() > self.sweep_max_drift {
+                    self.flagged_patches.append(&mut self.patches_since_check);
+                }
+            }
+            None => self.sweep_reference = Some(xyy),
+        }
+
+        self.patches_since_check.clear();
+    }
+}
+
+// Luminance fraction and Δu'v'
+fn drift(reference: Vec3, xyy: Vec3) -> (f64, f64) {
+    let y = if reference[2] > 0.0 {
+        (xyy[2] - reference[2]) / reference[2]
+    } else {
+        0.0
+    };
+
+    let [u1, v1] = xy_to_uv([reference[0], reference[1]]);
+    let [u2, v2] = xy_to_uv([xyy[0], xyy[1]]);
+
+    (y, (u2 - u1).hypot(v2 - v1))
+}
+
+impl Default for DriftMonitor {
+    fn default() -> Self {
+        Self {
+            level: 1.0,
+            interval_secs: 60,
+            duration_mins: 45,
+            stable_y_tolerance: 0.005,
+            stable_uv_tolerance: 0.001,
+            stable_mins: 5.0,
+            sweep_checks: false,
+            sweep_check_patches: 20,
+            sweep_max_drift: 0.01,
+            started: false,
+            measuring: None,
+            start: None,
+            next_reading: None,
+            samples: Default::default(),
+            sweep_reference: None,
+            patches_since_check: Default::default(),
+            flagged_patches: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use kolor_64::Vec3;
+
+    use super::DriftMonitor;
+
+    #[test]
+    fn warm_up_stabilization() {
+        let mut monitor = DriftMonitor {
+            interval_secs: 60,
+            duration_mins: 20,
+            ..Default::default()
+        };
+
+        let start = Instant::now();
+        monitor.start(start);
+        assert_eq!(monitor.time_until_next_reading(start), Some(Duration::ZERO));
+
+        let mut minute = 0;
+        loop {
+            let now = start + Duration::from_secs(minute * 60);
+            // Brightens by 2% per minute for 10 minutes, then stays stable
+            let y = 100.0 * (1.0 + 0.02 * minute.min(10) as f64);
+            if !monitor.add_sample(now, Vec3::new(0.3127, 0.329, y)) {
+                break;
+            }
+
+            assert_eq!(
+                monitor.time_until_next_reading(now),
+                Some(Duration::from_secs(60))
+            );
+            minute += 1;
+        }
+        assert!(!monitor.started);
+        assert_eq!(monitor.samples.len(), 21);
+
+        assert_eq!(monitor.stabilized_at(), Some(10.0));
+
+        let (y_points, uv_points) = monitor.drift_points();
+        assert!((y_points[0][1] + 100.0 * 0.2 / 1.2).abs() < 1e-9);
+        assert_eq!(y_points[20], [20.0, 0.0]);
+        assert_eq!(uv_points[0][1], 0.0);
+    }
+
+    #[test]
+    fn flag_sweep_patches() {
+        let mut monitor = DriftMonitor {
+            sweep_checks: true,
+            sweep_check_patches: 2,
+            ..Default::default()
+        };
+        monitor.begin_sweep();
+
+        let white = |y: f64| Vec3::new(0.3127, 0.329, y);
+
+        // Reference before the first patch
+        assert!(monitor.needs_sweep_reference());
+        monitor.add_sweep_check(white(100.0));
+        assert!(!monitor.needs_sweep_reference());
+
+        assert!(!monitor.record_sweep_patch(0));
+        assert!(monitor.record_sweep_patch(1));
+        monitor.add_sweep_check(white(100.5));
+        assert!(monitor.flagged_patches.is_empty());
+
+        assert!(!monitor.record_sweep_patch(2));
+        assert!(monitor.record_sweep_patch(3));
+        monitor.add_sweep_check(white(99.0));
+        assert!(monitor.flagged_patches.is_empty());
+
+        // Final check for the patches left at the end
+        assert!(!monitor.record_sweep_patch(4));
+        assert!(monitor.end_sweep());
+        monitor.add_sweep_check(white(98.0));
+        assert!(!monitor.end_sweep());
+        assert_eq!(monitor.flagged_patches, vec![4]);
+    }
+}
@@ -0,0 +1,100 @@
+use std::fmt;
+
+use kolor_64::{
+    Vec3,
+    details::{
+        color::WhitePoint,
+        transform::{CIELAB_to_XYZ, xyY_to_XYZ},
+    },
+};
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, Display, EnumIter, IntoEnumIterator};
+
+#[derive(
+    Display, AsRefStr, Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumIter,
+)]
+pub enum ExplicitTargetKind {
+    #[strum(to_string = "XYZ")]
+    Xyz,
+    #[strum(to_string = "xyY")]
+    XyY,
+    Lab,
+}
+
+/// Reference value of a patch, replacing the one computed from the stimulus.
+///
+/// XYZ and xyY are in nits, like the measurements.
+/// L*a*b* is D65 and relative to the target max Y.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct ExplicitTarget {
+    pub kind: ExplicitTargetKind,
+    pub values: [f64; 3],
+}
+
+impl ExplicitTargetKind {
+    /// Case insensitive
+    pub fn parse(kind: &str) -> Option<Self> {
+        Self::iter().find(|k| k.as_ref().eq_ignore_ascii_case(kind.trim()))
+    }
+}
+
+impl ExplicitTarget {
+    pub fn parse(kind: &str, values: [f64; 3]) -> Option<Self> {
+        ExplicitTargetKind::parse(kind).map(|kind| Self { kind, values })
+    }
+
+    /// Absolute XYZ in nits
+    pub fn to_xyz(&self, max_y: f64) -> Vec3 {
+        let values = Vec3::from_array(self.values);
+
+        match self.kind {
+            ExplicitTargetKind::Xyz => values,
+            ExplicitTargetKind::XyY if values.y > 0.0 => xyY_to_XYZ(values, WhitePoint::D65),
+            ExplicitTargetKind::XyY => Vec3::ZERO,
+            ExplicitTargetKind::Lab => CIELAB_to_XYZ(values, WhitePoint::D65) * max_y,
+        }
+    }
+}
+
+impl fmt::Display for ExplicitTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [v1, v2, v3] = self.values;
+        write!(f, "{} {v1:.4}, {v2:.4}, {v3:.4}", self.kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExplicitTarget, ExplicitTargetKind};
+
+    #[test]
+    fn explicit_target_xyz() {
+        assert_eq!(
+            ExplicitTargetKind::parse("xyz"),
+            Some(ExplicitTargetKind::Xyz)
+        );
+        assert_eq!(
+            ExplicitTargetKind::parse(" XYY"),
+            Some(ExplicitTargetKind::XyY)
+        );
+        assert_eq!(
+            ExplicitTargetKind::parse("LAB"),
+            Some(ExplicitTargetKind::Lab)
+        );
+        assert!(ExplicitTarget::parse("Luv", [0.0; 3]).is_none());
+
+        let xyz = ExplicitTarget::parse("XYZ", [19.0, 20.0, 21.8]).unwrap();
+        assert_eq!(xyz.to_xyz(100.0).to_array(), [19.0, 20.0, 21.8]);
+
+        let xyy = ExplicitTarget::parse("xyY", [0.3127, 0.329, 50.0]).unwrap();
+        let res = xyy.to_xyz(100.0);
+        assert!((res.x - 47.523).abs() < 1e-3);
+        assert!((res.y - 50.0).abs() < 1e-9);
+
+        // D65 white at L* 100 is the target peak
+        let lab = ExplicitTarget::parse("Lab", [100.0, 0.0, 0.0]).unwrap();
+        let res = lab.to_xyz(120.0);
+        assert!((res.y - 120.0).abs() < 1e-6);
+        assert!((res.x / res.y - 0.9505).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,519 @@
+use std::collections::HashMap;
+
+use kolor_64::{ColorConversion, Vec3};
+use strum::IntoEnumIterator;
+
+use super::{
+    CalibrationTarget, ReadingResult, TargetColorspace,
+    reading_result::{quantize_rgb, rgb_cube_levels},
+};
+
+// Primaries and secondaries, in hue order
+const HUE_ORDERED_RGB: [[f64; 3]; 6] = [
+    [1.0, 0.0, 0.0],
+    [1.0, 1.0, 0.0],
+    [0.0, 1.0, 0.0],
+    [0.0, 1.0, 1.0],
+    [0.0, 0.0, 1.0],
+    [1.0, 0.0, 1.0],
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct GamutCoverage {
+    pub colorspace: TargetColorspace,
+
+    // Fraction of the reference gamut area covered
+    pub xy: f64,
+    pub uv: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GamutVolume {
+    // In CIELAB units
+    pub measured: f64,
+    pub target: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct GamutReport {
+    /// Measured gamut polygon in xy, in hue order
+    pub measured_xy: Vec<[f64; 2]>,
+    pub coverage: Vec<GamutCoverage>,
+
+    // Measured gamut area relative to the target colorspace
+    pub relative_area_xy: f64,
+    pub relative_area_uv: f64,
+
+    pub volume: Option<GamutVolume>,
+}
+
+/// Report of the last results, only recomputed when they change
+#[derive(Debug, Default, Clone)]
+pub struct CachedGamutReport {
+    results: Vec<ReadingResult>,
+    target_csp: Option<TargetColorspace>,
+    report: Option<GamutReport>,
+}
+
+// Last reading wins for a given stimulus
+struct ResultsByRgb<'a>(HashMap<[i64; 3], &'a ReadingResult>);
+
+impl GamutReport {
+    pub fn from_results(
+        results: &[ReadingResult],
+        target_csp: TargetColorspace,
+        target_rgb_to_xyz: ColorConversion,
+    ) -> Option<Self> {
+        let by_rgb = ResultsByRgb::new(results);
+        let measured_xy = by_rgb.gamut_polygon()?;
+        let measured_uv: Vec<_> = measured_xy.iter().copied().map(xy_to_uv).collect();
+
+        let coverage = TargetColorspace::iter()
+            .map(|csp| {
+                let ref_xy = colorspace_xy_triangle(csp);
+                let ref_uv = ref_xy.map(xy_to_uv);
+
+                GamutCoverage {
+                    colorspace: csp,
+                    xy: polygon_coverage(&measured_xy, &ref_xy),
+                    uv: polygon_coverage(&measured_uv, &ref_uv),
+                }
+            })
+            .collect();
+
+        let target_xy = colorspace_xy_triangle(target_csp);
+        let target_uv = target_xy.map(xy_to_uv);
+
+        Some(Self {
+            relative_area_xy: polygon_area(&measured_xy) / polygon_area(&target_xy),
+            relative_area_uv: polygon_area(&measured_uv) / polygon_area(&target_uv),
+            measured_xy,
+            coverage,
+            volume: GamutVolume::from_measured(results, &by_rgb, target_rgb_to_xyz),
+        })
+    }
+}
+
+impl CachedGamutReport {
+    pub fn update(
+        &mut self,
+        results: &[ReadingResult],
+        target_csp: TargetColorspace,
+        target_rgb_to_xyz: ColorConversion,
+    ) {
+        if self.target_csp == Some(target_csp) && self.results == results {
+            return;
+        }
+
+        self.report = GamutReport::from_results(results, target_csp, target_rgb_to_xyz);
+        self.results = results.to_vec();
+        self.target_csp = Some(target_csp);
+    }
+
+    pub fn report(&self) -> Option<&GamutReport> {
+        self.report.as_ref()
+    }
+}
+
+impl GamutVolume {
+    /// Estimates the CIELAB gamut volume from either a complete RGB cube or a saturation sweep.
+    fn from_measured(
+        results: &[ReadingResult],
+        by_rgb: &ResultsByRgb,
+        target_rgb_to_xyz: ColorConversion,
+    ) -> Option<Self> {
+        let target = results.first()?.target;
+        let measured_lab = |rgb: [f64; 3]| by_rgb.get(rgb).map(|res| res.lab);
+        let target_lab = |rgb: [f64; 3]| Some(ref_lab_for_rgb(target, rgb, target_rgb_to_xyz));
+
+        let (measured, target) = if let Some(levels) = rgb_cube_levels(results) {
+            (
+                cube_surface_volume(&levels, measured_lab)?,
+                cube_surface_volume(&levels, target_lab)?,
+            )
+        } else {
+            let rings = saturation_rings(results)?;
+
+            // Assume the display tracks the target at the ends if black/white were not measured
+            let measured_or_target = |rgb: [f64; 3]| measured_lab(rgb).or_else(|| target_lab(rgb));
+            (
+                rings_surface_volume(&rings, measured_or_target)?,
+                rings_surface_volume(&rings, target_lab)?,
+            )
+        };
+
+        Some(Self { measured, target })
+    }
+
+    pub fn relative(&self) -> f64 {
+        self.measured / self.target
+    }
+}
+
+pub fn xy_to_uv(xy: [f64; 2]) -> [f64; 2] {
+    let [x, y] = xy;
+    let denom = -2.0 * x + 12.0 * y + 3.0;
+
+    [4.0 * x / denom, 9.0 * y / denom]
+}
+
+pub fn colorspace_xy_triangle(csp: TargetColorspace) -> [[f64; 2]; 3] {
+    *csp.to_kolor().primaries().values()
+}
+
+impl<'a> ResultsByRgb<'a> {
+    fn new(results: &'a [ReadingResult]) -> Self {
+        Self(
+            results
+                .iter()
+                .map(|res| (quantize_rgb(res.target.ref_rgb), res))
+                .collect(),
+        )
+    }
+
+    fn get(&self, rgb: [f64; 3]) -> Option<&'a ReadingResult> {
+        self.0.get(&quantize_rgb(rgb.into())).copied()
+    }
+
+    /// Measured gamut polygon in xy, in hue order.
+    /// Uses the secondaries too when they were measured.
+    fn gamut_polygon(&self) -> Option<Vec<[f64; 2]>> {
+        let to_xy = |res: &ReadingResult| [res.xyy[0], res.xyy[1]];
+
+        let hexagon: Option<Vec<_>> = HUE_ORDERED_RGB
+            .iter()
+            .map(|rgb| self.get(*rgb).map(to_xy))
+            .collect();
+
+        hexagon.or_else(|| {
+            HUE_ORDERED_RGB
+                .iter()
+                .step_by(2)
+                .map(|rgb| self.get(*rgb).map(to_xy))
+                .collect()
+        })
+    }
+}
+
+fn ref_lab_for_rgb(
+    target: CalibrationTarget,
+    rgb: [f64; 3],
+    target_rgb_to_xyz: ColorConversion,
+) -> Vec3 {
+    let res = ReadingResult {
+        target: CalibrationTarget {
+            ref_rgb: rgb.into(),
+            ..target
+        },
+        ..Default::default()
+    };
+
+    res.ref_lab_display_space(target_rgb_to_xyz)
+}
+
+pub fn polygon_area(points: &[[f64; 2]]) -> f64 {
+    let n = points.len();
+    let twice_area: f64 = (0..n)
+        .map(|i| {
+            let [x1, y1] = points[i];
+            let [x2, y2] = points[(i + 1) % n];
+
+            x1 * y2 - x2 * y1
+        })
+        .sum();
+
+    twice_area.abs() / 2.0
+}
+
+/// Fraction of the convex `reference` polygon covered by `subject`
+fn polygon_coverage(subject: &[[f64; 2]], reference: &[[f64; 2]]) -> f64 {
+    let intersection = clip_polygon(subject, reference);
+    polygon_area(&intersection) / polygon_area(reference)
+}
+
+/// Sutherland-Hodgman clipping of `subject` by the convex `clip` polygon
+fn clip_polygon(subject: &[[f64; 2]], clip: &[[f64; 2]]) -> Vec<[f64; 2]> {
+    let cross = |a: [f64; 2], b: [f64; 2], p: [f64; 2]| {
+        (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+    };
+
+    // Clip edges must be counter-clockwise for the inside test
+    let mut clip = clip.to_vec();
+    let signed_area: f64 = (0..clip.len())
+        .map(|i| cross([0.0, 0.0], clip[i], clip[(i + 1) % clip.len()]))
+        .sum();
+    if signed_area < 0.0 {
+        clip.reverse();
+    }
+
+    let mut output = subject.to_vec();
+    for i in 0..clip.len() {
+        if output.is_empty() {
+            break;
+        }
+
+        let (a, b) = (clip[i], clip[(i + 1) % clip.len()]);
+        let input = std::mem::take(&mut output);
+
+        for j in 0..input.len() {
+            let cur = input[j];
+            let prev = input[(j + input.len() - 1) % input.len()];
+
+            let (cur_side, prev_side) = (cross(a, b, cur), cross(a, b, prev));
+            let intersect = || {
+                let t = prev_side / (prev_side - cur_side);
+                [
+                    prev[0] + (cur[0] - prev[0]) * t,
+                    prev[1] + (cur[1] - prev[1]) * t,
+                ]
+            };
+
+            if cur_side >= 0.0 {
+                if prev_side < 0.0 {
+                    output.push(intersect());
+                }
+                output.push(cur);
+            } else if prev_side >= 0.0 {
+                output.push(intersect());
+            }
+        }
+    }
+
+    output
+}
+
+/// Volume enclosed by a closed triangulated surface, through the divergence theorem
+fn surface_volume(triangles: &[[Vec3; 3]]) -> f64 {
+    let signed_volume: f64 = triangles
+        .iter()
+        .map(|[a, b, c]| a.dot(b.cross(*c)) / 6.0)
+        .sum();
+
+    signed_volume.abs()
+}
+
+fn push_quad(triangles: &mut Vec<[Vec3; 3]>, quad: [Vec3; 4]) {
+    let [a, b, c, d] = quad;
+    triangles.push([a, b, c]);
+    triangles.push([a, c, d]);
+}
+
+fn cube_surface_volume<F>(levels: &[f64], lab_for_rgb: F) -> Option<f64>
+where
+    F: Fn([f64; 3]) -> Option<Vec3>,
+{
+    let n = levels.len();
+    let mut triangles = Vec::with_capacity(6 * 2 * (n - 1) * (n - 1));
+
+    for axis in 0..3 {
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+
+        for side in [0, n - 1] {
+            for i in 0..n - 1 {
+                for j in 0..n - 1 {
+                    let vertex = |u: usize, v: usize| {
+                        let mut rgb = [0.0; 3];
+                        rgb[axis] = levels[side];
+                        rgb[u_axis] = levels[u];
+                        rgb[v_axis] = levels[v];
+
+                        lab_for_rgb(rgb)
+                    };
+
+                    let mut quad = [
+                        vertex(i, j)?,
+                        vertex(i + 1, j)?,
+                        vertex(i + 1, j + 1)?,
+                        vertex(i, j + 1)?,
+                    ];
+                    // Keep the faces consistently oriented outwards
+                    if side == 0 {
+                        quad.reverse();
+                    }
+
+                    push_quad(&mut triangles, quad);
+                }
+            }
+        }
+    }
+
+    Some(surface_volume(&triangles))
+}
+
+// Rings of the six hues at full value, from least to most saturated
+fn saturation_rings(results: &[ReadingResult]) -> Option<Vec<[[f64; 3]; 6]>> {
+    let mut saturations: Vec<f64> = results
+        .iter()
+        .filter_map(|res| {
+            let (_, s, v) = ecolor::hsv_from_rgb(res.target.ref_rgb.to_array().map(|c| c as f32));
+            (v >= 1.0 && s > 0.0).then_some((s as f64 * 1e2).round() / 1e2)
+        })
+        .collect();
+    saturations.sort_by(|a, b| a.total_cmp(b));
+    saturations.dedup();
+
+    let rings: Vec<[[f64; 3]; 6]> = saturations
+        .into_iter()
+        .filter_map(|sat| {
+            let ring = HUE_ORDERED_RGB.map(|rgb| {
+                let (h, _, v) = ecolor::hsv_from_rgb(rgb.map(|c| c as f32));
+                let ring_rgb = ecolor::rgb_from_hsv((h, sat as f32, v)).map(|c| c as f64);
+
+                results
+                    .iter()
+                    .rev()
+                    .find(|res| {
+                        res.target
+                            .ref_rgb
+                            .to_array()
+                            .iter()
+                            .zip(ring_rgb)
+                            .all(|(a, b)| (a - b).abs() < 1e-2)
+                    })
+                    .map(|res| res.target.ref_rgb.to_array())
+            });
+
+            ring.iter()
+                .all(Option::is_some)
+                .then(|| ring.map(Option::unwrap))
+        })
+        .collect();
+
+    // Only the fully saturated ring reaches the black end of the gamut
+    let has_full_sat = rings
+        .last()
+        .is_some_and(|ring| ring.iter().zip(HUE_ORDERED_RGB).all(|(a, b)| a == &b));
+
+    has_full_sat.then_some(rings)
+}
+
+fn rings_surface_volume<F>(rings: &[[[f64; 3]; 6]], lab_for_rgb: F) -> Option<f64>
+where
+    F: Fn([f64; 3]) -> Option<Vec3>,
+{
+    let white = lab_for_rgb([1.0, 1.0, 1.0])?;
+    let black = lab_for_rgb([0.0, 0.0, 0.0])?;
+
+    let lab_rings = rings
+        .iter()
+        .map(|ring| {
+            let mut lab_ring = [Vec3::ZERO; 6];
+            for (lab, rgb) in lab_ring.iter_mut().zip(ring) {
+                *lab = lab_for_rgb(*rgb)?;
+            }
+
+            Some(lab_ring)
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let (first, last) = (lab_rings.first()?, lab_rings.last()?);
+    let mut triangles = Vec::with_capacity(lab_rings.len() * 12 + 12);
+
+    for i in 0..6 {
+        let next = (i + 1) % 6;
+        triangles.push([white, first[i], first[next]]);
+        triangles.push([black, last[next], last[i]]);
+
+        for (inner, outer) in lab_rings.iter().zip(lab_rings.iter().skip(1)) {
+            push_quad(
+                &mut triangles,
+                [inner[i], outer[i], outer[next], inner[next]],
+            );
+        }
+    }
+
+    Some(surface_volume(&triangles))
+}
+
+#[cfg(test)]
+mod tests {
+    use kolor_64::{ColorConversion, spaces::CIE_XYZ};
+
+    use crate::calibration::{CalibrationTarget, ReadingResult, TargetColorspace};
+
+    use super::{
+        CachedGamutReport, GamutReport, colorspace_xy_triangle, cube_surface_volume, polygon_area,
+        polygon_coverage, xy_to_uv,
+    };
+
+    #[test]
+    fn uv_d65() {
+        let [u, v] = xy_to_uv([0.3127, 0.329]);
+        assert_eq!((u * 1e4).round() / 1e4, 0.1978);
+        assert_eq!((v * 1e4).round() / 1e4, 0.4683);
+    }
+
+    #[test]
+    fn rec709_in_rec2020_coverage() {
+        let rec709 = colorspace_xy_triangle(TargetColorspace::Rec709);
+        let rec2020 = colorspace_xy_triangle(TargetColorspace::Rec2020);
+
+        assert!((polygon_coverage(&rec709, &rec709) - 1.0).abs() < 1e-12);
+        assert!((polygon_coverage(&rec2020, &rec709) - 1.0).abs() < 1e-12);
+
+        let coverage = polygon_coverage(&rec709, &rec2020);
+        let expected = polygon_area(&rec709) / polygon_area(&rec2020);
+        assert!((coverage - expected).abs() < 1e-12);
+        assert_eq!((coverage * 1e4).round() / 1e4, 0.5289);
+    }
+
+    #[test]
+    fn unit_cube_volume() {
+        let levels = [0.0, 0.5, 1.0];
+        let volume = cube_surface_volume(&levels, |rgb| Some(rgb.into())).unwrap();
+
+        assert!((volume - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn perfect_display_report() {
+        let target = CalibrationTarget::default();
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+
+        let levels = [0.0, 0.5, 1.0];
+        let results: Vec<_> = (0..27)
+            .map(|i| [levels[i % 3], levels[(i / 3) % 3], levels[i / 9]])
+            .map(|rgb| ReadingResult::perfect(target, rgb.into()))
+            .collect();
+
+        let report =
+            GamutReport::from_results(&results, target.colorspace, target_rgb_to_xyz).unwrap();
+        assert_eq!(report.measured_xy.len(), 6);
+        assert!((report.relative_area_xy - 1.0).abs() < 1e-4);
+        assert!((report.relative_area_uv - 1.0).abs() < 1e-4);
+
+        let rec709_coverage = report.coverage[0];
+        assert_eq!(rec709_coverage.colorspace, TargetColorspace::Rec709);
+        assert!((rec709_coverage.xy - 1.0).abs() < 1e-4);
+
+        let volume = report.volume.unwrap();
+        assert!(volume.target > 0.0);
+        assert!((volume.relative() - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn cached_report_follows_results() {
+        let target = CalibrationTarget::default();
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+
+        let mut results: Vec<_> = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+            .into_iter()
+            .map(|rgb| ReadingResult::perfect(target, rgb.into()))
+            .collect();
+        let mut cache = CachedGamutReport::default();
+        cache.update(&results, target.colorspace, target_rgb_to_xyz);
+        assert_eq!(cache.report().unwrap().measured_xy.len(), 3);
+        let area = cache.report().unwrap().relative_area_xy;
+        assert!((area - 1.0).abs() < 1e-4);
+
+        // Re-measured red wins over the first reading
+        let mut red = results[0];
+        red.xyy[0] -= 0.05;
+        results.push(red);
+        cache.update(&results, target.colorspace, target_rgb_to_xyz);
+        assert!(cache.report().unwrap().relative_area_xy < area);
+
+        cache.update(&results[..2], target.colorspace, target_rgb_to_xyz);
+        assert!(cache.report().is_none());
+    }
+}
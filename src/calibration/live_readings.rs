@@ -0,0 +1,191 @@
+use std::{collections::VecDeque, time::Instant};
+
+use kolor_64::Vec3;
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, Display, EnumIter};
+
+use super::ReadingResult;
+
+#[derive(
+    Display, AsRefStr, Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumIter,
+)]
+pub enum LiveMetric {
+    #[default]
+    Y,
+    #[strum(to_string = "x")]
+    SmallX,
+    #[strum(to_string = "y")]
+    SmallY,
+    #[strum(to_string = "CCT")]
+    Cct,
+    #[strum(to_string = "dE2000")]
+    DeltaE,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LiveSample {
+    /// Seconds since the first sample
+    pub time: f64,
+    pub xyy: Vec3,
+    pub cct: f64,
+    pub delta_e: f64,
+}
+
+/// Rolling time series of the readings of a single patch
+#[derive(Debug, Default, Clone)]
+pub struct LiveReadings {
+    start: Option<Instant>,
+    ref_rgb: Option<Vec3>,
+    samples: VecDeque<LiveSample>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiveStats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+impl LiveReadings {
+    /// Adds a reading, the series restarts when the patch changes
+    pub fn push(&mut self, res: &ReadingResult, delta_e: f64, capacity: usize) {
+        if self.ref_rgb != Some(res.target.ref_rgb) {
+            self.clear();
+            self.ref_rgb = Some(res.target.ref_rgb);
+        }
+
+        let time = self.start.get_or_insert_with(Instant::now).elapsed();
+        let sample = LiveSample {
+            time: time.as_secs_f64(),
+            xyy: res.xyy,
+            cct: res.cct,
+            delta_e,
+        };
+
+        self.push_sample(sample, capacity);
+    }
+
+    fn push_sample(&mut self, sample: LiveSample, capacity: usize) {
+        self.samples.push_back(sample);
+
+        while self.samples.len() > capacity.max(1) {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.start = None;
+        self.ref_rgb = None;
+        self.samples.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn latest(&self) -> Option<&LiveSample> {
+        self.samples.back()
+    }
+
+    /// Time against the metric value
+    pub fn points(&self, metric: LiveMetric) -> Vec<[f64; 2]> {
+        self.samples
+            .iter()
+            .map(|sample| [sample.time, sample.value(metric)])
+            .collect()
+    }
+
+    pub fn stats(&self, metric: LiveMetric) -> Option<LiveStats> {
+        let count = self.samples.len();
+        let mut values = self.samples.iter().map(|sample| sample.value(metric));
+        let first = values.next()?;
+
+        let (min, max, sum) = values.fold((first, first, first), |(min, max, sum), v| {
+            (min.min(v), max.max(v), sum + v)
+        });
+
+        Some(LiveStats {
+            min,
+            max,
+            mean: sum / count as f64,
+        })
+    }
+}
+
+impl LiveSample {
+    pub fn value(&self, metric: LiveMetric) -> f64 {
+        match metric {
+            LiveMetric::Y => self.xyy[2],
+            LiveMetric::SmallX => self.xyy[0],
+            LiveMetric::SmallY => self.xyy[1],
+            LiveMetric::Cct => self.cct,
+            LiveMetric::DeltaE => self.delta_e,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kolor_64::Vec3;
+
+    use crate::calibration::{CalibrationTarget, ReadingResult};
+
+    use super::{LiveMetric, LiveReadings, LiveSample, LiveStats};
+
+    #[test]
+    fn rolling_window_stats() {
+        let mut live = LiveReadings::default();
+        for i in 0..10 {
+            let sample = LiveSample {
+                time: i as f64,
+                xyy: Vec3::new(0.3127, 0.329, 100.0 + i as f64),
+                delta_e: 1.0,
+                ..Default::default()
+            };
+            live.push_sample(sample, 4);
+        }
+
+        let points = live.points(LiveMetric::Y);
+        assert_eq!(
+            points,
+            vec![[6.0, 106.0], [7.0, 107.0], [8.0, 108.0], [9.0, 109.0]]
+        );
+
+        let stats = live.stats(LiveMetric::Y).unwrap();
+        assert_eq!(
+            stats,
+            LiveStats {
+                min: 106.0,
+                max: 109.0,
+                mean: 107.5
+            }
+        );
+        assert_eq!(live.stats(LiveMetric::DeltaE).unwrap().mean, 1.0);
+    }
+
+    #[test]
+    fn restart_on_patch_change() {
+        let reading = |v: f64| ReadingResult {
+            target: CalibrationTarget {
+                ref_rgb: Vec3::splat(v),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut live = LiveReadings::default();
+        live.push(&reading(1.0), 0.5, 100);
+        live.push(&reading(1.0), 0.6, 100);
+        assert_eq!(live.points(LiveMetric::DeltaE).len(), 2);
+
+        live.push(&reading(0.5), 0.7, 100);
+        let points = live.points(LiveMetric::DeltaE);
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0][1], 0.7);
+        assert!(live.stats(LiveMetric::Cct).is_some());
+
+        live.clear();
+        assert!(live.is_empty());
+        assert!(live.stats(LiveMetric::Y).is_none());
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt::Write;
+
+use itertools::Itertools;
+use kolor_64::{ColorConversion, Vec3};
+
+use super::ReadingResult;
+
+pub const ARGYLL_CAL_ENTRIES: usize = 256;
+pub const CUBE_1D_SIZE: usize = 1024;
+
+/// Per-channel greyscale correction curves, from input stimulus to corrected stimulus
+#[derive(Debug, Clone)]
+pub struct Lut1d {
+    points: Vec<(f64, Vec3)>,
+}
+
+impl Lut1d {
+    /// Computes the corrections bringing the white balance and EOTF of the measured greyscale onto target.
+    pub fn from_results(
+        results: &[ReadingResult],
+        target_rgb_to_xyz: ColorConversion,
+    ) -> Option<Self> {
+        // Last reading wins for a given level
+        let greyscale: Vec<(f64, Vec3, f64)> = results
+            .iter()
+            .rev()
+            .filter(|res| res.is_white_stimulus_reading())
+            .unique_by(|res| (res.target.ref_rgb.x * 1e6).round() as i64)
+            .map(|res| {
+                let target_y = res.ref_xyz_display_space(target_rgb_to_xyz, true).y;
+                (res.target.ref_rgb.x, res.rgb, target_y)
+            })
+            .sorted_by(|a, b| a.0.total_cmp(&b.0))
+            .collect();
+
+        if greyscale.len() < 2 {
+            return None;
+        }
+
+        // Measured channel responses, forced monotonic
+        let mut responses: [Vec<(f64, f64)>; 3] = Default::default();
+        if greyscale[0].0 > 0.0 {
+            responses.iter_mut().for_each(|r| r.push((0.0, 0.0)));
+        }
+        for (level, rgb, _) in greyscale.iter() {
+            for (c, response) in responses.iter_mut().enumerate() {
+                let prev = response.last().map(|e| e.1).unwrap_or(f64::MIN);
+                response.push((*level, rgb[c].max(prev)));
+            }
+        }
+
+        // White point correction can only reduce the channels
+        let (_, peak_rgb, peak_target) = greyscale.last().copied()?;
+        let white_scale = (peak_rgb.min_element() / peak_target).min(1.0);
+
+        let mut points = Vec::with_capacity(greyscale.len() + 2);
+        // Black is left untouched
+        points.push((0.0, Vec3::ZERO));
+
+        let mut prev = Vec3::ZERO;
+        for (level, _, target_y) in greyscale.iter().filter(|e| e.0 > 0.0) {
+            let target_y = target_y * white_scale;
+            let corrected: Vec3 = responses
+                .each_ref()
+                .map(|response| inverse_response(response, target_y))
+                .into();
+
+            prev = corrected.max(prev);
+            points.push((*level, prev));
+        }
+
+        if points.last().is_some_and(|p| p.0 < 1.0) {
+            points.push((1.0, Vec3::ONE));
+        }
+
+        Some(Self { points })
+    }
+
+    pub fn sample(&self, v: f64) -> Vec3 {
+        let v = v.clamp(0.0, 1.0);
+        let idx = self.points.partition_point(|p| p.0 < v);
+
+        if idx == 0 {
+            self.points[0].1
+        } else if idx >= self.points.len() {
+            self.points[self.points.len() - 1].1
+        } else {
+            let (x0, y0) = self.points[idx - 1];
+            let (x1, y1) = self.points[idx];
+            let t = (v - x0) / (x1 - x0);
+
+            y0 + (y1 - y0) * t
+        }
+    }
+
+    fn sampled_curve(&self, size: usize) -> impl Iterator<Item = (f64, Vec3)> + '_ {
+        (0..size).map(move |i| {
+            let v = i as f64 / (size - 1) as f64;
+            (v, self.sample(v).clamp(Vec3::ZERO, Vec3::ONE))
+        })
+    }
+
+    pub fn to_argyll_cal(&self) -> String {
+        let mut out = String::with_capacity(ARGYLL_CAL_ENTRIES * 40);
+
+        out.push_str("CAL    \n\n");
+        out.push_str("DESCRIPTOR \"Argyll Device Calibration State\"\n");
+        out.push_str(&format!("ORIGINATOR \"{}\"\n", env!("CARGO_PKG_NAME")));
+        out.push_str("KEYWORD \"DEVICE_CLASS\"\nDEVICE_CLASS \"DISPLAY\"\n");
+        out.push_str("KEYWORD \"COLOR_REP\"\nCOLOR_REP \"RGB\"\n\n");
+        out.push_str("NUMBER_OF_FIELDS 4\n");
+        out.push_str("BEGIN_DATA_FORMAT\nRGB_I RGB_R RGB_G RGB_B\nEND_DATA_FORMAT\n\n");
+        out.push_str(&format!("NUMBER_OF_SETS {ARGYLL_CAL_ENTRIES}\n"));
+        out.push_str("BEGIN_DATA\n");
+        for (v, rgb) in self.sampled_curve(ARGYLL_CAL_ENTRIES) {
+            writeln!(out, "{v:.6} {:.6} {:.6} {:.6}", rgb.x, rgb.y, rgb.z).ok();
+        }
+        out.push_str("END_DATA\n");
+
+        out
+    }
+
+    pub fn to_cube(&self) -> String {
+        let mut out = String::with_capacity(CUBE_1D_SIZE * 30);
+
+        out.push_str(&format!(
+            "TITLE \"{} greyscale calibration\"\n",
+            env!("CARGO_PKG_NAME")
+        ));
+        out.push_str(&format!("LUT_1D_SIZE {CUBE_1D_SIZE}\n"));
+        out.push_str("DOMAIN_MIN 0.0 0.0 0.0\nDOMAIN_MAX 1.0 1.0 1.0\n");
+        for (_, rgb) in self.sampled_curve(CUBE_1D_SIZE) {
+            writeln!(out, "{:.6} {:.6} {:.6}", rgb.x, rgb.y, rgb.z).ok();
+        }
+
+        out
+    }
+}
+
+// Input stimulus producing the requested output, from a monotonic response
+fn inverse_response(response: &[(f64, f64)], target: f64) -> f64 {
+    let idx = response.partition_point(|p| p.1 < target);
+
+    if idx == 0 {
+        response[0].0
+    } else if idx >= response.len() {
+        response[response.len() - 1].0
+    } else {
+        let (x0, y0) = response[idx - 1];
+        let (x1, y1) = response[idx];
+
+        if y1 > y0 {
+            x0 + (x1 - x0) * (target - y0) / (y1 - y0)
+        } else {
+            x0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kolor_64::{ColorConversion, Vec3, spaces::CIE_XYZ};
+
+    use crate::calibration::{CalibrationTarget, LuminanceEotf, ReadingResult};
+
+    use super::Lut1d;
+
+    // Target is gamma 2.2, the display follows `display_eotf`
+    fn greyscale_readings(display_eotf: LuminanceEotf) -> Vec<ReadingResult> {
+        (0..=10)
+            .map(|i| {
+                let target = CalibrationTarget {
+                    ref_rgb: Vec3::splat(i as f64 / 10.0),
+                    ..Default::default()
+                };
+                let display = CalibrationTarget {
+                    eotf: display_eotf,
+                    ..target
+                };
+
+                ReadingResult::simulated(target, display)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identity_for_perfect_display() {
+        let target = CalibrationTarget::default();
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+        let results = greyscale_readings(LuminanceEotf::Gamma22);
+
+        let lut = Lut1d::from_results(&results, target_rgb_to_xyz).unwrap();
+        for v in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            let corrected = lut.sample(v);
+            assert!((corrected - Vec3::splat(v)).abs().max_element() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn corrects_darker_eotf() {
+        let target = CalibrationTarget::default();
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+
+        // Display tracks 2.4 while the target is 2.2, mids must be raised
+        let results = greyscale_readings(LuminanceEotf::Gamma24);
+
+        let lut = Lut1d::from_results(&results, target_rgb_to_xyz).unwrap();
+        let corrected = lut.sample(0.5);
+        let expected = 0.5_f64.powf(2.2 / 2.4);
+        assert!((corrected.x - expected).abs() < 1e-2);
+        assert_eq!(lut.sample(1.0), Vec3::ONE);
+    }
+
+    #[test]
+    fn export_formats() {
+        let target = CalibrationTarget::default();
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+        let results = greyscale_readings(LuminanceEotf::Gamma22);
+        let lut = Lut1d::from_results(&results, target_rgb_to_xyz).unwrap();
+
+        let cal = lut.to_argyll_cal();
+        assert!(cal.starts_with("CAL"));
+        assert!(cal.contains("NUMBER_OF_SETS 256\n"));
+        assert_eq!(
+            cal.lines().filter(|l| l.split(' ').count() == 4).count(),
+            257
+        );
+
+        let cube = lut.to_cube();
+        assert!(cube.contains("LUT_1D_SIZE 1024\n"));
+        assert!(cube.trim_end().ends_with("1.000000 1.000000 1.000000"));
+    }
+}
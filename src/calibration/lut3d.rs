@@ -0,0 +1,399 @@
+use std::{collections::HashMap, fmt::Write};
+
+use itertools::Itertools;
+use kolor_64::{ColorConversion, Mat3, Vec3, spaces::CIE_XYZ};
+
+use super::{
+    CalibrationTarget, LuminanceEotf, Lut1d, ReadingResult, TargetColorspace,
+    reading_result::{quantize_rgb, rgb_cube_levels},
+};
+
+pub const CUBE_3D_SIZE: usize = 33;
+pub const EECOLOR_3D_SIZE: usize = 65;
+pub const LUSTRE_3DL_SIZE: usize = 17;
+
+const MAX_NEWTON_ITERATIONS: usize = 20;
+
+/// Display model fitted from the measurements of a full RGB cube,
+/// used to compute the corrections toward the calibration target
+#[derive(Debug, Clone)]
+pub struct Lut3d {
+    target: CalibrationTarget,
+    target_rgb_to_xyz: ColorConversion,
+
+    /// Stimulus levels of the measured cube, per channel
+    levels: Vec<f64>,
+    /// Measured RGB in target space, encoded with the target EOTF. R changing fastest
+    grid: Vec<Vec3>,
+}
+
+/// LUTs of the last results, only rebuilt when they change
+#[derive(Debug, Default, Clone)]
+pub struct CachedLuts {
+    results: Vec<ReadingResult>,
+    target_csp: Option<TargetColorspace>,
+    lut_1d: Option<Lut1d>,
+    lut_3d: Option<Lut3d>,
+}
+
+impl CachedLuts {
+    pub fn update(
+        &mut self,
+        results: &[ReadingResult],
+        target_csp: TargetColorspace,
+        target_rgb_to_xyz: ColorConversion,
+    ) {
+        if self.target_csp == Some(target_csp) && self.results == results {
+            return;
+        }
+
+        self.lut_1d = Lut1d::from_results(results, target_rgb_to_xyz);
+        self.lut_3d = Lut3d::from_results(results, target_rgb_to_xyz);
+        self.results = results.to_vec();
+        self.target_csp = Some(target_csp);
+    }
+
+    pub fn lut_1d(&self) -> Option<&Lut1d> {
+        self.lut_1d.as_ref()
+    }
+
+    pub fn lut_3d(&self) -> Option<&Lut3d> {
+        self.lut_3d.as_ref()
+    }
+}
+
+impl Lut3d {
+    pub fn from_results(
+        results: &[ReadingResult],
+        target_rgb_to_xyz: ColorConversion,
+    ) -> Option<Self> {
+        // Last reading wins for a given stimulus
+        let measured: HashMap<[i64; 3], &ReadingResult> = results
+            .iter()
+            .map(|res| (quantize_rgb(res.target.ref_rgb), res))
+            .collect();
+
+        let levels = rgb_cube_levels(results)?;
+
+        let size = levels.len();
+        if levels[0] > 0.0 || levels[size - 1] < 1.0 {
+            return None;
+        }
+
+        let target = measured.values().next()?.target;
+        let mut lut = Self {
+            target,
+            target_rgb_to_xyz,
+            levels,
+            grid: Vec::with_capacity(size.pow(3)),
+        };
+
+        for (b, g, r) in itertools::iproduct!(&lut.levels, &lut.levels, &lut.levels) {
+            let res = measured.get(&quantize_rgb(Vec3::new(*r, *g, *b)))?;
+            lut.grid.push(lut.encode(res.rgb));
+        }
+
+        Some(lut)
+    }
+
+    pub fn cube_size(&self) -> usize {
+        self.levels.len()
+    }
+
+    // Interpolating in the encoded domain keeps the model accurate between the cube nodes
+    fn encode(&self, rgb: Vec3) -> Vec3 {
+        let peak = if self.target.eotf == LuminanceEotf::PQ {
+            10_000.0
+        } else {
+            self.target.max_y
+        };
+
+        let encoded = (rgb / peak)
+            .to_array()
+            .map(|c| c.signum() * self.target.eotf.oetf(c.abs()));
+
+        Vec3::from_array(encoded)
+    }
+
+    /// Predicted encoded RGB for a stimulus, trilinear interpolation of the measured cube
+    pub fn model(&self, rgb: Vec3) -> Vec3 {
+        let size = self.levels.len();
+        let (idx, t): (Vec<usize>, Vec<f64>) = rgb
+            .clamp(Vec3::ZERO, Vec3::ONE)
+            .to_array()
+            .into_iter()
+            .map(|v| {
+                let i = self.levels.partition_point(|l| *l <= v).clamp(1, size - 1) - 1;
+                let (l0, l1) = (self.levels[i], self.levels[i + 1]);
+
+                (i, ((v - l0) / (l1 - l0)).clamp(0.0, 1.0))
+            })
+            .unzip();
+
+        let node = |r: usize, g: usize, b: usize| {
+            self.grid[(idx[2] + b) * size * size + (idx[1] + g) * size + idx[0] + r]
+        };
+        let lerp = |a: Vec3, b: Vec3, t: f64| a + (b - a) * t;
+
+        let c00 = lerp(node(0, 0, 0), node(1, 0, 0), t[0]);
+        let c10 = lerp(node(0, 1, 0), node(1, 1, 0), t[0]);
+        let c01 = lerp(node(0, 0, 1), node(1, 0, 1), t[0]);
+        let c11 = lerp(node(0, 1, 1), node(1, 1, 1), t[0]);
+
+        lerp(lerp(c00, c10, t[1]), lerp(c01, c11, t[1]), t[2])
+    }
+
+    /// Target encoded RGB for a stimulus, in the same scale as the measurements
+    pub fn target_rgb(&self, rgb: Vec3) -> Vec3 {
+        let res = ReadingResult {
+            target: CalibrationTarget {
+                ref_rgb: rgb,
+                ..self.target
+            },
+            ..Default::default()
+        };
+        let xyz = res.ref_xyz_display_space(self.target_rgb_to_xyz, true);
+
+        let xyz_to_rgb = ColorConversion::new(CIE_XYZ, self.target.colorspace.to_kolor());
+        self.encode(xyz_to_rgb.convert(xyz))
+    }
+
+    /// Stimulus to send to the display so that it reproduces the target of `rgb`
+    pub fn correct(&self, rgb: Vec3) -> Vec3 {
+        let target = self.target_rgb(rgb);
+
+        // Newton's method on the display model, constrained to the valid range
+        let mut x = rgb;
+        for _ in 0..MAX_NEWTON_ITERATIONS {
+            let residual = self.model(x) - target;
+            let jacobian = self.jacobian(x);
+            if jacobian.determinant().abs() < 1e-12 {
+                break;
+            }
+
+            let next = (x - jacobian.inverse() * residual).clamp(Vec3::ZERO, Vec3::ONE);
+            let step = (next - x).abs().max_element();
+            x = next;
+
+            if step < 1e-7 {
+                break;
+            }
+        }
+
+        x
+    }
+
+    fn jacobian(&self, rgb: Vec3) -> Mat3 {
+        let h = 1e-4;
+        let axes = [Vec3::X, Vec3::Y, Vec3::Z].map(|axis| {
+            let lo = (rgb - axis * h).clamp(Vec3::ZERO, Vec3::ONE);
+            let hi = (rgb + axis * h).clamp(Vec3::ZERO, Vec3::ONE);
+
+            (self.model(hi) - self.model(lo)) / (hi - lo).max_element()
+        });
+
+        Mat3::from_cols(axes[0], axes[1], axes[2])
+    }
+
+    // Corrected LUT nodes, R changing fastest
+    fn sampled_lut(&self, size: usize) -> Vec<Vec3> {
+        let step = |i: usize| i as f64 / (size - 1) as f64;
+
+        itertools::iproduct!(0..size, 0..size, 0..size)
+            .map(|(b, g, r)| self.correct(Vec3::new(step(r), step(g), step(b))))
+            .collect()
+    }
+
+    pub fn to_cube(&self) -> String {
+        let lut = self.sampled_lut(CUBE_3D_SIZE);
+        let mut out = String::with_capacity(lut.len() * 30);
+
+        out.push_str(&format!(
+            "TITLE \"{} 3D calibration\"\n",
+            env!("CARGO_PKG_NAME")
+        ));
+        out.push_str(&format!("LUT_3D_SIZE {CUBE_3D_SIZE}\n"));
+        out.push_str("DOMAIN_MIN 0.0 0.0 0.0\nDOMAIN_MAX 1.0 1.0 1.0\n");
+        for rgb in lut {
+            writeln!(out, "{:.6} {:.6} {:.6}", rgb.x, rgb.y, rgb.z).ok();
+        }
+
+        out
+    }
+
+    /// eeColor box format, B changing fastest
+    pub fn to_eecolor(&self) -> String {
+        let size = EECOLOR_3D_SIZE;
+        let lut = self.sampled_lut(size);
+        let mut out = String::with_capacity(lut.len() * 30);
+
+        for (r, g, b) in itertools::iproduct!(0..size, 0..size, 0..size) {
+            let rgb = lut[b * size * size + g * size + r];
+            writeln!(out, "{:.6} {:.6} {:.6}", rgb.x, rgb.y, rgb.z).ok();
+        }
+
+        out
+    }
+
+    /// Lustre 3DL format as read by madVR, 10 bit input mesh and 12 bit output, B changing fastest
+    pub fn to_3dl(&self) -> String {
+        let size = LUSTRE_3DL_SIZE;
+        let lut = self.sampled_lut(size);
+        let mut out = String::with_capacity(lut.len() * 16);
+
+        let mesh = (0..size)
+            .map(|i| ((i as f64 / (size - 1) as f64) * 1023.0).round() as u16)
+            .join(" ");
+        writeln!(out, "{mesh}").ok();
+
+        for (r, g, b) in itertools::iproduct!(0..size, 0..size, 0..size) {
+            let rgb = (lut[b * size * size + g * size + r] * 4095.0).round();
+            writeln!(out, "{} {} {}", rgb.x as u16, rgb.y as u16, rgb.z as u16).ok();
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kolor_64::{ColorConversion, Vec3, spaces::CIE_XYZ};
+
+    use crate::{
+        calibration::{CalibrationTarget, LuminanceEotf, ReadingResult},
+        generators::internal::PatchListPreset,
+    };
+
+    use super::{CachedLuts, Lut3d};
+
+    // Target is gamma 2.2, the display follows `display_eotf` with `display_gain` per channel
+    fn cube_readings(display_eotf: LuminanceEotf, display_gain: Vec3) -> Vec<ReadingResult> {
+        PatchListPreset::Cube5
+            .rgb_float_list()
+            .into_iter()
+            .map(|rgb| {
+                let target = CalibrationTarget {
+                    ref_rgb: Vec3::from_array(rgb),
+                    ..Default::default()
+                };
+                let display = CalibrationTarget {
+                    eotf: display_eotf,
+                    ref_rgb: target.ref_rgb * display_gain,
+                    ..target
+                };
+
+                ReadingResult::simulated(target, display)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identity_for_perfect_display() {
+        let target = CalibrationTarget::default();
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+        let results = cube_readings(LuminanceEotf::Gamma22, Vec3::ONE);
+
+        let lut = Lut3d::from_results(&results, target_rgb_to_xyz).unwrap();
+        assert_eq!(lut.cube_size(), 5);
+
+        for rgb in [
+            Vec3::ZERO,
+            Vec3::splat(0.5),
+            Vec3::new(0.8, 0.3, 0.1),
+            Vec3::ONE,
+        ] {
+            let corrected = lut.correct(rgb);
+            assert!((corrected - rgb).abs().max_element() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn ignores_patches_off_the_cube() {
+        let target = CalibrationTarget::default();
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+
+        let mut results = cube_readings(LuminanceEotf::Gamma22, Vec3::ONE);
+        results.extend(
+            [Vec3::splat(0.35), Vec3::new(0.9, 0.1, 0.6)]
+                .map(|rgb| ReadingResult::perfect(target, rgb)),
+        );
+
+        let lut = Lut3d::from_results(&results, target_rgb_to_xyz).unwrap();
+        assert_eq!(lut.cube_size(), 5);
+    }
+
+    #[test]
+    fn corrects_channel_gain() {
+        let target = CalibrationTarget::default();
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+
+        // Blue is too strong, the correction must reduce it
+        let gain = Vec3::new(1.0, 1.0, 1.25);
+        let results = cube_readings(LuminanceEotf::Gamma22, gain);
+        let lut = Lut3d::from_results(&results, target_rgb_to_xyz).unwrap();
+
+        let rgb = Vec3::splat(0.5);
+        let corrected = lut.correct(rgb);
+        assert!((corrected.x - 0.5).abs() < 1e-3);
+        assert!((corrected.z - 0.4).abs() < 1e-2);
+
+        let predicted = lut.model(corrected);
+        let expected = lut.target_rgb(rgb);
+        assert!((predicted - expected).abs().max_element() < 1e-3);
+    }
+
+    #[test]
+    fn incomplete_cube() {
+        let target = CalibrationTarget::default();
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+
+        let mut results = cube_readings(LuminanceEotf::Gamma22, Vec3::ONE);
+        results.remove(42);
+        assert!(Lut3d::from_results(&results, target_rgb_to_xyz).is_none());
+    }
+
+    #[test]
+    fn cached_luts_follow_results() {
+        let target = CalibrationTarget::default();
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+        let mut results = cube_readings(LuminanceEotf::Gamma22, Vec3::ONE);
+
+        let mut cache = CachedLuts::default();
+        cache.update(&results, target.colorspace, target_rgb_to_xyz);
+        assert!(cache.lut_1d().is_some());
+        assert_eq!(cache.lut_3d().map(Lut3d::cube_size), Some(5));
+
+        results.remove(42);
+        cache.update(&results, target.colorspace, target_rgb_to_xyz);
+        assert!(cache.lut_1d().is_some());
+        assert!(cache.lut_3d().is_none());
+    }
+
+    #[test]
+    fn export_formats() {
+        let target = CalibrationTarget::default();
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+        let results = cube_readings(LuminanceEotf::Gamma22, Vec3::ONE);
+        let lut = Lut3d::from_results(&results, target_rgb_to_xyz).unwrap();
+
+        let cube = lut.to_cube();
+        assert!(cube.contains("LUT_3D_SIZE 33\n"));
+        assert_eq!(cube.lines().skip(4).count(), 33 * 33 * 33);
+
+        let eecolor = lut.to_eecolor();
+        assert_eq!(eecolor.lines().count(), 65 * 65 * 65);
+        assert!(
+            eecolor
+                .lines()
+                .nth(1)
+                .unwrap()
+                .starts_with("0.000000 0.000000 0.015")
+        );
+
+        let lustre = lut.to_3dl();
+        let mut lines = lustre.lines();
+        assert!(lines.next().unwrap().ends_with("959 1023"));
+        assert_eq!(lines.next(), Some("0 0 0"));
+        assert_eq!(lines.last(), Some("4095 4095 4095"));
+    }
+}
@@ -5,15 +5,49 @@ use kolor_64::{ColorSpace, Vec3};
 use serde::{Deserialize, Serialize};
 use strum::{AsRefStr, Display, EnumIter};
 
+mod abl;
 mod cct;
+mod display_capability;
+mod display_checks;
+mod drift;
+mod explicit_target;
+mod gamut;
+mod live_readings;
 mod luminance_eotf;
+mod lut1d;
+mod lut3d;
 mod reading_result;
+mod results_stats;
+mod session;
+mod target_luminance;
+mod tolerance;
+mod uniformity;
+mod white_balance;
 
+pub use abl::{AblReport, AblSweep};
 pub use cct::xyz_to_cct;
+pub use display_capability::DisplayCapability;
+pub use display_checks::{Additivity, CheckWindow, DisplayCheckStep, DisplayChecks};
+pub use drift::{DriftMonitor, DriftReadingKind, DriftSample};
+pub use explicit_target::{ExplicitTarget, ExplicitTargetKind};
+pub use gamut::{CachedGamutReport, GamutCoverage, GamutReport, GamutVolume, xy_to_uv};
+pub use live_readings::{LiveMetric, LiveReadings, LiveSample, LiveStats};
 pub use luminance_eotf::LuminanceEotf;
+pub use lut1d::Lut1d;
+pub use lut3d::{CachedLuts, Lut3d};
 pub use reading_result::ReadingResult;
+pub use results_stats::{DeltaEStats, PatchGroup, ResultsStats, StatsThresholds};
+pub use session::{matching_result, session_from_csv, session_to_csv};
+pub use target_luminance::TargetLuminanceReadings;
+pub use tolerance::{CachedToleranceGrade, ToleranceGrade, ToleranceProfile};
+pub use uniformity::{
+    MAX_CUSTOM_GRID_SIZE, UniformityDeviation, UniformityGrid, UniformityGridSize,
+};
+pub use white_balance::{
+    WhiteBalanceAdvice, WhiteBalanceControl, WhiteBalanceControls, WhiteBalanceStep,
+};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct CalibrationTarget {
     pub min_y: f64,
     pub max_y: f64,
@@ -23,6 +57,8 @@ pub struct CalibrationTarget {
 
     // Linear
     pub ref_rgb: Vec3,
+    /// Overrides the reference computed from `ref_rgb`
+    pub explicit: Option<ExplicitTarget>,
 }
 
 #[derive(
@@ -82,6 +118,7 @@ impl Default for CalibrationTarget {
             colorspace: Default::default(),
             eotf: Default::default(),
             ref_rgb: Default::default(),
+            explicit: Default::default(),
         }
     }
 }
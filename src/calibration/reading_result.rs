@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use anyhow::{Result, anyhow, bail};
 use deltae::{DEMethod::DE2000, Delta, DeltaE};
 use itertools::Itertools;
@@ -20,7 +22,7 @@ static RESULT_XYZ_REGEX: Lazy<Regex> =
 static RESULT_LAB_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"Lab:\s(-?\d+\.\d+)\s(?<a>-?\d+\.\d+)\s(?<b>-?\d+\.\d+)").unwrap());
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct ReadingResult {
     pub target: CalibrationTarget,
     // From sample, ArgyllCMS spotread value
@@ -171,6 +173,15 @@ impl ReadingResult {
         scale_to_y: bool,
     ) -> Vec3 {
         let is_pq = self.target.eotf == LuminanceEotf::PQ;
+        if let Some(explicit) = self.target.explicit {
+            let xyz = explicit.to_xyz(self.target.max_y);
+            return if !is_pq && !scale_to_y {
+                xyz / self.target.max_y
+            } else {
+                xyz
+            };
+        }
+
         let ref_rgb_clipped = if is_pq {
             // Clip to MDL PQ code, since ref display is expected to clip
             let max_pq = self.target.eotf.oetf(self.target.max_hdr_mdl / 10_000.0);
@@ -261,6 +272,75 @@ impl ReadingResult {
     }
 }
 
+/// Stimulus component rounded for lookups, measured levels aren't exact
+pub(super) fn quantize(v: f64) -> i64 {
+    (v * 1e4).round() as i64
+}
+
+pub(super) fn quantize_rgb(rgb: Vec3) -> [i64; 3] {
+    rgb.to_array().map(quantize)
+}
+
+/// Component levels when the stimuli contain a complete N×N×N RGB cube.
+/// Patches off the cube grid, like extra greyscale steps, are ignored.
+pub(super) fn rgb_cube_levels(results: &[ReadingResult]) -> Option<Vec<f64>> {
+    let measured: HashSet<[i64; 3]> = results
+        .iter()
+        .map(|res| quantize_rgb(res.target.ref_rgb))
+        .collect();
+    let mut levels: Vec<f64> = results
+        .iter()
+        .flat_map(|res| res.target.ref_rgb.to_array())
+        .sorted_by(|a, b| a.total_cmp(b))
+        .dedup_by(|a, b| quantize(*a) == quantize(*b))
+        .collect();
+
+    // Levels off the grid only have a few measured cube nodes, drop them
+    loop {
+        let is_measured = |rgb: [f64; 3]| measured.contains(&quantize_rgb(rgb.into()));
+        let nodes_per_level = levels.iter().map(|l| {
+            itertools::iproduct!(&levels, &levels)
+                .filter(|(a, b)| {
+                    [[*l, **a, **b], [**a, *l, **b], [**a, **b, *l]]
+                        .into_iter()
+                        .all(is_measured)
+                })
+                .count()
+        });
+        let (worst, nodes) = nodes_per_level
+            .enumerate()
+            .min_by_key(|(_, nodes)| *nodes)?;
+
+        let node_count = levels.len().pow(2);
+        if nodes * 2 >= node_count {
+            return (levels.len() >= 2 && nodes == node_count).then_some(levels);
+        }
+        levels.remove(worst);
+    }
+}
+
+#[cfg(test)]
+impl ReadingResult {
+    /// Reading of a display that exactly follows `display`, measured for `target`
+    pub(crate) fn simulated(target: CalibrationTarget, display: CalibrationTarget) -> Self {
+        let display_rgb_to_xyz =
+            ColorConversion::new(display.colorspace.to_kolor(), kolor_64::spaces::CIE_XYZ);
+        let xyz = Self {
+            target: display,
+            ..Default::default()
+        }
+        .ref_xyz_display_space(display_rgb_to_xyz, true);
+
+        Self::from_argyll_results(target, xyz, Vec3::ZERO)
+    }
+
+    /// Perfect reading of the `ref_rgb` stimulus
+    pub(crate) fn perfect(target: CalibrationTarget, ref_rgb: Vec3) -> Self {
+        let target = CalibrationTarget { ref_rgb, ..target };
+        Self::simulated(target, target)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use kolor_64::{
@@ -270,7 +350,7 @@ mod tests {
     };
 
     use crate::{
-        calibration::{LuminanceEotf, TargetColorspace},
+        calibration::{ExplicitTarget, LuminanceEotf, TargetColorspace},
         utils::round_colour,
     };
 
@@ -409,6 +489,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ref_values_from_explicit_target() {
+        // Stimulus is ignored
+        let target = CalibrationTarget {
+            max_y: 120.0,
+            ref_rgb: Vec3::new(1.0, 0.0, 0.0),
+            explicit: ExplicitTarget::parse("xyY", [0.3127, 0.329, 60.0]),
+            ..Default::default()
+        };
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+
+        let ref_xyy = ReadingResult {
+            target,
+            ..Default::default()
+        }
+        .ref_xyy_display_space(target_rgb_to_xyz);
+        assert!(
+            (ref_xyy - Vec3::new(0.3127, 0.329, 60.0))
+                .abs()
+                .max_element()
+                < 1e-9
+        );
+
+        let reading = ReadingResult::perfect(target, target.ref_rgb);
+        let xyz = reading.xyz;
+        assert!(
+            reading
+                .delta_e2000_incl_luminance(target_rgb_to_xyz)
+                .value()
+                < &1e-3
+        );
+
+        let brighter = ReadingResult::from_argyll_results(target, xyz * 1.1, Vec3::ZERO);
+        assert!(brighter.delta_e2000(target_rgb_to_xyz).value() < &1e-3);
+        assert!(
+            brighter
+                .delta_e2000_incl_luminance(target_rgb_to_xyz)
+                .value()
+                > &2.0
+        );
+    }
+
     #[test]
     fn delta_e2000_calc() {
         // 100% stimulus
@@ -468,6 +590,7 @@ mod tests {
             ref_rgb: Vec3::new(0.5, 0.5, 0.5),
             colorspace: TargetColorspace::DisplayP3,
             eotf: LuminanceEotf::PQ,
+            explicit: None,
         };
 
         let xyz = Vec3::new(95.41516, 100.072455, 108.983916);
@@ -492,6 +615,7 @@ mod tests {
             ref_rgb: Vec3::new(0.950147, 0.950147, 0.950147),
             colorspace: TargetColorspace::DisplayP3,
             eotf: LuminanceEotf::PQ,
+            explicit: None,
         };
 
         let xyz = Vec3::new(754.483535, 793.981817, 864.330001);
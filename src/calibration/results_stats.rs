@@ -0,0 +1,263 @@
+use kolor_64::ColorConversion;
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, Display, EnumIter, IntoEnumIterator};
+
+use super::{ReadingResult, ToleranceGrade, cct::xyz_to_duv, tolerance::contrast_failure};
+
+/// Signal level below which a patch is near black
+const NEAR_BLACK_MAX: f64 = 0.2;
+/// Signal level from which a patch is bright
+const BRIGHT_MIN: f64 = 0.8;
+/// Greyscale below this signal level is too noisy for CCT
+const GREYSCALE_CCT_MIN: f64 = 0.1;
+
+#[derive(Display, AsRefStr, Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum PatchGroup {
+    Greyscale,
+    Colours,
+    #[strum(to_string = "Near black")]
+    NearBlack,
+    Mid,
+    Bright,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeltaEStats {
+    pub count: usize,
+    pub average: f64,
+    pub max: f64,
+    pub p95: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResultsStats {
+    pub delta_e: Option<DeltaEStats>,
+    pub groups: Vec<(PatchGroup, DeltaEStats)>,
+    /// Brightest greyscale against the 0% stimulus
+    pub contrast: Option<f64>,
+    pub greyscale_cct: Option<f64>,
+    pub greyscale_duv: Option<f64>,
+}
+
+/// Pass/fail limits of the results summary, graded like a [`super::ToleranceProfile`]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct StatsThresholds {
+    pub max_average_delta_e: f64,
+    pub max_p95_delta_e: f64,
+    pub max_delta_e: f64,
+    pub min_contrast: f64,
+}
+
+impl PatchGroup {
+    pub fn contains(&self, res: &ReadingResult) -> bool {
+        let level = res.target.ref_rgb.max_element();
+
+        match self {
+            Self::Greyscale => res.is_white_stimulus_reading(),
+            Self::Colours => !res.is_white_stimulus_reading(),
+            Self::NearBlack => level < NEAR_BLACK_MAX,
+            Self::Mid => (NEAR_BLACK_MAX..BRIGHT_MIN).contains(&level),
+            Self::Bright => level >= BRIGHT_MIN,
+        }
+    }
+}
+
+impl DeltaEStats {
+    pub fn from_values(values: impl IntoIterator<Item = f64>) -> Option<Self> {
+        let mut values: Vec<f64> = values.into_iter().collect();
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+
+        let count = values.len();
+        // Nearest rank
+        let p95_idx = ((count as f64 * 0.95).ceil() as usize).saturating_sub(1);
+
+        Some(Self {
+            count,
+            average: values.iter().sum::<f64>() / count as f64,
+            max: values[count - 1],
+            p95: values[p95_idx],
+        })
+    }
+}
+
+impl ResultsStats {
+    pub fn from_results(
+        results: &[ReadingResult],
+        target_rgb_to_xyz: ColorConversion,
+        incl_luminance: bool,
+    ) -> Self {
+        let delta_e = |res: &ReadingResult| {
+            let delta_e = if incl_luminance {
+                res.delta_e2000_incl_luminance(target_rgb_to_xyz)
+            } else {
+                res.delta_e2000(target_rgb_to_xyz)
+            };
+            *delta_e.value() as f64
+        };
+
+        let groups = PatchGroup::iter()
+            .filter_map(|group| {
+                let values = results
+                    .iter()
+                    .filter(|res| group.contains(res))
+                    .map(delta_e);
+                DeltaEStats::from_values(values).map(|stats| (group, stats))
+            })
+            .collect();
+
+        let greyscale: Vec<_> = results
+            .iter()
+            .filter(|res| res.is_white_stimulus_reading())
+            .collect();
+
+        let cct_readings: Vec<_> = greyscale
+            .iter()
+            .filter(|res| res.target.ref_rgb.x >= GREYSCALE_CCT_MIN && res.cct > 0.0)
+            .collect();
+        let average = |values: Vec<f64>| {
+            (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+        };
+        let greyscale_cct = average(cct_readings.iter().map(|res| res.cct).collect());
+        let greyscale_duv = average(
+            cct_readings
+                .iter()
+                .filter_map(|res| xyz_to_duv(res.xyz))
+                .collect(),
+        );
+
+        Self {
+            delta_e: DeltaEStats::from_values(results.iter().map(delta_e)),
+            groups,
+            contrast: greyscale_contrast(results),
+            greyscale_cct,
+            greyscale_duv,
+        }
+    }
+
+    /// Exceeded thresholds, none of them tied to a single patch
+    pub fn grade(&self, thresholds: &StatsThresholds) -> ToleranceGrade {
+        let mut failures = Vec::new();
+
+        if let Some(stats) = self.delta_e {
+            let checks = [
+                ("Average dE", stats.average, thresholds.max_average_delta_e),
+                ("95th percentile dE", stats.p95, thresholds.max_p95_delta_e),
+                ("Max dE", stats.max, thresholds.max_delta_e),
+            ];
+            failures.extend(
+                checks
+                    .into_iter()
+                    .filter(|(_, value, max)| value > max)
+                    .map(|(label, value, max)| format!("{label} {value:.2} > {max:.2}")),
+            );
+        }
+
+        failures.extend(contrast_failure(self.contrast, thresholds.min_contrast));
+
+        ToleranceGrade::from_failures(failures)
+    }
+}
+
+/// Brightest greyscale against the 0% stimulus, infinite for a zero black
+pub fn greyscale_contrast<'a>(results: impl IntoIterator<Item = &'a ReadingResult>) -> Option<f64> {
+    let greyscale: Vec<_> = results
+        .into_iter()
+        .filter(|res| res.is_white_stimulus_reading())
+        .collect();
+
+    let black = greyscale.iter().find(|res| res.target.ref_rgb.x == 0.0)?;
+    let white = greyscale_white(greyscale.iter().copied())?;
+
+    let (black_y, white_y) = (black.xyy[2], white.xyy[2]);
+    (white.target.ref_rgb.x > 0.0 && white_y > 0.0).then(|| white_y / black_y.max(0.0))
+}
+
+/// Greyscale reading of the highest stimulus
+pub fn greyscale_white<'a>(
+    results: impl IntoIterator<Item = &'a ReadingResult>,
+) -> Option<&'a ReadingResult> {
+    results
+        .into_iter()
+        .filter(|res| res.is_white_stimulus_reading())
+        .max_by(|a, b| a.target.ref_rgb.x.total_cmp(&b.target.ref_rgb.x))
+}
+
+impl Default for StatsThresholds {
+    fn default() -> Self {
+        Self {
+            max_average_delta_e: 1.0,
+            max_p95_delta_e: 2.0,
+            max_delta_e: 3.0,
+            min_contrast: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kolor_64::{ColorConversion, Vec3, spaces::CIE_XYZ};
+
+    use crate::calibration::{CalibrationTarget, LuminanceEotf, ReadingResult};
+
+    use super::{DeltaEStats, PatchGroup, ResultsStats, StatsThresholds};
+
+    #[test]
+    fn delta_e_percentiles() {
+        let stats = DeltaEStats::from_values((1..=20).map(|v| v as f64 / 10.0)).unwrap();
+        assert_eq!(stats.count, 20);
+        assert!((stats.average - 1.05).abs() < 1e-9);
+        assert_eq!(stats.max, 2.0);
+        assert_eq!(stats.p95, 1.9);
+
+        let single = DeltaEStats::from_values([0.5]).unwrap();
+        assert_eq!(single.p95, 0.5);
+        assert!(DeltaEStats::from_values([]).is_none());
+    }
+
+    #[test]
+    fn greyscale_stats_and_verdict() {
+        let target = CalibrationTarget {
+            min_y: 0.1,
+            max_y: 100.0,
+            eotf: LuminanceEotf::Gamma22,
+            ..Default::default()
+        };
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+
+        // Perfect D65 greyscale
+        let results: Vec<_> = [0.0, 0.1, 0.5, 1.0]
+            .into_iter()
+            .map(|level| ReadingResult::perfect(target, Vec3::splat(level)))
+            .collect();
+
+        let stats = ResultsStats::from_results(&results, target_rgb_to_xyz, true);
+        assert!(stats.delta_e.unwrap().max < 0.01);
+        assert!((stats.contrast.unwrap() - 1000.0).abs() < 1.0);
+        assert!((stats.greyscale_cct.unwrap() - 6504.0).abs() < 5.0);
+        assert!((stats.greyscale_duv.unwrap() - 0.0032).abs() < 1e-4);
+
+        let groups: Vec<_> = stats.groups.iter().map(|(group, _)| *group).collect();
+        assert_eq!(
+            groups,
+            [
+                PatchGroup::Greyscale,
+                PatchGroup::NearBlack,
+                PatchGroup::Mid,
+                PatchGroup::Bright
+            ]
+        );
+
+        let mut thresholds = StatsThresholds::default();
+        assert!(stats.grade(&thresholds).passed());
+
+        thresholds.min_contrast = 2000.0;
+        thresholds.max_delta_e = 0.0;
+        let grade = stats.grade(&thresholds);
+        assert!(grade.failing_patches.is_empty());
+        assert_eq!(grade.failures.len(), 2);
+        assert_eq!(grade.failures[1], "Contrast 1000:1 < 2000:1");
+    }
+}
@@ -0,0 +1,206 @@
+use std::io::Read;
+
+use anyhow::Result;
+use kolor_64::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::Rgb;
+
+use super::{
+    CalibrationTarget, ExplicitTarget, ExplicitTargetKind, LuminanceEotf, ReadingResult,
+    TargetColorspace,
+};
+
+/// One measured patch of a saved session
+#[derive(Debug, Deserialize, Serialize)]
+struct SessionRecord {
+    red: u16,
+    green: u16,
+    blue: u16,
+
+    ref_red: f64,
+    ref_green: f64,
+    ref_blue: f64,
+
+    #[serde(rename = "X")]
+    x: f64,
+    #[serde(rename = "Y")]
+    y: f64,
+    #[serde(rename = "Z")]
+    z: f64,
+
+    min_y: f64,
+    max_y: f64,
+    max_hdr_mdl: f64,
+    eotf: LuminanceEotf,
+    colorspace: TargetColorspace,
+
+    // Older sessions have no explicit target columns
+    #[serde(default)]
+    target_kind: Option<ExplicitTargetKind>,
+    #[serde(default)]
+    target_1: Option<f64>,
+    #[serde(default)]
+    target_2: Option<f64>,
+    #[serde(default)]
+    target_3: Option<f64>,
+}
+
+pub fn session_to_csv(patches: impl Iterator<Item = (Rgb, ReadingResult)>) -> Result<String> {
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    for (rgb, res) in patches {
+        wtr.serialize(SessionRecord::from((rgb, res)))?;
+    }
+
+    Ok(String::from_utf8(wtr.into_inner()?)?)
+}
+
+pub fn session_from_csv<R: Read>(reader: R) -> Result<Vec<(Rgb, ReadingResult)>> {
+    let mut rdr = csv::Reader::from_reader(reader);
+
+    rdr.deserialize::<SessionRecord>()
+        .map(|record| record.map(Into::into).map_err(Into::into))
+        .collect()
+}
+
+/// Result of the same stimulus in another session
+pub fn matching_result<'a>(
+    results: &'a [ReadingResult],
+    res: &ReadingResult,
+) -> Option<&'a ReadingResult> {
+    results.iter().rev().find(|other| {
+        (other.target.ref_rgb - res.target.ref_rgb)
+            .abs()
+            .max_element()
+            < 1e-6
+    })
+}
+
+impl From<(Rgb, ReadingResult)> for SessionRecord {
+    fn from((rgb, res): (Rgb, ReadingResult)) -> Self {
+        let target = res.target;
+
+        Self {
+            red: rgb[0],
+            green: rgb[1],
+            blue: rgb[2],
+            ref_red: target.ref_rgb.x,
+            ref_green: target.ref_rgb.y,
+            ref_blue: target.ref_rgb.z,
+            x: res.xyz.x,
+            y: res.xyz.y,
+            z: res.xyz.z,
+            min_y: target.min_y,
+            max_y: target.max_y,
+            max_hdr_mdl: target.max_hdr_mdl,
+            eotf: target.eotf,
+            colorspace: target.colorspace,
+            target_kind: target.explicit.map(|explicit| explicit.kind),
+            target_1: target.explicit.map(|explicit| explicit.values[0]),
+            target_2: target.explicit.map(|explicit| explicit.values[1]),
+            target_3: target.explicit.map(|explicit| explicit.values[2]),
+        }
+    }
+}
+
+impl From<SessionRecord> for (Rgb, ReadingResult) {
+    fn from(record: SessionRecord) -> Self {
+        let explicit = match (
+            record.target_kind,
+            record.target_1,
+            record.target_2,
+            record.target_3,
+        ) {
+            (Some(kind), Some(v1), Some(v2), Some(v3)) => Some(ExplicitTarget {
+                kind,
+                values: [v1, v2, v3],
+            }),
+            _ => None,
+        };
+        let target = CalibrationTarget {
+            min_y: record.min_y,
+            max_y: record.max_y,
+            colorspace: record.colorspace,
+            eotf: record.eotf,
+            max_hdr_mdl: record.max_hdr_mdl,
+            ref_rgb: Vec3::new(record.ref_red, record.ref_green, record.ref_blue),
+            explicit,
+        };
+        let xyz = Vec3::new(record.x, record.y, record.z);
+
+        (
+            [record.red, record.green, record.blue],
+            ReadingResult::from_argyll_results(target, xyz, Vec3::ZERO),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kolor_64::Vec3;
+
+    use crate::calibration::{CalibrationTarget, ExplicitTarget, LuminanceEotf, ReadingResult};
+
+    use super::{matching_result, session_from_csv, session_to_csv};
+
+    #[test]
+    fn session_roundtrip() {
+        let target = CalibrationTarget {
+            max_y: 120.0,
+            eotf: LuminanceEotf::Gamma24,
+            ref_rgb: Vec3::new(0.5, 0.5, 0.5),
+            explicit: ExplicitTarget::parse("Lab", [50.0, 1.0, -2.0]),
+            ..Default::default()
+        };
+        let res =
+            ReadingResult::from_argyll_results(target, Vec3::new(20.1, 21.2, 22.3), Vec3::ZERO);
+
+        let csv = session_to_csv([([512, 512, 512], res)].into_iter()).unwrap();
+        assert!(csv.starts_with("red,green,blue,ref_red,ref_green,ref_blue,X,Y,Z,"));
+
+        let session = session_from_csv(csv.as_bytes()).unwrap();
+        assert_eq!(session.len(), 1);
+
+        let (rgb, loaded) = session[0];
+        assert_eq!(rgb, [512, 512, 512]);
+        assert_eq!(loaded.xyz, res.xyz);
+        assert_eq!(loaded.xyy, res.xyy);
+        assert_eq!(loaded.target.max_y, 120.0);
+        assert_eq!(loaded.target.eotf, LuminanceEotf::Gamma24);
+        assert_eq!(loaded.target.explicit, target.explicit);
+    }
+
+    #[test]
+    fn match_by_stimulus() {
+        let results: Vec<_> = [0.0, 0.5, 1.0]
+            .into_iter()
+            .map(|v| ReadingResult {
+                target: CalibrationTarget {
+                    ref_rgb: Vec3::splat(v),
+                    ..Default::default()
+                },
+                xyz: Vec3::splat(v * 100.0),
+                ..Default::default()
+            })
+            .collect();
+
+        let res = ReadingResult {
+            target: CalibrationTarget {
+                ref_rgb: Vec3::splat(0.5),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let other = matching_result(&results, &res).unwrap();
+        assert_eq!(other.xyz.y, 50.0);
+
+        let res = ReadingResult {
+            target: CalibrationTarget {
+                ref_rgb: Vec3::new(1.0, 0.0, 0.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(matching_result(&results, &res).is_none());
+    }
+}
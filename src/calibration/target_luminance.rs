@@ -0,0 +1,59 @@
+/// Measures black then white, to use as the target luminance
+#[derive(Debug, Default, Clone)]
+pub struct TargetLuminanceReadings {
+    pub started: bool,
+    /// Continue with the patch sweep once done
+    pub then_sweep: bool,
+    black: Option<f64>,
+}
+
+impl TargetLuminanceReadings {
+    pub fn start(&mut self, then_sweep: bool) {
+        self.started = true;
+        self.then_sweep = then_sweep;
+        self.black = None;
+    }
+
+    pub fn stop(&mut self) {
+        self.started = false;
+        self.black = None;
+    }
+
+    /// Stimulus level of the next reading
+    pub fn next_level(&self) -> f64 {
+        if self.black.is_some() { 1.0 } else { 0.0 }
+    }
+
+    /// Returns the black and white luminance once both were measured
+    pub fn add_reading(&mut self, y: f64) -> Option<(f64, f64)> {
+        match self.black.take() {
+            Some(black) => {
+                self.started = false;
+                Some((black, y))
+            }
+            None => {
+                self.black = Some(y);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TargetLuminanceReadings;
+
+    #[test]
+    fn black_then_white() {
+        let mut readings = TargetLuminanceReadings::default();
+        readings.start(true);
+        assert_eq!(readings.next_level(), 0.0);
+        assert!(readings.add_reading(0.05).is_none());
+
+        assert_eq!(readings.next_level(), 1.0);
+        assert_eq!(readings.add_reading(120.0), Some((0.05, 120.0)));
+        assert!(!readings.started);
+        assert!(readings.then_sweep);
+        assert_eq!(readings.next_level(), 0.0);
+    }
+}
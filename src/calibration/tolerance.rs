@@ -0,0 +1,303 @@
+use kolor_64::ColorConversion;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    PatchGroup, ReadingResult, TargetColorspace,
+    gamut::xy_to_uv,
+    results_stats::{greyscale_contrast, greyscale_white},
+};
+
+/// Named pass/fail limits for verification runs
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ToleranceProfile {
+    pub name: String,
+    pub incl_luminance: bool,
+
+    /// Patches below 20% signal, greyscale or not
+    pub max_delta_e_near_black: f64,
+    pub max_delta_e_greyscale: f64,
+    pub max_delta_e_colours: f64,
+
+    pub max_gamma_deviation: f64,
+    pub max_white_delta_uv: f64,
+    /// Zero to skip
+    pub min_contrast: f64,
+}
+
+/// Grade of the last patches, only recomputed when they or the profile change
+#[derive(Debug, Default, Clone)]
+pub struct CachedToleranceGrade {
+    patches: Vec<(usize, ReadingResult)>,
+    profile: Option<ToleranceProfile>,
+    target_csp: Option<TargetColorspace>,
+    grade: Option<ToleranceGrade>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ToleranceGrade {
+    /// Patch index and the reasons it failed
+    pub failing_patches: Vec<(usize, Vec<String>)>,
+    /// Failures not tied to a single patch
+    pub failures: Vec<String>,
+}
+
+impl ToleranceProfile {
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self {
+                name: "Reference".to_string(),
+                incl_luminance: true,
+                max_delta_e_near_black: 2.0,
+                max_delta_e_greyscale: 1.0,
+                max_delta_e_colours: 2.0,
+                max_gamma_deviation: 0.1,
+                max_white_delta_uv: 0.002,
+                min_contrast: 0.0,
+            },
+            Self {
+                name: "Consumer".to_string(),
+                incl_luminance: true,
+                max_delta_e_near_black: 5.0,
+                max_delta_e_greyscale: 2.0,
+                max_delta_e_colours: 3.0,
+                max_gamma_deviation: 0.2,
+                max_white_delta_uv: 0.004,
+                min_contrast: 0.0,
+            },
+        ]
+    }
+
+    pub fn patch_max_delta_e(&self, res: &ReadingResult) -> f64 {
+        if PatchGroup::NearBlack.contains(res) {
+            self.max_delta_e_near_black
+        } else if PatchGroup::Greyscale.contains(res) {
+            self.max_delta_e_greyscale
+        } else {
+            self.max_delta_e_colours
+        }
+    }
+
+    /// Grades the measured patches, given with their index in the patch list
+    pub fn grade(
+        &self,
+        patches: &[(usize, ReadingResult)],
+        target_rgb_to_xyz: ColorConversion,
+    ) -> ToleranceGrade {
+        let mut grade = ToleranceGrade::default();
+
+        let white = greyscale_white(patches.iter().map(|(_, res)| res));
+        let white_delta_uv = white.map(|res| {
+            let target_xyy = res.ref_xyy_display_space(target_rgb_to_xyz);
+            let [u, v] = xy_to_uv([res.xyy[0], res.xyy[1]]);
+            let [target_u, target_v] = xy_to_uv([target_xyy[0], target_xyy[1]]);
+
+            (u - target_u).hypot(v - target_v)
+        });
+
+        for (i, res) in patches {
+            let mut reasons = Vec::new();
+
+            let delta_e = if self.incl_luminance {
+                res.delta_e2000_incl_luminance(target_rgb_to_xyz)
+            } else {
+                res.delta_e2000(target_rgb_to_xyz)
+            };
+            let delta_e = *delta_e.value() as f64;
+            let max_delta_e = self.patch_max_delta_e(res);
+            if delta_e > max_delta_e {
+                reasons.push(format!("dE {delta_e:.2} > {max_delta_e:.2}"));
+            }
+
+            let gamma_deviation = res
+                .gamma_around_zero()
+                .filter(|_| res.is_white_stimulus_reading());
+            if let Some(deviation) =
+                gamma_deviation.filter(|deviation| deviation.abs() > self.max_gamma_deviation)
+            {
+                reasons.push(format!(
+                    "Gamma deviation {deviation:+.3} > {:.3}",
+                    self.max_gamma_deviation
+                ));
+            }
+
+            let is_white = white.is_some_and(|white| std::ptr::eq(white, res));
+            if let Some(delta_uv) =
+                white_delta_uv.filter(|delta_uv| is_white && *delta_uv > self.max_white_delta_uv)
+            {
+                reasons.push(format!(
+                    "White Δu'v' {delta_uv:.4} > {:.4}",
+                    self.max_white_delta_uv
+                ));
+            }
+
+            if !reasons.is_empty() {
+                grade.failing_patches.push((*i, reasons));
+            }
+        }
+
+        let contrast = greyscale_contrast(patches.iter().map(|(_, res)| res));
+        grade
+            .failures
+            .extend(contrast_failure(contrast, self.min_contrast));
+
+        grade
+    }
+}
+
+impl CachedToleranceGrade {
+    /// Patches are given with their index in the patch list
+    pub fn update(
+        &mut self,
+        patches: impl Iterator<Item = (usize, ReadingResult)> + Clone,
+        profile: Option<&ToleranceProfile>,
+        target_csp: TargetColorspace,
+        target_rgb_to_xyz: ColorConversion,
+    ) {
+        if self.target_csp == Some(target_csp)
+            && self.profile.as_ref() == profile
+            && self.patches.iter().copied().eq(patches.clone())
+        {
+            return;
+        }
+
+        self.patches = patches.collect();
+        self.profile = profile.cloned();
+        self.target_csp = Some(target_csp);
+        self.grade = profile
+            .filter(|_| !self.patches.is_empty())
+            .map(|profile| profile.grade(&self.patches, target_rgb_to_xyz));
+    }
+
+    pub fn grade(&self) -> Option<&ToleranceGrade> {
+        self.grade.as_ref()
+    }
+}
+
+impl ToleranceGrade {
+    /// Only failures not tied to a single patch
+    pub fn from_failures(failures: Vec<String>) -> Self {
+        Self {
+            failing_patches: Vec::new(),
+            failures,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failing_patches.is_empty() && self.failures.is_empty()
+    }
+
+    pub fn patch_failures(&self, idx: usize) -> Option<&[String]> {
+        self.failing_patches
+            .iter()
+            .find(|(i, _)| *i == idx)
+            .map(|(_, reasons)| reasons.as_slice())
+    }
+}
+
+/// Contrast below `min_contrast`, never failing for a zero minimum
+pub(super) fn contrast_failure(contrast: Option<f64>, min_contrast: f64) -> Option<String> {
+    contrast
+        .filter(|contrast| min_contrast > 0.0 && *contrast < min_contrast)
+        .map(|contrast| format!("Contrast {contrast:.0}:1 < {min_contrast:.0}:1"))
+}
+
+impl Default for ToleranceProfile {
+    fn default() -> Self {
+        Self {
+            name: "New profile".to_string(),
+            ..Self::defaults().remove(0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kolor_64::{
+        ColorConversion, Vec3,
+        details::{color::WhitePoint, transform::xyY_to_XYZ},
+        spaces::CIE_XYZ,
+    };
+
+    use crate::calibration::{CalibrationTarget, ReadingResult};
+
+    use super::{CachedToleranceGrade, ToleranceProfile};
+
+    #[test]
+    fn grade_greyscale() {
+        let target = CalibrationTarget {
+            min_y: 0.01,
+            ..Default::default()
+        };
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+
+        // Measured as the target, except for a blue tinted white
+        let white = ReadingResult::perfect(target, Vec3::ONE);
+        let tinted_xyy = white.xyy - Vec3::new(0.003, 0.003, 0.0);
+        let tinted_white = ReadingResult::from_argyll_results(
+            white.target,
+            xyY_to_XYZ(tinted_xyy, WhitePoint::D65),
+            Vec3::ZERO,
+        );
+        let patches = vec![
+            (0, ReadingResult::perfect(target, Vec3::ZERO)),
+            (2, ReadingResult::perfect(target, Vec3::splat(0.5))),
+            (5, tinted_white),
+        ];
+
+        let mut profile = ToleranceProfile::defaults().remove(0);
+        let grade = profile.grade(&patches, target_rgb_to_xyz);
+        assert!(!grade.passed());
+        assert_eq!(grade.failing_patches.len(), 1);
+
+        let reasons = grade.patch_failures(5).unwrap();
+        assert_eq!(reasons.len(), 2);
+        assert!(reasons[0].starts_with("dE"));
+        assert!(reasons[1].starts_with("White Δu'v'"));
+        assert!(grade.patch_failures(2).is_none());
+
+        profile.max_delta_e_greyscale = 5.0;
+        profile.max_white_delta_uv = 0.01;
+        assert!(profile.grade(&patches, target_rgb_to_xyz).passed());
+
+        // 100 nits against 0.01 nits black
+        profile.min_contrast = 20_000.0;
+        let grade = profile.grade(&patches, target_rgb_to_xyz);
+        assert!(grade.failing_patches.is_empty());
+        assert_eq!(grade.failures, ["Contrast 10000:1 < 20000:1"]);
+    }
+
+    #[test]
+    fn cached_grade_follows_profile() {
+        let target = CalibrationTarget::default();
+        let target_rgb_to_xyz = ColorConversion::new(target.colorspace.to_kolor(), CIE_XYZ);
+        let patches = [(0, ReadingResult::perfect(target, Vec3::ONE))];
+
+        let mut cache = CachedToleranceGrade::default();
+        cache.update(
+            patches.into_iter(),
+            None,
+            target.colorspace,
+            target_rgb_to_xyz,
+        );
+        assert!(cache.grade().is_none());
+
+        let mut profile = ToleranceProfile::default();
+        cache.update(
+            patches.into_iter(),
+            Some(&profile),
+            target.colorspace,
+            target_rgb_to_xyz,
+        );
+        assert!(cache.grade().unwrap().passed());
+
+        // Even a perfect reading fails
+        profile.max_delta_e_greyscale = -1.0;
+        cache.update(
+            patches.into_iter(),
+            Some(&profile),
+            target.colorspace,
+            target_rgb_to_xyz,
+        );
+        assert!(!cache.grade().unwrap().passed());
+    }
+}
@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, Display, EnumIter};
+
+use crate::pgen::pattern_config::PGenPatternConfig;
+
+use super::{ReadingResult, xy_to_uv};
+
+/// Upper bound of the custom columns and rows
+pub const MAX_CUSTOM_GRID_SIZE: usize = 15;
+
+#[derive(
+    Display, AsRefStr, Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumIter,
+)]
+pub enum UniformityGridSize {
+    #[default]
+    #[strum(to_string = "3x3")]
+    Grid3x3,
+    #[strum(to_string = "5x5")]
+    Grid5x5,
+    Custom,
+}
+
+/// Measures a patch at every cell of a grid covering the screen
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UniformityGrid {
+    pub grid_size: UniformityGridSize,
+    pub custom_columns: usize,
+    pub custom_rows: usize,
+    /// Stimulus level of the patch, background is black
+    pub level: f64,
+
+    #[serde(skip)]
+    pub started: bool,
+    /// A reading of the current cell is in progress
+    #[serde(skip)]
+    pub measuring: bool,
+    #[serde(skip)]
+    pub current_cell: usize,
+    /// Row major
+    #[serde(skip)]
+    pub results: Vec<Option<ReadingResult>>,
+    /// Pattern config before the measurements started, restored at the end
+    #[serde(skip)]
+    pub previous_pattern_cfg: Option<PGenPatternConfig>,
+}
+
+/// Deviation of a cell relative to the centre cell
+#[derive(Debug, Clone, Copy)]
+pub struct UniformityDeviation {
+    /// Fraction of the centre luminance
+    pub luminance: f64,
+    pub delta_uv: f64,
+}
+
+impl UniformityGrid {
+    /// Columns and rows, the custom size is clamped as it may come from a saved config
+    pub fn dimensions(&self) -> (usize, usize) {
+        let clamp = |v: usize| v.clamp(1, MAX_CUSTOM_GRID_SIZE);
+        match self.grid_size {
+            UniformityGridSize::Grid3x3 => (3, 3),
+            UniformityGridSize::Grid5x5 => (5, 5),
+            UniformityGridSize::Custom => (clamp(self.custom_columns), clamp(self.custom_rows)),
+        }
+    }
+
+    pub fn cell_count(&self) -> usize {
+        let (columns, rows) = self.dimensions();
+        columns * rows
+    }
+
+    /// Middle cell, rounded towards the top left for even dimensions
+    pub fn centre_cell(&self) -> usize {
+        let (columns, rows) = self.dimensions();
+        (rows / 2) * columns + columns / 2
+    }
+
+    /// Column and row of the cell
+    pub fn cell_coords(&self, cell: usize) -> (usize, usize) {
+        let (columns, _) = self.dimensions();
+        (cell % columns, cell / columns)
+    }
+
+    /// Patch size and position, the patch is half the cell size and centred in it
+    pub fn cell_patch_size_and_position(
+        &self,
+        cell: usize,
+        width: u16,
+        height: u16,
+    ) -> ((u16, u16), (u16, u16)) {
+        let (columns, rows) = self.dimensions();
+        let (column, row) = self.cell_coords(cell);
+
+        let cell_w = width as usize / columns;
+        let cell_h = height as usize / rows;
+        let (patch_w, patch_h) = ((cell_w / 2).max(1), (cell_h / 2).max(1));
+
+        let x = column * cell_w + cell_w.saturating_sub(patch_w) / 2;
+        let y = row * cell_h + cell_h.saturating_sub(patch_h) / 2;
+
+        ((patch_w as u16, patch_h as u16), (x as u16, y as u16))
+    }
+
+    /// Starts from the centre, as the other cells are relative to it
+    pub fn start(&mut self, previous_pattern_cfg: PGenPatternConfig) {
+        self.started = true;
+        self.measuring = false;
+        self.current_cell = self.centre_cell();
+        self.results = vec![None; self.cell_count()];
+        self.previous_pattern_cfg = Some(previous_pattern_cfg);
+    }
+
+    /// Stores the reading of the current cell and moves to the next one without a result.
+    /// Returns false once every cell was measured.
+    pub fn set_current_result(&mut self, result: ReadingResult) -> bool {
+        self.measuring = false;
+        if let Some(cell) = self.results.get_mut(self.current_cell) {
+            *cell = Some(result);
+        }
+
+        let count = self.results.len();
+        let next_cell = (1..count)
+            .map(|i| (self.current_cell + i) % count)
+            .find(|i| self.results[*i].is_none());
+
+        if let Some(next_cell) = next_cell {
+            self.current_cell = next_cell;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the pattern config to restore
+    pub fn stop(&mut self) -> Option<PGenPatternConfig> {
+        self.started = false;
+        self.measuring = false;
+        self.previous_pattern_cfg.take()
+    }
+
+    /// Per cell deviation, once the centre was measured
+    pub fn deviations(&self) -> Vec<Option<UniformityDeviation>> {
+        let centre = self.results.get(self.centre_cell()).copied().flatten();
+        let Some(centre) = centre.filter(|res| res.xyy[2] > 0.0) else {
+            return vec![None; self.results.len()];
+        };
+
+        let centre_uv = xy_to_uv([centre.xyy[0], centre.xyy[1]]);
+        self.results
+            .iter()
+            .map(|res| {
+                res.map(|res| {
+                    let [u, v] = xy_to_uv([res.xyy[0], res.xyy[1]]);
+
+                    UniformityDeviation {
+                        luminance: (res.xyy[2] - centre.xyy[2]) / centre.xyy[2],
+                        delta_uv: (u - centre_uv[0]).hypot(v - centre_uv[1]),
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+impl Default for UniformityGrid {
+    fn default() -> Self {
+        Self {
+            grid_size: Default::default(),
+            custom_columns: 7,
+            custom_rows: 5,
+            level: 1.0,
+            started: false,
+            measuring: false,
+            current_cell: 0,
+            results: Default::default(),
+            previous_pattern_cfg: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kolor_64::Vec3;
+
+    use crate::{calibration::ReadingResult, pgen::pattern_config::PGenPatternConfig};
+
+    use super::{MAX_CUSTOM_GRID_SIZE, UniformityGrid, UniformityGridSize};
+
+    #[test]
+    fn grid_cells_positions() {
+        let grid = UniformityGrid {
+            grid_size: UniformityGridSize::Grid3x3,
+            ..Default::default()
+        };
+        assert_eq!(grid.cell_count(), 9);
+        assert_eq!(grid.centre_cell(), 4);
+
+        let (size, pos) = grid.cell_patch_size_and_position(0, 1920, 1080);
+        assert_eq!(size, (320, 180));
+        assert_eq!(pos, (160, 90));
+
+        let (size, pos) = grid.cell_patch_size_and_position(4, 1920, 1080);
+        assert_eq!(size, (320, 180));
+        assert_eq!(pos, (800, 450));
+
+        let (_, pos) = grid.cell_patch_size_and_position(8, 1920, 1080);
+        assert_eq!(pos, (1440, 810));
+
+        let grid = UniformityGrid {
+            grid_size: UniformityGridSize::Custom,
+            custom_columns: 4,
+            custom_rows: 2,
+            ..Default::default()
+        };
+        assert_eq!(grid.centre_cell(), 6);
+        assert_eq!(grid.cell_coords(6), (2, 1));
+
+        // Out of range sizes from a saved config
+        let grid = UniformityGrid {
+            grid_size: UniformityGridSize::Custom,
+            custom_columns: 5000,
+            custom_rows: 0,
+            ..Default::default()
+        };
+        assert_eq!(grid.dimensions(), (MAX_CUSTOM_GRID_SIZE, 1));
+
+        let (size, pos) = grid.cell_patch_size_and_position(14, 10, 10);
+        assert_eq!(size, (1, 5));
+        assert_eq!(pos, (0, 2));
+    }
+
+    #[test]
+    fn deviation_from_centre() {
+        let mut grid = UniformityGrid::default();
+        grid.start(PGenPatternConfig::default());
+
+        assert_eq!(grid.current_cell, 4);
+
+        let mut cells = 0;
+        loop {
+            let (column, _) = grid.cell_coords(grid.current_cell);
+            // Darker with a colour shift on the left edge
+            let (y, x) = if column == 0 {
+                (90.0, 0.3100)
+            } else {
+                (100.0, 0.3127)
+            };
+            let res = ReadingResult {
+                xyy: Vec3::new(x, 0.329, y),
+                ..Default::default()
+            };
+            cells += 1;
+
+            if !grid.set_current_result(res) {
+                break;
+            }
+        }
+        assert_eq!(cells, 9);
+        assert!(grid.stop().is_some());
+
+        let deviations = grid.deviations();
+        let centre = deviations[4].unwrap();
+        assert_eq!(centre.luminance, 0.0);
+        assert_eq!(centre.delta_uv, 0.0);
+
+        let left = deviations[3].unwrap();
+        assert!((left.luminance + 0.1).abs() < 1e-9);
+        assert!(left.delta_uv > 0.001 && left.delta_uv < 0.003);
+        assert_eq!(deviations[5].unwrap().luminance, 0.0);
+    }
+}
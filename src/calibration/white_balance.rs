@@ -0,0 +1,214 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, Display, EnumIter};
+
+use super::ReadingResult;
+
+const CHANNEL_NAMES: [&str; 3] = ["Red", "Green", "Blue"];
+
+/// White balance controls available on the display
+#[derive(
+    Display, AsRefStr, Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumIter,
+)]
+pub enum WhiteBalanceControls {
+    #[default]
+    #[strum(to_string = "2-point")]
+    TwoPoint,
+    #[strum(to_string = "10-point")]
+    TenPoint,
+    #[strum(to_string = "20-point")]
+    TwentyPoint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WhiteBalanceControl {
+    Gain,
+    Offset,
+    /// Stimulus percent of the control point
+    Point(u8),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WhiteBalanceAdvice {
+    /// Signal level of the greyscale patch
+    pub stimulus: f64,
+    pub control: WhiteBalanceControl,
+    /// Relative change of each channel for it to match the luminance, positive to raise
+    pub adjustments: [f64; 3],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WhiteBalanceStep {
+    pub control: WhiteBalanceControl,
+    pub channel: usize,
+    pub adjustment: f64,
+}
+
+impl WhiteBalanceControls {
+    pub fn control_for_stimulus(&self, stimulus: f64) -> WhiteBalanceControl {
+        let points = match self {
+            Self::TwoPoint => {
+                return if stimulus >= 0.5 {
+                    WhiteBalanceControl::Gain
+                } else {
+                    WhiteBalanceControl::Offset
+                };
+            }
+            Self::TenPoint => 10.0,
+            Self::TwentyPoint => 20.0,
+        };
+
+        let point = (stimulus * points).round().clamp(1.0, points) / points;
+        WhiteBalanceControl::Point((point * 100.0).round() as u8)
+    }
+}
+
+impl WhiteBalanceAdvice {
+    /// Only for greyscale readings above black
+    pub fn from_result(res: &ReadingResult, controls: WhiteBalanceControls) -> Option<Self> {
+        let stimulus = res.target.ref_rgb.x;
+        if !res.is_white_stimulus_reading() || stimulus <= 0.01 {
+            return None;
+        }
+
+        let normalized_rgb = res.gamma_normalized_rgb().to_array();
+        if normalized_rgb.iter().any(|c| *c <= 0.0) {
+            return None;
+        }
+
+        Some(Self {
+            stimulus,
+            control: controls.control_for_stimulus(stimulus),
+            adjustments: normalized_rgb.map(|c| (1.0 / c) - 1.0),
+        })
+    }
+
+    /// Largest channel adjustment out of tolerance, one control at a time
+    pub fn next_step(&self, tolerance: f64) -> Option<WhiteBalanceStep> {
+        self.adjustments
+            .iter()
+            .enumerate()
+            .filter(|(_, adjustment)| adjustment.abs() > tolerance)
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+            .map(|(channel, adjustment)| WhiteBalanceStep {
+                control: self.control,
+                channel,
+                adjustment: *adjustment,
+            })
+    }
+
+    /// Next step over all the readings, for the worst one
+    pub fn overall_next_step(advices: &[Self], tolerance: f64) -> Option<WhiteBalanceStep> {
+        advices
+            .iter()
+            .filter_map(|advice| advice.next_step(tolerance))
+            .max_by(|a, b| a.adjustment.abs().total_cmp(&b.adjustment.abs()))
+    }
+}
+
+impl fmt::Display for WhiteBalanceControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gain => write!(f, "Gain"),
+            Self::Offset => write!(f, "Offset"),
+            Self::Point(percent) => write!(f, "{percent}%"),
+        }
+    }
+}
+
+impl fmt::Display for WhiteBalanceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = if self.adjustment > 0.0 {
+            "Raise"
+        } else {
+            "Lower"
+        };
+
+        write!(
+            f,
+            "{}: {direction} {} by ~{:.1}%",
+            self.control,
+            CHANNEL_NAMES[self.channel],
+            self.adjustment.abs() * 100.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kolor_64::Vec3;
+
+    use crate::calibration::{CalibrationTarget, ReadingResult};
+
+    use super::{WhiteBalanceAdvice, WhiteBalanceControl, WhiteBalanceControls};
+
+    fn reading(stimulus: f64, rgb: Vec3) -> ReadingResult {
+        ReadingResult {
+            target: CalibrationTarget {
+                ref_rgb: Vec3::splat(stimulus),
+                ..Default::default()
+            },
+            xyy: Vec3::new(0.3127, 0.329, 50.0),
+            rgb: rgb * 50.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn control_points() {
+        let two_point = WhiteBalanceControls::TwoPoint;
+        assert_eq!(
+            two_point.control_for_stimulus(0.8),
+            WhiteBalanceControl::Gain
+        );
+        assert_eq!(
+            two_point.control_for_stimulus(0.2),
+            WhiteBalanceControl::Offset
+        );
+
+        let twenty_point = WhiteBalanceControls::TwentyPoint;
+        assert_eq!(
+            twenty_point.control_for_stimulus(0.02),
+            WhiteBalanceControl::Point(5)
+        );
+        assert_eq!(
+            twenty_point.control_for_stimulus(0.48),
+            WhiteBalanceControl::Point(50)
+        );
+        assert_eq!(
+            WhiteBalanceControls::TenPoint.control_for_stimulus(1.0),
+            WhiteBalanceControl::Point(100)
+        );
+    }
+
+    #[test]
+    fn next_step_advice() {
+        let controls = WhiteBalanceControls::TwoPoint;
+
+        // Too much red at the top, too little blue near black
+        let bright = reading(0.9, Vec3::new(1.05, 1.0, 0.99));
+        let dark = reading(0.2, Vec3::new(1.0, 1.0, 0.9));
+
+        let bright = WhiteBalanceAdvice::from_result(&bright, controls).unwrap();
+        let step = bright.next_step(0.01).unwrap();
+        assert_eq!(step.control, WhiteBalanceControl::Gain);
+        assert_eq!(step.channel, 0);
+        assert!((step.adjustment + 0.0476).abs() < 1e-3);
+        assert_eq!(step.to_string(), "Gain: Lower Red by ~4.8%");
+        assert!(bright.next_step(0.1).is_none());
+
+        let dark = WhiteBalanceAdvice::from_result(&dark, controls).unwrap();
+        let overall = WhiteBalanceAdvice::overall_next_step(&[bright, dark], 0.01).unwrap();
+        assert_eq!(overall.to_string(), "Offset: Raise Blue by ~11.1%");
+
+        let colour = ReadingResult {
+            target: CalibrationTarget {
+                ref_rgb: Vec3::new(1.0, 0.0, 0.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(WhiteBalanceAdvice::from_result(&colour, controls).is_none());
+    }
+}
@@ -3,7 +3,8 @@ use tokio::sync::mpsc::Sender;
 use tokio_stream::wrappers::ReceiverStream;
 
 use crate::{
-    app::{PGenAppUpdate, ReadFileType},
+    app::{PGenAppUpdate, ReadFileType, SaveFileType},
+    calibration::Lut3d,
     generators::{
         GeneratorClient, GeneratorClientCmd, GeneratorInterface, start_tcp_generator_client,
     },
@@ -23,6 +24,9 @@ pub enum ExternalJobCmd {
     SpotreadDoneMeasuring,
 
     ReadFile(ReadFileType),
+    SaveFile(SaveFileType, String),
+    /// Samples and serialises the LUT, sent back with `PGenAppUpdate::Lut3dExported`
+    ExportLut3d(SaveFileType, Box<Lut3d>),
 }
 
 pub fn start_external_jobs_worker(
@@ -115,6 +119,42 @@ pub fn start_external_jobs_worker(
 
                                 app_tx.try_send(PGenAppUpdate::DoneProcessing).ok();
                             }
+                            ExternalJobCmd::SaveFile(file_type, contents) => {
+                                let title = file_type.title();
+
+                                let mut dialog = rfd::FileDialog::new()
+                                    .set_title(title)
+                                    .set_file_name(file_type.default_file_name());
+                                for (filter_name, exts) in file_type.filters() {
+                                    dialog = dialog.add_filter(*filter_name, exts);
+                                }
+
+                                if let Some(path) = dialog.save_file() {
+                                    match std::fs::write(&path, contents) {
+                                        Ok(_) => log::info!("Saved {title} to {}", path.display()),
+                                        Err(e) => log::error!("Failed saving {title}: {e}"),
+                                    }
+                                }
+
+                                app_tx.try_send(PGenAppUpdate::DoneProcessing).ok();
+                            }
+                            ExternalJobCmd::ExportLut3d(file_type, lut_3d) => {
+                                let app_tx = app_tx.clone();
+
+                                // Solving every LUT node takes a while, don't hold up other jobs
+                                tokio::spawn(async move {
+                                    let res = tokio::task::spawn_blocking(move || match file_type {
+                                        SaveFileType::EeColor => lut_3d.to_eecolor(),
+                                        SaveFileType::Lustre3dl => lut_3d.to_3dl(),
+                                        _ => lut_3d.to_cube(),
+                                    })
+                                    .await;
+
+                                    let contents = res.inspect_err(|e| log::error!("Failed computing 3D LUT: {e}")).ok();
+                                    app_tx.send(PGenAppUpdate::Lut3dExported(file_type, contents)).await.ok();
+                                    app_tx.try_send(PGenAppUpdate::DoneProcessing).ok();
+                                });
+                            }
                         }
                     }
                 }
@@ -3,7 +3,9 @@ use serde::{Deserialize, Serialize};
 use strum::{AsRefStr, Display, EnumIter};
 
 use crate::{
-    calibration::{PatternInsertionConfig, RGB_PRIMARIES, RGB_SECONDARIES, ReadingResult},
+    calibration::{
+        ExplicitTarget, PatternInsertionConfig, RGB_PRIMARIES, RGB_SECONDARIES, ReadingResult,
+    },
     pgen::pattern_config::PGenPatternConfig,
     utils::{Rgb, get_rgb_real_range},
 };
@@ -20,11 +22,17 @@ pub struct InternalGenerator {
     /// Selected patch from list
     pub selected_idx: Option<usize>,
     pub read_selected_continuously: bool,
+
+    /// Show the code values emitted for YCbCr outputs
+    #[serde(default)]
+    pub show_ycbcr_codes: bool,
 }
 
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
 pub struct InternalPattern {
     pub rgb: Rgb,
+    #[serde(default)]
+    pub target: Option<ExplicitTarget>,
 
     #[serde(skip)]
     pub result: Option<ReadingResult>,
@@ -46,6 +54,10 @@ pub enum PatchListPreset {
     SaturationSweep,
     #[strum(to_string = "Min/max brightness")]
     MinMax,
+    #[strum(to_string = "5x5x5 RGB cube")]
+    Cube5,
+    #[strum(to_string = "9x9x9 RGB cube")]
+    Cube9,
 }
 
 impl InternalGenerator {
@@ -142,6 +154,17 @@ impl PatchListPreset {
             Self::MinMax => {
                 vec![[0.0, 0.0, 0.0], [1.0, 1.0, 1.0]]
             }
+            Self::Cube5 => rgb_cube(5),
+            Self::Cube9 => rgb_cube(9),
         }
     }
 }
+
+// R changing fastest
+fn rgb_cube(size: usize) -> Vec<[f64; 3]> {
+    let step = |i: usize| i as f64 / (size - 1) as f64;
+
+    itertools::iproduct!(0..size, 0..size, 0..size)
+        .map(|(b, g, r)| [step(r), step(g), step(b)])
+        .collect()
+}
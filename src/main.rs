@@ -42,10 +42,9 @@ async fn main() -> Result<()> {
         controller.clone(),
     );
 
-    let app = PGenApp::new(app_rx, controller_tx, external_tx);
+    let app = PGenApp::new(app_rx, controller_tx, external_tx, app_tx.clone());
 
     pgen::controller::daemon::start_pgen_controller_worker(controller, controller_rx);
-    app::compute_cie_chromaticity_diagram_worker(app_tx.clone());
 
     let res = eframe::run_native(
         "pgen_client",
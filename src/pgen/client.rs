@@ -1,7 +1,6 @@
 use std::net::SocketAddr;
 use std::time::Duration;
 
-use anyhow::Result;
 use itertools::Itertools;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
@@ -12,15 +11,25 @@ use crate::utils::{Rgb, compute_rgb_range};
 use super::ColorFormat;
 use super::commands::{PGenCommand, PGenCommandResponse, PGenGetConfCommand, PGenSetConfCommand};
 use super::pattern_config::PGenPatternConfig;
+use super::response::{PGenError, PGenResponse};
 
 const PGEN_CMD_END_BYTE_STR: &str = "\x02\x0D";
 const PGEN_CMD_END_BYTES: &[u8] = PGEN_CMD_END_BYTE_STR.as_bytes();
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+// SET commands are answered right away, a silent batch means pipelining isn't supported
+const BATCH_RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[derive(Debug)]
 pub struct PGenClient {
     stream: Option<TcpStream>,
     socket_addr: SocketAddr,
     response_buffer: Vec<u8>,
+    // Received bytes not yet returned as a response
+    buffered_len: usize,
+    response_timeout: Duration,
+    batch_response_timeout: Duration,
+    // Set once a pipelined batch went unanswered, until the address changes
+    sequential_set_commands: bool,
 
     pub connect_state: ConnectState,
 }
@@ -51,12 +60,25 @@ impl PGenClient {
             stream: None,
             socket_addr,
             response_buffer: vec![0; 8192],
+            buffered_len: 0,
+            response_timeout: RESPONSE_TIMEOUT,
+            batch_response_timeout: BATCH_RESPONSE_TIMEOUT,
+            sequential_set_commands: false,
             connect_state: Default::default(),
         }
     }
 
+    /// Client over an already established connection
+    pub fn with_stream(socket_addr: SocketAddr, stream: TcpStream) -> Self {
+        Self {
+            stream: Some(stream),
+            ..Self::new(socket_addr)
+        }
+    }
+
     pub fn set_socket_address(&mut self, socket_addr: &SocketAddr) {
         self.connect_state.connected = false;
+        self.sequential_set_commands = false;
         self.socket_addr.set_ip(socket_addr.ip());
         self.socket_addr.set_port(socket_addr.port());
     }
@@ -69,62 +91,129 @@ impl PGenClient {
         self.connect().await
     }
 
-    async fn send_tcp_command(&mut self, cmd: &str) -> Result<String> {
-        if self.stream.is_none() {
-            return Ok(String::from("Not connected to TCP socket"));
+    async fn send_tcp_command(&mut self, cmd: &str) -> Result<PGenResponse, PGenError> {
+        log::debug!("Sending command {}", cmd);
+        self.write_commands(&format!("{cmd}{PGEN_CMD_END_BYTE_STR}"))
+            .await?;
+
+        let (mut responses, err) = self.read_responses(1, self.response_timeout).await;
+        if let Some(e) = err {
+            return Err(e);
         }
 
-        log::debug!("Sending command {}", cmd);
+        Ok(responses.pop().unwrap_or(PGenResponse::Closed))
+    }
 
-        let stream = self.stream.as_mut().unwrap();
-        stream
-            .write_all(format!("{cmd}{PGEN_CMD_END_BYTE_STR}").as_bytes())
-            .await?;
+    async fn write_commands(&mut self, cmds: &str) -> Result<(), PGenError> {
+        let Some(stream) = self.stream.as_mut() else {
+            return Err(PGenError::NotConnected);
+        };
 
-        let res_bytes = timeout(Duration::from_secs(10), async {
-            let mut n = 0;
-            let mut correct_end_bytes = false;
+        stream.write_all(cmds.as_bytes()).await?;
 
-            while let Ok(read_bytes) = stream.read(&mut self.response_buffer[n..]).await {
-                n += read_bytes;
-                if read_bytes == 0 {
+        Ok(())
+    }
+
+    /// Reads up to `count` framed responses, in the order the commands were sent.
+    /// Returns the responses read before any error.
+    ///
+    /// Bytes past the last response are kept for the next read.
+    async fn read_responses(
+        &mut self,
+        count: usize,
+        response_timeout: Duration,
+    ) -> (Vec<PGenResponse>, Option<PGenError>) {
+        let Some(stream) = self.stream.as_mut() else {
+            return (Vec::new(), Some(PGenError::NotConnected));
+        };
+
+        let buf = &mut self.response_buffer;
+        let n = &mut self.buffered_len;
+        let mut responses = Vec::with_capacity(count);
+
+        let res = timeout(response_timeout, async {
+            loop {
+                while responses.len() < count
+                    && let Some(end) = buf[..*n]
+                        .windows(PGEN_CMD_END_BYTES.len())
+                        .position(|w| w == PGEN_CMD_END_BYTES)
+                {
+                    let response = String::from_utf8_lossy(&buf[..end]);
+                    log::trace!("  Response: {response}");
+                    responses.push(PGenResponse::parse(&response));
+
+                    let frame_len = end + PGEN_CMD_END_BYTES.len();
+                    buf.copy_within(frame_len..*n, 0);
+                    *n -= frame_len;
+                }
+
+                if responses.len() == count {
                     break;
-                } else {
-                    correct_end_bytes =
-                        matches!(&self.response_buffer[n - 2..n], PGEN_CMD_END_BYTES);
-                    if correct_end_bytes {
-                        break;
+                }
+
+                if *n == buf.len() {
+                    buf.resize(buf.len() * 2, 0);
+                }
+
+                let read_bytes = stream.read(&mut buf[*n..]).await?;
+                if read_bytes == 0 {
+                    if *n > 0 {
+                        let response = String::from_utf8_lossy(&buf[..*n]).to_string();
+                        return Err(PGenError::MalformedFraming(response));
                     }
+
+                    log::trace!("  Connection closed");
+                    responses.push(PGenResponse::Closed);
+                    break;
                 }
+                *n += read_bytes;
             }
 
-            if correct_end_bytes {
-                &self.response_buffer[..n - 2]
-            } else {
-                &self.response_buffer[..n]
-            }
+            Ok(())
         })
-        .await?;
+        .await;
+
+        let err = match res {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(e),
+            Err(e) => Some(e.into()),
+        };
 
-        let response = String::from_utf8_lossy(res_bytes).to_string();
-        log::trace!("  Response: {response}");
+        // A late reply would be paired with the next command
+        if matches!(
+            err,
+            Some(PGenError::Timeout | PGenError::MalformedFraming(_))
+        ) {
+            self.reset_stream();
+        }
 
-        Ok(response)
+        (responses, err)
+    }
+
+    /// Drops the connection along with any unread responses
+    fn reset_stream(&mut self) {
+        log::debug!("Resetting connection to {}", self.socket_addr);
+
+        self.stream = None;
+        self.buffered_len = 0;
+        self.connect_state.connected = false;
+    }
+
+    /// Sends a command expecting an `OK:` response, returns its payload
+    async fn send_ok_command(&mut self, cmd: &str) -> Result<String, PGenError> {
+        self.send_tcp_command(cmd).await?.into_ok()
     }
 
     async fn send_heartbeat(&mut self) -> PGenCommandResponse {
-        let is_alive = if let Ok(res) = self.send_tcp_command("IS_ALIVE").await {
-            res == "ALIVE"
-        } else {
-            false
-        };
+        let res = self.send_tcp_command("IS_ALIVE").await;
+        let is_alive = matches!(res, Ok(PGenResponse::Alive));
 
         self.connect_state.connected = is_alive;
 
         PGenCommandResponse::Alive(is_alive)
     }
 
-    pub async fn set_stream(&mut self) -> Result<()> {
+    pub async fn set_stream(&mut self) -> Result<(), PGenError> {
         if self.stream.is_some() {
             self.disconnect().await;
         }
@@ -135,6 +224,7 @@ impl PGenClient {
         )
         .await??;
         self.stream = Some(stream);
+        self.buffered_len = 0;
 
         let stream = self.stream.as_mut().unwrap();
         log::info!("Successfully connected to {}", &stream.peer_addr()?);
@@ -170,7 +260,7 @@ impl PGenClient {
         let res = if self.connect_state.connected {
             self.send_tcp_command("QUIT")
                 .await
-                .map(|res| !res.is_empty())
+                .map(|res| res != PGenResponse::Closed)
         } else {
             log::trace!("Already disconnected");
             Ok(false)
@@ -193,17 +283,10 @@ impl PGenClient {
     }
 
     async fn shutdown_device(&mut self) -> PGenCommandResponse {
-        let res = self
-            .send_tcp_command("CMD:HALT")
-            .await
-            .map(|res| res == "OK:");
-
-        match res {
-            Ok(res) => {
-                if res {
-                    self.connect_state.connected = false;
-                    self.stream = None;
-                }
+        match self.send_ok_command("CMD:HALT").await {
+            Ok(_) => {
+                self.connect_state.connected = false;
+                self.stream = None;
             }
             Err(e) => self.connect_state.error = Some(e.to_string()),
         };
@@ -212,17 +295,10 @@ impl PGenClient {
     }
 
     async fn reboot_device(&mut self) -> PGenCommandResponse {
-        let res = self
-            .send_tcp_command("CMD:REBOOT")
-            .await
-            .map(|res| res == "OK:");
-
-        match &res {
-            Ok(res) => {
-                if *res {
-                    self.connect_state.connected = false;
-                    self.stream = None;
-                }
+        match self.send_ok_command("CMD:REBOOT").await {
+            Ok(_) => {
+                self.connect_state.connected = false;
+                self.stream = None;
             }
             Err(e) => self.connect_state.error = Some(e.to_string()),
         };
@@ -231,7 +307,7 @@ impl PGenClient {
     }
 
     async fn restart_software(&mut self) -> PGenCommandResponse {
-        PGenCommandResponse::Ok(self.send_tcp_command("RESTARTPGENERATOR:").await.is_ok())
+        self.send_tcp_command("RESTARTPGENERATOR:").await.into()
     }
 
     async fn send_test_pattern(&mut self, test_pattern: &PGenTestPattern) -> PGenCommandResponse {
@@ -259,7 +335,7 @@ impl PGenClient {
         let cmd = format!("RGB={rect};{w},{h};0;{r},{g},{b};{bg_r},{bg_b},{bg_g};0,0,{x},{y};-1");
 
         log::info!("Sent pattern RGB: [{r}, {g}, {b}], background: [{bg_r}, {bg_g}, {bg_b}]");
-        PGenCommandResponse::Ok(self.send_tcp_command(&cmd).await.is_ok())
+        self.send_tcp_command(&cmd).await.into()
     }
 
     pub async fn send_multiple_get_conf_commands(
@@ -269,11 +345,9 @@ impl PGenClient {
         let commands_str = commands.iter().map(|c| c.as_ref()).join(":");
         let cmd = format!("CMD:MULTIPLE:{commands_str}");
 
-        let res = self.send_tcp_command(&cmd).await;
-
-        match res {
+        match self.send_ok_command(&cmd).await {
             Ok(res) => {
-                // Skip `OK:\n`
+                // Skip the rest of the `OK:` line
                 let command_results = res.split_terminator('\n').skip(1);
                 let paired_results = command_results
                     .zip(commands.iter().copied())
@@ -282,35 +356,84 @@ impl PGenClient {
                 PGenCommandResponse::MultipleGetConfRes(paired_results)
             }
             Err(e) => {
-                let err_str = e.to_string();
-                self.connect_state.error = Some(err_str.clone());
-                PGenCommandResponse::Errored(err_str)
+                self.connect_state.error = Some(e.to_string());
+                PGenCommandResponse::Errored(e)
             }
         }
     }
 
+    /// Pipelines the commands in a single write, responses come back in order.
+    /// Falls back to one command at a time for devices that don't answer a batch.
     pub async fn send_multiple_set_conf_commands(
         &mut self,
         commands: &[PGenSetConfCommand],
     ) -> PGenCommandResponse {
-        let mut ret = Vec::with_capacity(commands.len());
+        let set_cmd = |cmd: &PGenSetConfCommand| format!("CMD:{}:{}", cmd.as_ref(), cmd.value());
+        log::debug!("Sending {} SET commands", commands.len());
 
-        for cmd in commands.iter().copied() {
-            let cmd_str = format!("CMD:{}:{}", cmd.as_ref(), cmd.value());
-            let res = self
-                .send_tcp_command(&cmd_str)
-                .await
-                .map(|res| res == "OK:");
-            match res {
-                Ok(res) => ret.push((cmd, res)),
-                Err(e) => {
-                    let err_str = e.to_string();
-                    self.connect_state.error = Some(err_str.clone());
-                    return PGenCommandResponse::Errored(err_str);
+        let (mut responses, mut err) = if self.sequential_set_commands {
+            (Vec::new(), None)
+        } else {
+            let batch: String = commands
+                .iter()
+                .map(|cmd| format!("{}{PGEN_CMD_END_BYTE_STR}", set_cmd(cmd)))
+                .collect();
+
+            match self.write_commands(&batch).await {
+                Ok(()) => {
+                    self.read_responses(commands.len(), self.batch_response_timeout)
+                        .await
+                }
+                Err(e) => (Vec::new(), Some(e)),
+            }
+        };
+
+        // Missing responses to a batch, the connection was reset
+        if err == Some(PGenError::Timeout) && self.set_stream().await.is_ok() {
+            log::warn!(
+                "Got {} of {} SET responses, sending SET commands separately from now on",
+                responses.len(),
+                commands.len()
+            );
+            self.connect_state.connected = true;
+            self.sequential_set_commands = true;
+            err = None;
+        }
+
+        if self.sequential_set_commands && err.is_none() {
+            for cmd in commands[responses.len()..].iter() {
+                match self.send_tcp_command(&set_cmd(cmd)).await {
+                    Ok(res) => responses.push(res),
+                    Err(e) => {
+                        err = Some(e);
+                        break;
+                    }
                 }
             }
         }
 
+        if let Some(e) = err.as_ref() {
+            self.connect_state.error = Some(e.to_string());
+        }
+
+        // Commands without a response failed with the batch error
+        let mut responses = responses.into_iter();
+        let ret = commands
+            .iter()
+            .copied()
+            .map(|cmd| {
+                let res = match responses.next() {
+                    Some(res) => res.into_ok().map(|_| ()),
+                    None => Err(err.clone().unwrap_or(PGenError::NotConnected)),
+                };
+                if let Err(e) = res.as_ref() {
+                    log::error!("Failed setting {cmd:?}: {e}");
+                }
+
+                (cmd, res)
+            })
+            .collect();
+
         PGenCommandResponse::MultipleSetConfRes(ret)
     }
 
@@ -319,9 +442,9 @@ impl PGenClient {
             match cmd {
                 PGenCommand::UpdateSocket(socket_addr) => {
                     self.set_socket_address(&socket_addr);
-                    return PGenCommandResponse::Ok(true);
+                    return PGenCommandResponse::Ok;
                 }
-                _ => return PGenCommandResponse::NotConnected,
+                _ => return PGenCommandResponse::Errored(PGenError::NotConnected),
             }
         }
 
@@ -373,3 +496,157 @@ impl PGenTestPattern {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+    use std::time::{Duration, Instant};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::{PGEN_CMD_END_BYTE_STR, PGenClient, PGenResponse};
+    use crate::pgen::commands::{PGenCommandResponse, PGenSetConfCommand};
+    use crate::pgen::response::PGenError;
+    use crate::pgen::{BitDepth, Colorimetry};
+
+    #[tokio::test]
+    async fn set_conf_responses() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let socket_addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 1024];
+
+            loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+
+                let cmds = String::from_utf8_lossy(&buf[..n]).to_string();
+                let mut res = String::new();
+                for cmd in cmds.split_terminator(PGEN_CMD_END_BYTE_STR) {
+                    if cmd.starts_with("IS_ALIVE") {
+                        res.push_str("ALIVE");
+                    } else if cmd.starts_with("CMD:SET_PGENERATOR_CONF_COLORIMETRY") {
+                        res.push_str("ERROR");
+                    } else if cmd.starts_with("CMD:SET_PGENERATOR_CONF_MAX_BPC") {
+                        res.push_str("OK:");
+                    } else {
+                        // Missing end bytes, then close
+                        stream.write_all(b"OK:").await.unwrap();
+                        return;
+                    }
+                    res.push_str(PGEN_CMD_END_BYTE_STR);
+                }
+
+                // Split in two writes to exercise partial reads
+                let (first, second) = res.split_at(res.len() / 2);
+                stream.write_all(first.as_bytes()).await.unwrap();
+                stream.write_all(second.as_bytes()).await.unwrap();
+            }
+        });
+
+        let mut client = PGenClient::new(socket_addr);
+        let PGenCommandResponse::Connect(state) = client.connect().await else {
+            panic!("Expected connect response");
+        };
+        assert!(state.connected);
+
+        let commands = [
+            PGenSetConfCommand::SetBitDepth(BitDepth::Ten),
+            PGenSetConfCommand::SetColorimetry(Colorimetry::Bt2020Rgb),
+            PGenSetConfCommand::SetBitDepth(BitDepth::Eight),
+        ];
+        let PGenCommandResponse::MultipleSetConfRes(res) =
+            client.send_multiple_set_conf_commands(&commands).await
+        else {
+            panic!("Expected set conf response");
+        };
+
+        assert_eq!(res.len(), 3);
+        assert!(res[0].1.is_ok());
+        assert_eq!(res[1].1, Err(PGenError::Device("ERROR".to_string())));
+        assert!(res[2].1.is_ok());
+
+        let res = client.send_tcp_command("CMD:GET_MODE").await;
+        assert!(matches!(res, Err(PGenError::MalformedFraming(_))));
+    }
+
+    #[tokio::test]
+    async fn set_conf_sequential_fallback() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let socket_addr = listener.local_addr().unwrap();
+
+        // Only answers the first command of each segment
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = connections.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                accepted.fetch_add(1, Ordering::SeqCst);
+
+                tokio::spawn(async move {
+                    let mut buf = vec![0; 1024];
+
+                    loop {
+                        let n = stream.read(&mut buf).await.unwrap_or(0);
+                        if n == 0 {
+                            break;
+                        }
+
+                        let cmds = String::from_utf8_lossy(&buf[..n]).to_string();
+                        let res = if cmds.starts_with("IS_ALIVE") {
+                            "ALIVE"
+                        } else {
+                            "OK:"
+                        };
+                        stream
+                            .write_all(format!("{res}{PGEN_CMD_END_BYTE_STR}").as_bytes())
+                            .await
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let mut client = PGenClient::new(socket_addr);
+        client.batch_response_timeout = Duration::from_millis(200);
+        client.connect().await;
+
+        let commands = [
+            PGenSetConfCommand::SetBitDepth(BitDepth::Ten),
+            PGenSetConfCommand::SetColorimetry(Colorimetry::Bt2020Rgb),
+            PGenSetConfCommand::SetBitDepth(BitDepth::Eight),
+        ];
+        let PGenCommandResponse::MultipleSetConfRes(res) =
+            client.send_multiple_set_conf_commands(&commands).await
+        else {
+            panic!("Expected set conf response");
+        };
+
+        assert!(res.iter().all(|(_, res)| res.is_ok()));
+        assert!(client.connect_state.connected);
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+        // No leftover response from the batch
+        let res = client.send_tcp_command("IS_ALIVE").await;
+        assert_eq!(res, Ok(PGenResponse::Alive));
+
+        // Later batches go straight to separate commands, without waiting or reconnecting
+        let start = Instant::now();
+        let PGenCommandResponse::MultipleSetConfRes(res) =
+            client.send_multiple_set_conf_commands(&commands).await
+        else {
+            panic!("Expected set conf response");
+        };
+        assert!(res.iter().all(|(_, res)| res.is_ok()));
+        assert!(start.elapsed() < Duration::from_millis(200));
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+}
@@ -6,6 +6,7 @@ use super::{
     BitDepth, ColorFormat, Colorimetry, DoviMapMode, DynamicRange, HdrEotf, Primaries, QuantRange,
     client::{ConnectState, PGenTestPattern},
     controller::DisplayMode,
+    response::{PGenError, PGenResponse},
 };
 
 #[derive(Debug, Clone)]
@@ -24,21 +25,20 @@ pub enum PGenCommand {
 
 #[derive(Debug, Clone)]
 pub enum PGenCommandResponse {
-    NotConnected,
     Busy,
-    Ok(bool),
-    Errored(String),
+    Ok,
+    Errored(PGenError),
     Alive(bool),
     Connect(ConnectState),
     Quit(ConnectState),
     Shutdown(ConnectState),
     Reboot(ConnectState),
     MultipleGetConfRes(Vec<(PGenGetConfCommand, String)>),
-    // true if the config was properly set
-    MultipleSetConfRes(Vec<(PGenSetConfCommand, bool)>),
+    // Result of each command, in order
+    MultipleSetConfRes(Vec<(PGenSetConfCommand, Result<(), PGenError>)>),
 }
 
-#[derive(Display, AsRefStr, Debug, Copy, Clone)]
+#[derive(Display, AsRefStr, Debug, Copy, Clone, PartialEq, Eq)]
 pub enum PGenGetConfCommand {
     #[strum(to_string = "GET_PGENERATOR_VERSION")]
     GetPGeneratorVersion,
@@ -130,6 +130,22 @@ pub enum PGenSetConfCommand {
     SetHdrMaxFALL(u16),
 }
 
+/// Requested SET value that the device doesn't report back
+#[derive(Debug, Clone)]
+pub struct ConfigMismatch {
+    pub command: PGenSetConfCommand,
+    pub reported: String,
+}
+
+impl From<Result<PGenResponse, PGenError>> for PGenCommandResponse {
+    fn from(res: Result<PGenResponse, PGenError>) -> Self {
+        match res.and_then(PGenResponse::into_ok) {
+            Ok(_) => Self::Ok,
+            Err(e) => Self::Errored(e),
+        }
+    }
+}
+
 impl PGenGetConfCommand {
     pub const fn base_info_commands() -> &'static [Self] {
         &[
@@ -210,7 +226,73 @@ impl PGenSetConfCommand {
         }
     }
 
-    fn base_config_for_dynamic_range(dynamic_range: DynamicRange) -> (bool, bool, bool, Vec<Self>) {
+    /// Command reading back the value, if the PGenerator reports it
+    pub const fn get_command(self) -> Option<PGenGetConfCommand> {
+        let get_cmd = match self {
+            Self::SetDisplayMode(_) => PGenGetConfCommand::GetCurrentMode,
+            Self::SetColorFormat(_) => PGenGetConfCommand::GetColorFormat,
+            Self::SetBitDepth(_) => PGenGetConfCommand::GetBitDepth,
+            Self::SetQuantRange(_) => PGenGetConfCommand::GetQuantRange,
+            Self::SetColorimetry(_) => PGenGetConfCommand::GetColorimetry,
+            Self::SetOutputIsSDR(_) => PGenGetConfCommand::GetOutputIsSDR,
+            Self::SetOutputIsHDR(_) => PGenGetConfCommand::GetOutputIsHDR,
+            Self::SetOutputIsLLDV(_) => PGenGetConfCommand::GetOutputIsLLDV,
+            Self::SetOutputIsStdDovi(_) => PGenGetConfCommand::GetOutputIsStdDovi,
+            Self::SetDoviStatus(_) | Self::SetDoviInterface(_) => return None,
+            Self::SetDoviMapMode(_) => PGenGetConfCommand::GetDoviMapMode,
+            Self::SetHdrEotf(_) => PGenGetConfCommand::GetHdrEotf,
+            Self::SetHdrPrimaries(_) => PGenGetConfCommand::GetHdrPrimaries,
+            Self::SetHdrMaxMdl(_) => PGenGetConfCommand::GetHdrMaxMdl,
+            Self::SetHdrMinMdl(_) => PGenGetConfCommand::GetHdrMinMdl,
+            Self::SetHdrMaxCLL(_) => PGenGetConfCommand::GetHdrMaxCLL,
+            Self::SetHdrMaxFALL(_) => PGenGetConfCommand::GetHdrMaxFALL,
+        };
+
+        Some(get_cmd)
+    }
+
+    fn is_applied(self, get_cmd: PGenGetConfCommand, res: &str) -> bool {
+        match self {
+            Self::SetDisplayMode(mode) => {
+                DisplayMode::try_from_str(get_cmd.parse_string_config(res))
+                    .is_ok_and(|reported| reported.id == mode.id)
+            }
+            Self::SetOutputIsSDR(value)
+            | Self::SetOutputIsHDR(value)
+            | Self::SetOutputIsLLDV(value)
+            | Self::SetOutputIsStdDovi(value) => get_cmd.parse_bool_config(res.to_owned()) == value,
+            _ => get_cmd.parse_number_config::<usize>(res.to_owned()) == Some(self.value()),
+        }
+    }
+
+    /// Compares the requested values to the ones read back from the device.
+    /// Only the last request of each setting is considered.
+    pub fn find_mismatches(
+        requested: &[Self],
+        get_res: &[(PGenGetConfCommand, String)],
+    ) -> Vec<ConfigMismatch> {
+        let last_requests = requested.iter().enumerate().filter(|(i, cmd)| {
+            !requested[i + 1..]
+                .iter()
+                .any(|later| std::mem::discriminant(later) == std::mem::discriminant(*cmd))
+        });
+
+        last_requests
+            .filter_map(|(_, cmd)| {
+                let get_cmd = cmd.get_command()?;
+                let (_, res) = get_res.iter().find(|(res_cmd, _)| *res_cmd == get_cmd)?;
+
+                (!cmd.is_applied(get_cmd, res)).then(|| ConfigMismatch {
+                    command: *cmd,
+                    reported: get_cmd.parse_string_config(res).to_owned(),
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn base_config_for_dynamic_range(
+        dynamic_range: DynamicRange,
+    ) -> (bool, bool, bool, Vec<Self>) {
         let is_sdr = dynamic_range == DynamicRange::Sdr;
         let is_hdr = dynamic_range == DynamicRange::Hdr;
         let is_dovi = dynamic_range == DynamicRange::Dovi;
@@ -267,3 +349,63 @@ impl PGenSetConfCommand {
         commands
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{PGenCommandResponse, PGenGetConfCommand, PGenSetConfCommand};
+    use crate::pgen::{
+        BitDepth, Colorimetry,
+        response::{PGenError, PGenResponse},
+    };
+
+    #[test]
+    fn command_response_from_reply() {
+        let res = PGenCommandResponse::from(Ok(PGenResponse::Ok(String::new())));
+        assert!(matches!(res, PGenCommandResponse::Ok));
+
+        let res = PGenCommandResponse::from(Ok(PGenResponse::Other("ERROR".to_string())));
+        assert!(matches!(
+            res,
+            PGenCommandResponse::Errored(PGenError::Device(e)) if e == "ERROR"
+        ));
+
+        let res = PGenCommandResponse::from(Ok(PGenResponse::Closed));
+        assert!(matches!(
+            res,
+            PGenCommandResponse::Errored(PGenError::NotConnected)
+        ));
+    }
+
+    #[test]
+    fn find_config_mismatches() {
+        let requested = [
+            PGenSetConfCommand::SetColorimetry(Colorimetry::Bt709Ycc),
+            PGenSetConfCommand::SetBitDepth(BitDepth::Ten),
+            PGenSetConfCommand::SetOutputIsHDR(true),
+            PGenSetConfCommand::SetColorimetry(Colorimetry::Bt2020Rgb),
+            PGenSetConfCommand::SetDoviStatus(false),
+        ];
+        let get_res = [
+            (
+                PGenGetConfCommand::GetColorimetry,
+                "GET_PGENERATOR_CONF_COLORIMETRY:9".to_string(),
+            ),
+            (
+                PGenGetConfCommand::GetBitDepth,
+                "GET_PGENERATOR_CONF_MAX_BPC:8".to_string(),
+            ),
+            (
+                PGenGetConfCommand::GetOutputIsHDR,
+                "GET_PGENERATOR_CONF_IS_HDR:1".to_string(),
+            ),
+        ];
+
+        let mismatches = PGenSetConfCommand::find_mismatches(&requested, &get_res);
+        assert_eq!(mismatches.len(), 1);
+        assert!(matches!(
+            mismatches[0].command,
+            PGenSetConfCommand::SetBitDepth(BitDepth::Ten)
+        ));
+        assert_eq!(mismatches[0].reported, "8");
+    }
+}
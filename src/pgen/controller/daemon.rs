@@ -19,8 +19,9 @@ async fn init_command_loop(
     controller_handle: PGenControllerHandle,
     controller_rx: Receiver<PGenControllerCmd>,
 ) {
-    let reconnect_period = std::time::Duration::from_secs(30 * 60);
-    let mut reconnect_stream = interval(reconnect_period);
+    // Reconnection attempts are scheduled by the controller
+    let reconnect_check_period = std::time::Duration::from_secs(1);
+    let mut reconnect_check_stream = interval(reconnect_check_period);
 
     let heartbeat_period = std::time::Duration::from_secs(30);
     let mut heartbeat_stream = interval(heartbeat_period);
@@ -38,11 +39,14 @@ async fn init_command_loop(
                         controller.ctx.egui_ctx.replace(egui_ctx);
                     }
                     PGenControllerCmd::SetInitialState(state) => controller.set_initial_state(state).await,
-                    PGenControllerCmd::UpdateState(state) => controller.state = state,
+                    PGenControllerCmd::UpdateState(state) => controller.update_state(state),
                     PGenControllerCmd::InitialConnect => controller.initial_connect().await,
                     PGenControllerCmd::UpdateSocket(socket_addr) => controller.update_socket(socket_addr).await,
+                    PGenControllerCmd::SwitchDevice(socket_addr) => controller.switch_device(socket_addr).await,
                     PGenControllerCmd::Disconnect => controller.disconnect().await,
-                    PGenControllerCmd::TestPattern(config) => controller.send_pattern_from_cfg(config).await,
+                    PGenControllerCmd::TestPattern(config) => {
+                        controller.send_pattern_from_cfg(config).await.ok();
+                    },
                     PGenControllerCmd::SendCurrentPattern => controller.send_current_pattern().await,
                     PGenControllerCmd::SetBlank => controller.set_blank().await,
                     PGenControllerCmd::PGen(cmd) => {
@@ -55,6 +59,8 @@ async fn init_command_loop(
                         // Restart must be done manually to apply changes
                         controller.send_multiple_set_conf_commands(commands).await
                     },
+                    PGenControllerCmd::ApplyOutputPreset(preset) => controller.apply_output_preset(preset).await,
+                    PGenControllerCmd::DiscoverDevices => controller.discover_devices(),
                 }
 
                 controller.ctx.app_tx.as_ref().and_then(|app_tx| app_tx.try_send(PGenAppUpdate::DoneProcessing).ok());
@@ -64,9 +70,9 @@ async fn init_command_loop(
                 let mut controller = controller_handle.lock().await;
                 controller.send_heartbeat().await;
             }
-            _ = reconnect_stream.tick().fuse() => {
+            _ = reconnect_check_stream.tick().fuse() => {
                 let mut controller = controller_handle.lock().await;
-                controller.reconnect().await;
+                controller.try_reconnect().await;
             }
         }
     }
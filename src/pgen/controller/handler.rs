@@ -1,6 +1,6 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use base64::{Engine as _, engine::general_purpose::STANDARD};
 use tokio::sync::Mutex;
@@ -14,10 +14,14 @@ use crate::pgen::{
     ColorFormat, DynamicRange,
     client::{PGenClient, PGenTestPattern},
     commands::{PGenCommand, PGenCommandResponse, PGenGetConfCommand},
+    discovery,
+    response::PGenError,
 };
 use crate::utils::scale_pattern_config_rgb_values;
 
-use super::{DisplayMode, PGenControllerContext, PGenControllerState};
+use super::{
+    ConnectionStatus, DisplayMode, OutputPreset, PGenControllerContext, PGenControllerState,
+};
 
 pub type PGenControllerHandle = Arc<Mutex<PGenController>>;
 
@@ -26,6 +30,10 @@ pub struct PGenController {
     pub ctx: PGenControllerContext,
     pub state: PGenControllerState,
     pub previous_cfg: Option<PGenPatternConfig>,
+
+    next_reconnect: Option<Instant>,
+    // Applied SET commands, verified on the next restart
+    pending_set_commands: Vec<PGenSetConfCommand>,
 }
 
 impl PGenController {
@@ -43,6 +51,8 @@ impl PGenController {
             ctx,
             state,
             previous_cfg: None,
+            next_reconnect: None,
+            pending_set_commands: Vec::new(),
         }
     }
 
@@ -59,6 +69,13 @@ impl PGenController {
         self.handle_pgen_response(res);
     }
 
+    /// Keeps the connection status, which only the controller drives
+    pub fn update_state(&mut self, state: PGenControllerState) {
+        let connection_status = self.state.connection_status;
+        self.state = state;
+        self.state.connection_status = connection_status;
+    }
+
     pub fn update_ui(&self) {
         if let Some(egui_ctx) = self.ctx.egui_ctx.as_ref() {
             egui_ctx.request_repaint();
@@ -75,29 +92,65 @@ impl PGenController {
     }
 
     pub fn handle_pgen_response(&mut self, res: PGenCommandResponse) {
-        let mut state_updated =
-            !matches!(res, PGenCommandResponse::Busy | PGenCommandResponse::Ok(_));
+        let mut state_updated = !matches!(res, PGenCommandResponse::Busy | PGenCommandResponse::Ok);
         if let PGenCommandResponse::Alive(is_alive) = res {
             state_updated = is_alive != self.state.connected_state.connected;
         };
 
+        let mut connection_lost = false;
+
         match res {
-            PGenCommandResponse::NotConnected => self.state.connected_state.connected = false,
-            PGenCommandResponse::Busy | PGenCommandResponse::Ok(_) => (),
-            PGenCommandResponse::Errored(e) => self.state.connected_state.error = Some(e),
-            PGenCommandResponse::Alive(is_alive) => self.state.connected_state.connected = is_alive,
-            PGenCommandResponse::Connect(state)
-            | PGenCommandResponse::Quit(state)
+            PGenCommandResponse::Busy | PGenCommandResponse::Ok => (),
+            PGenCommandResponse::Errored(PGenError::NotConnected) => {
+                self.state.connected_state.connected = false;
+                connection_lost = true;
+            }
+            PGenCommandResponse::Errored(e) => {
+                connection_lost = e.is_connection_error();
+                self.state.connected_state.error = Some(e.to_string())
+            }
+            PGenCommandResponse::Alive(is_alive) => {
+                self.state.connected_state.connected = is_alive;
+                connection_lost = !is_alive;
+            }
+            PGenCommandResponse::Connect(state) => {
+                self.state.connected_state = state;
+
+                // Reconnection attempts update the status themselves
+                if !self.state.connection_status.is_recovering() {
+                    self.state.connection_status = if self.state.connected_state.connected {
+                        ConnectionStatus::Connected
+                    } else {
+                        ConnectionStatus::Disconnected
+                    };
+                }
+            }
+            PGenCommandResponse::Quit(state)
             | PGenCommandResponse::Shutdown(state)
-            | PGenCommandResponse::Reboot(state) => self.state.connected_state = state,
+            | PGenCommandResponse::Reboot(state) => {
+                self.state.connected_state = state;
+                self.state.connection_status = ConnectionStatus::Disconnected;
+                self.next_reconnect = None;
+            }
             PGenCommandResponse::MultipleGetConfRes(res) => {
                 self.parse_multiple_get_conf_commands_res(res);
+                self.state.update_saved_device_info();
             }
             PGenCommandResponse::MultipleSetConfRes(res) => {
+                connection_lost = res
+                    .iter()
+                    .any(|(_, res)| res.as_ref().is_err_and(PGenError::is_connection_error));
+
                 self.parse_multiple_set_conf_commands_res(&res);
+                self.state.update_saved_device_info();
             }
         }
 
+        if connection_lost && self.state.connection_status != ConnectionStatus::Disconnected {
+            self.connection_lost();
+            state_updated = true;
+        }
+
         self.try_update_app_state(state_updated);
     }
 
@@ -111,10 +164,25 @@ impl PGenController {
         self.handle_pgen_response(res);
     }
 
+    /// Leaves the device config as is, only `switch_device` applies the saved one
     pub async fn update_socket(&mut self, socket_addr: SocketAddr) {
         self.state.socket_addr = socket_addr;
+
+        // Stop reconnecting to the previous address
+        self.next_reconnect = None;
+        // Requested SETs and mismatches belong to the previous device
+        self.pending_set_commands.clear();
+        self.state.config_mismatches.clear();
+        if self.state.connection_status.is_recovering() {
+            self.state.connection_status = ConnectionStatus::Disconnected;
+        }
+
         self.pgen_command(PGenCommand::UpdateSocket(socket_addr))
             .await;
+
+        if self.state.connected_state.connected {
+            self.fetch_base_info().await;
+        }
     }
 
     pub async fn send_heartbeat(&mut self) {
@@ -126,37 +194,174 @@ impl PGenController {
     }
 
     pub async fn initial_connect(&mut self) {
+        self.next_reconnect = None;
+        self.set_connection_status(ConnectionStatus::Connecting);
+
         self.pgen_command(PGenCommand::Connect).await;
         self.fetch_base_info().await;
     }
 
+    /// Connects to a saved device and applies its preferred output config
+    pub async fn switch_device(&mut self, socket_addr: SocketAddr) {
+        self.update_socket(socket_addr).await;
+
+        if !self.state.connected_state.connected {
+            self.initial_connect().await;
+        }
+        self.apply_saved_device_config().await;
+    }
+
+    /// Sets the preferred output config of the saved device, if it differs
+    pub async fn apply_saved_device_config(&mut self) {
+        let Some(current) = self.state.pgen_info.as_ref().map(|e| &e.output_config) else {
+            return;
+        };
+        let Some((name, preferred)) = self
+            .state
+            .saved_device()
+            .and_then(|device| Some((&device.name, device.preferred_config.as_ref()?)))
+        else {
+            return;
+        };
+
+        let commands = preferred.set_conf_commands_diff(current);
+        if commands.is_empty() {
+            return;
+        }
+
+        log::info!("Applying preferred output config of device {name}");
+        self.send_multiple_set_conf_commands(commands).await;
+        self.restart_pgenerator_software(true).await;
+    }
+
+    /// Sends only the differing settings, then restarts once
+    pub async fn apply_output_preset(&mut self, preset: OutputPreset) {
+        let Some(pgen_info) = self.state.pgen_info.as_ref() else {
+            return;
+        };
+
+        let commands: Vec<_> = preset
+            .differences(pgen_info)
+            .into_iter()
+            .flat_map(|diff| diff.commands)
+            .collect();
+        if commands.is_empty() {
+            log::info!("Output preset {} already applied", preset.name);
+            return;
+        }
+
+        log::info!(
+            "Applying output preset {}: {} commands",
+            preset.name,
+            commands.len()
+        );
+        self.send_multiple_set_conf_commands(commands).await;
+        self.restart_pgenerator_software(true).await;
+    }
+
     pub async fn disconnect(&mut self) {
+        self.next_reconnect = None;
+
         if self.state.connected_state.connected {
             self.set_blank().await;
             self.pgen_command(PGenCommand::Quit).await;
         }
+
+        self.set_connection_status(ConnectionStatus::Disconnected);
     }
 
-    pub async fn reconnect(&mut self) {
-        {
-            let mut client = self.ctx.client.lock().await;
+    fn set_connection_status(&mut self, status: ConnectionStatus) {
+        self.state.connection_status = status;
+        self.try_update_app_state(true);
+    }
 
-            // Don't auto connect
-            if !client.connect_state.connected {
-                return;
-            }
+    fn connection_lost(&mut self) {
+        self.state.connected_state.connected = false;
 
-            log::warn!("Reconnecting TCP socket stream");
-            let res = client.set_stream().await;
-            match res {
-                Ok(_) => {
-                    client.connect_state.connected = true;
-                }
-                Err(e) => client.connect_state.error = Some(e.to_string()),
-            };
+        // Failures while restoring are handled by `reconnect`
+        if self.state.connection_status == ConnectionStatus::Connected {
+            log::warn!("Lost connection to {}", self.state.socket_addr);
+            self.schedule_reconnect(1);
         }
+    }
 
-        self.fetch_base_info().await;
+    fn schedule_reconnect(&mut self, attempt: u32) {
+        let delay_secs = ConnectionStatus::reconnect_delay_secs(attempt);
+
+        self.next_reconnect = Some(Instant::now() + Duration::from_secs(delay_secs));
+        self.state.connection_status = ConnectionStatus::Reconnecting {
+            attempt,
+            delay_secs,
+        };
+    }
+
+    /// Reconnects if a scheduled attempt is due
+    pub async fn try_reconnect(&mut self) {
+        if self.next_reconnect.is_some_and(|at| Instant::now() >= at) {
+            self.reconnect().await;
+        }
+    }
+
+    /// Returns whether the connection was restored
+    pub async fn reconnect(&mut self) -> bool {
+        let ConnectionStatus::Reconnecting { attempt, .. } = self.state.connection_status else {
+            return self.state.connected_state.connected;
+        };
+        self.next_reconnect = None;
+
+        log::warn!(
+            "Reconnecting to {} (attempt {attempt})",
+            self.state.socket_addr
+        );
+        self.pgen_command(PGenCommand::Connect).await;
+
+        if self.state.connected_state.connected {
+            self.set_connection_status(ConnectionStatus::Restoring);
+            self.fetch_base_info().await;
+            self.restore_pattern().await;
+        }
+
+        if self.state.connected_state.connected {
+            log::info!("Connection to {} restored", self.state.socket_addr);
+            self.set_connection_status(ConnectionStatus::Connected);
+        } else {
+            self.schedule_reconnect(attempt + 1);
+            self.try_update_app_state(true);
+        }
+
+        self.state.connected_state.connected
+    }
+
+    async fn restore_pattern(&mut self) {
+        if let Some(config) = self.previous_cfg {
+            let pattern = PGenTestPattern::from_config(self.get_color_format(), &config);
+            self.pgen_command(PGenCommand::TestPattern(pattern)).await;
+        }
+    }
+
+    /// Runs in the background, results are sent to the app
+    pub fn discover_devices(&self) {
+        let Some(app_tx) = self.ctx.app_tx.clone() else {
+            return;
+        };
+
+        let port = match self.state.socket_addr.port() {
+            0 => PGenControllerState::default_socket_addr().port(),
+            port => port,
+        };
+        let egui_ctx = self.ctx.egui_ctx.clone();
+
+        tokio::spawn(async move {
+            let devices = discovery::discover_devices(port).await;
+            app_tx
+                .send(PGenAppUpdate::DiscoveredDevices(devices))
+                .await
+                .ok();
+
+            if let Some(egui_ctx) = egui_ctx {
+                egui_ctx.request_repaint();
+            }
+        });
     }
 
     pub async fn fetch_base_info(&mut self) {
@@ -171,14 +376,50 @@ impl PGenController {
     pub async fn restart_pgenerator_software(&mut self, refetch: bool) {
         self.pgen_command(PGenCommand::RestartSoftware).await;
         self.set_blank().await;
+        self.state.config_mismatches.clear();
 
-        if refetch {
+        if !self.pending_set_commands.is_empty() {
+            self.verify_applied_config().await;
+        } else if refetch {
             self.fetch_base_info().await;
         } else {
             self.update_pgenerator_pid().await;
         }
     }
 
+    /// Re-reads the config and reports requested values that didn't take
+    async fn verify_applied_config(&mut self) {
+        // Keep the requested values to verify after the next restart
+        if !self.state.connected_state.connected {
+            return;
+        }
+
+        let requested = std::mem::take(&mut self.pending_set_commands);
+
+        let res = {
+            let mut client = self.ctx.client.lock().await;
+            client
+                .send_multiple_get_conf_commands(PGenGetConfCommand::base_info_commands())
+                .await
+        };
+
+        if let PGenCommandResponse::MultipleGetConfRes(get_res) = &res {
+            let mismatches = PGenSetConfCommand::find_mismatches(&requested, get_res);
+            for mismatch in mismatches.iter() {
+                let cmd = mismatch.command;
+                log::warn!(
+                    "PGenerator ignored {cmd} = {}, reports {}",
+                    cmd.value(),
+                    mismatch.reported
+                );
+            }
+
+            self.state.config_mismatches = mismatches;
+        }
+
+        self.handle_pgen_response(res);
+    }
+
     async fn update_pgenerator_pid(&mut self) {
         self.pgen_command(PGenCommand::MultipleGetConfCommands(&[
             PGenGetConfCommand::GetPGeneratorPid,
@@ -254,11 +495,12 @@ impl PGenController {
             .unwrap_or_default()
     }
 
+    /// Fails if the pattern isn't displayed, it is re-sent once reconnected
     async fn send_pattern_from_cfg_internal(
         &mut self,
         config: PGenPatternConfig,
         update_state: bool,
-    ) {
+    ) -> Result<(), PGenError> {
         // Only send non repeated patterns
         let different_pattern = self.previous_cfg.map(|prev| prev != config).unwrap_or(true);
 
@@ -272,11 +514,35 @@ impl PGenController {
             }
 
             let pattern = PGenTestPattern::from_config(self.get_color_format(), &config);
-            self.pgen_command(PGenCommand::TestPattern(pattern)).await;
+            let res = {
+                let mut client = self.ctx.client.lock().await;
+                client
+                    .send_generic_command(PGenCommand::TestPattern(pattern))
+                    .await
+            };
+
+            let err = match &res {
+                PGenCommandResponse::Errored(e) => Some(e.clone()),
+                _ => None,
+            };
+            self.handle_pgen_response(res);
+
+            if let Some(e) = err {
+                return Err(e);
+            }
+        }
+
+        if self.state.connected_state.connected {
+            Ok(())
+        } else {
+            Err(PGenError::NotConnected)
         }
     }
 
-    pub async fn send_pattern_from_cfg(&mut self, config: PGenPatternConfig) {
+    pub async fn send_pattern_from_cfg(
+        &mut self,
+        config: PGenPatternConfig,
+    ) -> Result<(), PGenError> {
         let mut new_pattern_cfg = PGenPatternConfig {
             bit_depth: config.bit_depth,
             patch_colour: config.patch_colour,
@@ -292,12 +558,13 @@ impl PGenController {
         }
 
         self.send_pattern_from_cfg_internal(new_pattern_cfg, true)
-            .await;
+            .await
     }
 
     pub async fn send_current_pattern(&mut self) {
         self.send_pattern_from_cfg_internal(self.state.pattern_config, false)
-            .await;
+            .await
+            .ok();
     }
 
     pub async fn set_blank(&mut self) {
@@ -306,12 +573,21 @@ impl PGenController {
         config.background_colour = Default::default();
 
         // Blank should not reset pattern config
-        self.send_pattern_from_cfg_internal(config, false).await;
+        self.send_pattern_from_cfg_internal(config, false)
+            .await
+            .ok();
     }
 
-    pub async fn send_pattern_and_wait(&mut self, config: PGenPatternConfig, duration: Duration) {
-        self.send_pattern_from_cfg(config).await;
+    /// Doesn't wait when the pattern failed to send
+    pub async fn send_pattern_and_wait(
+        &mut self,
+        config: PGenPatternConfig,
+        duration: Duration,
+    ) -> Result<(), PGenError> {
+        self.send_pattern_from_cfg(config).await?;
         tokio::time::sleep(duration).await;
+
+        Ok(())
     }
 
     pub fn parse_multiple_get_conf_commands_res(&mut self, res: Vec<(PGenGetConfCommand, String)>) {
@@ -466,11 +742,22 @@ impl PGenController {
             .await;
     }
 
-    pub fn parse_multiple_set_conf_commands_res(&mut self, res: &[(PGenSetConfCommand, bool)]) {
+    pub fn parse_multiple_set_conf_commands_res(
+        &mut self,
+        res: &[(PGenSetConfCommand, Result<(), PGenError>)],
+    ) {
         let mut changed_mode = false;
 
+        self.state.set_conf_errors = res
+            .iter()
+            .filter_map(|(cmd, res)| res.clone().err().map(|e| (*cmd, e)))
+            .collect();
+
         if let Some(pgen_info) = self.state.pgen_info.as_mut() {
-            let successful_sets = res.iter().filter_map(|(cmd, ok)| ok.then_some(*cmd));
+            let successful_sets = res
+                .iter()
+                .filter_map(|(cmd, res)| res.is_ok().then_some(*cmd));
+            self.pending_set_commands.extend(successful_sets.clone());
 
             for cmd in successful_sets {
                 match cmd {
@@ -544,3 +831,157 @@ impl PGenController {
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::PGenController;
+    use crate::app::PGenAppUpdate;
+    use crate::pgen::BitDepth;
+    use crate::pgen::commands::PGenSetConfCommand;
+    use crate::pgen::controller::ConnectionStatus;
+    use crate::pgen::pattern_config::PGenPatternConfig;
+
+    pub(crate) type ReceivedCommands = Arc<Mutex<Vec<Vec<String>>>>;
+
+    // Closes the first connection after its first pattern
+    pub(crate) async fn mock_server() -> (SocketAddr, ReceivedCommands) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let socket_addr = listener.local_addr().unwrap();
+        let received = ReceivedCommands::default();
+
+        let connections = received.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let conn_idx = {
+                    let mut connections = connections.lock().unwrap();
+                    connections.push(Vec::new());
+                    connections.len() - 1
+                };
+                let mut buf = vec![0; 1024];
+
+                loop {
+                    let n = stream.read(&mut buf).await.unwrap_or(0);
+                    if n == 0 {
+                        break;
+                    }
+
+                    let cmd = String::from_utf8_lossy(&buf[..n]).trim_end().to_string();
+                    let res = if cmd.starts_with("IS_ALIVE") {
+                        "ALIVE"
+                    } else {
+                        "OK:"
+                    };
+                    stream
+                        .write_all(format!("{res}\x02\x0D").as_bytes())
+                        .await
+                        .unwrap();
+
+                    let is_pattern = cmd.starts_with("RGB=");
+                    connections.lock().unwrap()[conn_idx].push(cmd);
+                    if conn_idx == 0 && is_pattern {
+                        break;
+                    }
+                }
+            }
+        });
+
+        (socket_addr, received)
+    }
+
+    #[tokio::test]
+    async fn socket_change_drops_pending_set_commands() {
+        let (socket_addr, _) = mock_server().await;
+
+        let mut controller = PGenController::new(None);
+        controller
+            .pending_set_commands
+            .push(PGenSetConfCommand::SetBitDepth(BitDepth::Eight));
+        controller.update_socket(socket_addr).await;
+
+        assert!(controller.pending_set_commands.is_empty());
+    }
+
+    pub(crate) fn pattern(rgb: [u16; 3]) -> PGenPatternConfig {
+        PGenPatternConfig {
+            patch_colour: rgb,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn reconnect_restores_pattern() {
+        let (socket_addr, received) = mock_server().await;
+        let (app_tx, mut app_rx) = tokio::sync::mpsc::channel(64);
+
+        let mut controller = PGenController::new(Some(app_tx));
+        controller.update_socket(socket_addr).await;
+        controller.initial_connect().await;
+        assert_eq!(
+            controller.state.connection_status,
+            ConnectionStatus::Connected
+        );
+
+        assert!(
+            controller
+                .send_pattern_from_cfg(pattern([100, 100, 100]))
+                .await
+                .is_ok()
+        );
+
+        // Connection was closed after the first pattern
+        let res = controller
+            .send_pattern_from_cfg(pattern([512, 256, 128]))
+            .await;
+        assert!(res.is_err());
+        assert!(matches!(
+            controller.state.connection_status,
+            ConnectionStatus::Reconnecting { attempt: 1, .. }
+        ));
+
+        // Don't wait for the backoff
+        controller.next_reconnect = Some(Instant::now());
+        controller.try_reconnect().await;
+        assert_eq!(
+            controller.state.connection_status,
+            ConnectionStatus::Connected
+        );
+
+        let mut statuses = Vec::new();
+        while let Ok(update) = app_rx.try_recv() {
+            if let PGenAppUpdate::NewState(state) = update
+                && statuses.last() != Some(&state.connection_status)
+            {
+                statuses.push(state.connection_status);
+            }
+        }
+        let transitions = statuses
+            .iter()
+            .skip_while(|status| **status != ConnectionStatus::Connected);
+        assert!(transitions.eq(&[
+            ConnectionStatus::Connected,
+            ConnectionStatus::Reconnecting {
+                attempt: 1,
+                delay_secs: 1
+            },
+            ConnectionStatus::Restoring,
+            ConnectionStatus::Connected,
+        ]));
+
+        // The failed pattern was re-sent on the new connection
+        let connections = received.lock().unwrap();
+        assert_eq!(connections.len(), 2);
+        assert!(
+            connections[1]
+                .iter()
+                .any(|cmd| cmd.starts_with("RGB=") && cmd.contains(";512,256,128;"))
+        );
+    }
+}
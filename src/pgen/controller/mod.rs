@@ -18,8 +18,9 @@ pub use handler::PGenControllerHandle;
 use super::{
     BitDepth, ColorFormat, Colorimetry, DoviMapMode, DynamicRange, HdrMetadata, QuantRange,
     client::{ConnectState, PGenClient},
-    commands::{PGenCommand, PGenSetConfCommand},
+    commands::{ConfigMismatch, PGenCommand, PGenSetConfCommand},
     pattern_config::PGenPatternConfig,
+    response::PGenError,
 };
 
 #[derive(Debug)]
@@ -37,13 +38,70 @@ pub struct PGenControllerState {
 
     #[serde(skip)]
     pub connected_state: ConnectState,
+    #[serde(skip)]
+    pub connection_status: ConnectionStatus,
 
     #[serde(skip)]
     pub pgen_info: Option<PGenInfo>,
     pub pattern_config: PGenPatternConfig,
+
+    // Failed commands of the last SET batch
+    #[serde(skip)]
+    pub set_conf_errors: Vec<(PGenSetConfCommand, PGenError)>,
+    // Values ignored by the device, checked after restarting
+    #[serde(skip)]
+    pub config_mismatches: Vec<ConfigMismatch>,
+
+    #[serde(default)]
+    pub saved_devices: Vec<SavedDevice>,
+    #[serde(default)]
+    pub output_presets: Vec<OutputPreset>,
+}
+
+/// Connection state machine, driven by the controller
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    #[default]
+    Disconnected,
+    Connecting,
+    Connected,
+    /// Connection lost, next attempt after `delay_secs`
+    Reconnecting {
+        attempt: u32,
+        delay_secs: u64,
+    },
+    /// Reconnected, fetching the device info and restoring the pattern
+    Restoring,
+}
+
+/// Named output config and display mode
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OutputPreset {
+    pub name: String,
+    pub display_mode: DisplayMode,
+    pub output_config: PGenOutputConfig,
+}
+
+/// Setting that differs from the device
+#[derive(Debug, Clone)]
+pub struct ConfigDifference {
+    pub setting: &'static str,
+    pub current: String,
+    pub new: String,
+    pub commands: Vec<PGenSetConfCommand>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SavedDevice {
+    pub name: String,
+    pub socket_addr: SocketAddr,
+
+    pub last_info: Option<PGenInfo>,
+    /// Applied when connecting to the device
+    pub preferred_config: Option<PGenOutputConfig>,
 }
 
-#[derive(Debug, Default, Clone, Deserialize)]
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct PGenInfo {
     pub version: String,
     pub pid: String,
@@ -53,7 +111,7 @@ pub struct PGenInfo {
     pub output_config: PGenOutputConfig,
 }
 
-#[derive(Debug, Default, Clone, Deserialize)]
+#[derive(Debug, Default, Clone, Deserialize, Serialize, PartialEq)]
 pub struct PGenOutputConfig {
     pub format: ColorFormat,
     pub bit_depth: BitDepth,
@@ -79,6 +137,7 @@ pub enum PGenControllerCmd {
     UpdateState(PGenControllerState),
     UpdateSocket(SocketAddr),
     InitialConnect,
+    SwitchDevice(SocketAddr),
     Disconnect,
     TestPattern(PGenPatternConfig),
     SendCurrentPattern,
@@ -88,6 +147,8 @@ pub enum PGenControllerCmd {
     ChangeDisplayMode(DisplayMode),
     MultipleSetConfCommands(Vec<PGenSetConfCommand>),
     UpdateDynamicRange(DynamicRange),
+    ApplyOutputPreset(OutputPreset),
+    DiscoverDevices,
 }
 
 impl PGenControllerState {
@@ -113,6 +174,26 @@ impl PGenControllerState {
         }
     }
 
+    pub fn saved_device(&self) -> Option<&SavedDevice> {
+        self.saved_devices
+            .iter()
+            .find(|device| device.socket_addr == self.socket_addr)
+    }
+
+    pub fn update_saved_device_info(&mut self) {
+        let Some(pgen_info) = self.pgen_info.as_ref() else {
+            return;
+        };
+
+        if let Some(device) = self
+            .saved_devices
+            .iter_mut()
+            .find(|device| device.socket_addr == self.socket_addr)
+        {
+            device.last_info = Some(pgen_info.clone());
+        }
+    }
+
     pub fn is_dovi_mode(&self) -> bool {
         self.pgen_info
             .as_ref()
@@ -120,7 +201,177 @@ impl PGenControllerState {
     }
 }
 
+impl PGenOutputConfig {
+    /// Settings differing from the `current` config
+    pub fn differences(&self, current: &Self) -> Vec<ConfigDifference> {
+        let mut diffs = Vec::new();
+
+        if self.dynamic_range != current.dynamic_range {
+            let (.., commands) =
+                PGenSetConfCommand::base_config_for_dynamic_range(self.dynamic_range);
+            diffs.push(ConfigDifference::new(
+                "Dynamic range",
+                current.dynamic_range,
+                self.dynamic_range,
+                commands,
+            ));
+        }
+
+        let (meta, current_meta) = (&self.hdr_meta, &current.hdr_meta);
+        let fields = [
+            ConfigDifference::compare(
+                "Color format",
+                current.format,
+                self.format,
+                PGenSetConfCommand::SetColorFormat,
+            ),
+            ConfigDifference::compare(
+                "Bit depth",
+                current.bit_depth,
+                self.bit_depth,
+                PGenSetConfCommand::SetBitDepth,
+            ),
+            ConfigDifference::compare(
+                "Quant range",
+                current.quant_range,
+                self.quant_range,
+                PGenSetConfCommand::SetQuantRange,
+            ),
+            ConfigDifference::compare(
+                "Colorimetry",
+                current.colorimetry,
+                self.colorimetry,
+                PGenSetConfCommand::SetColorimetry,
+            ),
+            ConfigDifference::compare(
+                "DoVi map mode",
+                current.dovi_map_mode,
+                self.dovi_map_mode,
+                PGenSetConfCommand::SetDoviMapMode,
+            ),
+            ConfigDifference::compare(
+                "EOTF",
+                current_meta.eotf,
+                meta.eotf,
+                PGenSetConfCommand::SetHdrEotf,
+            ),
+            ConfigDifference::compare(
+                "Primaries",
+                current_meta.primaries,
+                meta.primaries,
+                PGenSetConfCommand::SetHdrPrimaries,
+            ),
+            ConfigDifference::compare(
+                "Max MDL",
+                current_meta.max_mdl,
+                meta.max_mdl,
+                PGenSetConfCommand::SetHdrMaxMdl,
+            ),
+            ConfigDifference::compare(
+                "Min MDL",
+                current_meta.min_mdl,
+                meta.min_mdl,
+                PGenSetConfCommand::SetHdrMinMdl,
+            ),
+            ConfigDifference::compare(
+                "MaxCLL",
+                current_meta.maxcll,
+                meta.maxcll,
+                PGenSetConfCommand::SetHdrMaxCLL,
+            ),
+            ConfigDifference::compare(
+                "MaxFALL",
+                current_meta.maxfall,
+                meta.maxfall,
+                PGenSetConfCommand::SetHdrMaxFALL,
+            ),
+        ];
+        diffs.extend(fields.into_iter().flatten());
+
+        diffs
+    }
+
+    /// Commands changing the `current` config into this one
+    pub fn set_conf_commands_diff(&self, current: &Self) -> Vec<PGenSetConfCommand> {
+        self.differences(current)
+            .into_iter()
+            .flat_map(|diff| diff.commands)
+            .collect()
+    }
+}
+
+impl ConfigDifference {
+    fn new(
+        setting: &'static str,
+        current: impl std::fmt::Display,
+        new: impl std::fmt::Display,
+        commands: Vec<PGenSetConfCommand>,
+    ) -> Self {
+        Self {
+            setting,
+            current: current.to_string(),
+            new: new.to_string(),
+            commands,
+        }
+    }
+
+    fn compare<T: PartialEq + std::fmt::Display + Copy>(
+        setting: &'static str,
+        current: T,
+        new: T,
+        command: fn(T) -> PGenSetConfCommand,
+    ) -> Option<Self> {
+        (current != new).then(|| Self::new(setting, current, new, vec![command(new)]))
+    }
+}
+
+impl OutputPreset {
+    pub fn from_info(name: String, pgen_info: &PGenInfo) -> Self {
+        Self {
+            name,
+            display_mode: pgen_info.current_display_mode,
+            output_config: pgen_info.output_config.clone(),
+        }
+    }
+
+    pub fn differences(&self, pgen_info: &PGenInfo) -> Vec<ConfigDifference> {
+        let mut diffs = Vec::new();
+
+        let mode = self.display_mode;
+        let current_mode = pgen_info.current_display_mode;
+        if !mode.same_timing(&current_mode) {
+            // Mode IDs are specific to the connected display
+            let device_mode = pgen_info
+                .display_modes
+                .iter()
+                .find(|device_mode| device_mode.same_timing(&mode));
+
+            let (new, commands) = match device_mode {
+                Some(device_mode) => (
+                    device_mode.to_string(),
+                    vec![PGenSetConfCommand::SetDisplayMode(*device_mode)],
+                ),
+                None => (format!("{mode} (unavailable)"), Vec::new()),
+            };
+            diffs.push(ConfigDifference::new(
+                "Display mode",
+                current_mode,
+                new,
+                commands,
+            ));
+        }
+
+        diffs.extend(self.output_config.differences(&pgen_info.output_config));
+
+        diffs
+    }
+}
+
 impl DisplayMode {
+    pub fn same_timing(&self, other: &Self) -> bool {
+        self.resolution == other.resolution && self.refresh_rate == other.refresh_rate
+    }
+
     pub fn try_from_str(line: &str) -> Result<Self> {
         let mut chars = line.chars();
 
@@ -162,8 +413,46 @@ impl Default for PGenControllerState {
         Self {
             socket_addr: PGenControllerState::default_socket_addr(),
             connected_state: Default::default(),
+            connection_status: Default::default(),
             pgen_info: Default::default(),
             pattern_config: Default::default(),
+            set_conf_errors: Default::default(),
+            config_mismatches: Default::default(),
+            saved_devices: Default::default(),
+            output_presets: Default::default(),
+        }
+    }
+}
+
+impl ConnectionStatus {
+    const MAX_RECONNECT_DELAY_SECS: u64 = 60;
+
+    /// Exponential backoff starting at 1 s
+    pub fn reconnect_delay_secs(attempt: u32) -> u64 {
+        2_u64
+            .saturating_pow(attempt.saturating_sub(1))
+            .min(Self::MAX_RECONNECT_DELAY_SECS)
+    }
+
+    pub const fn is_recovering(&self) -> bool {
+        matches!(self, Self::Reconnecting { .. } | Self::Restoring)
+    }
+}
+
+impl std::fmt::Display for ConnectionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Disconnected => f.write_str("Not connected"),
+            Self::Connecting => f.write_str("Connecting"),
+            Self::Connected => f.write_str("Connected"),
+            Self::Reconnecting {
+                attempt,
+                delay_secs,
+            } => write!(
+                f,
+                "Connection lost, reconnect attempt {attempt} in {delay_secs} s"
+            ),
+            Self::Restoring => f.write_str("Restoring device info and pattern"),
         }
     }
 }
@@ -179,7 +468,8 @@ impl std::fmt::Display for DisplayMode {
 
 #[cfg(test)]
 mod test {
-    use super::DisplayMode;
+    use super::{ConnectionStatus, DisplayMode, OutputPreset, PGenInfo, PGenOutputConfig};
+    use crate::pgen::{BitDepth, DynamicRange, commands::PGenSetConfCommand};
 
     #[test]
     fn parse_display_mode_str() {
@@ -190,4 +480,84 @@ mod test {
         assert_eq!(mode.resolution, (1920, 1080));
         assert_eq!(mode.refresh_rate, 59.94);
     }
+
+    #[test]
+    fn output_config_diff() {
+        let current = PGenOutputConfig::default();
+        assert!(current.set_conf_commands_diff(&current).is_empty());
+
+        let mut preferred = current.clone();
+        preferred.bit_depth = BitDepth::Eight;
+        preferred.hdr_meta.maxcll = 1000;
+
+        let commands = preferred.set_conf_commands_diff(&current);
+        assert_eq!(commands.len(), 2);
+        assert!(matches!(
+            commands[0],
+            PGenSetConfCommand::SetBitDepth(BitDepth::Eight)
+        ));
+        assert!(matches!(
+            commands[1],
+            PGenSetConfCommand::SetHdrMaxCLL(1000)
+        ));
+
+        preferred.dynamic_range = DynamicRange::Hdr;
+        let commands = preferred.set_conf_commands_diff(&current);
+        assert!(matches!(
+            commands[0],
+            PGenSetConfCommand::SetOutputIsSDR(false)
+        ));
+        assert_eq!(commands.len(), 8);
+    }
+
+    #[test]
+    fn reconnect_backoff() {
+        let delays: Vec<_> = (1..=8)
+            .map(ConnectionStatus::reconnect_delay_secs)
+            .collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(ConnectionStatus::reconnect_delay_secs(100), 60);
+    }
+
+    #[test]
+    fn output_preset_differences() {
+        let mode_1080p = DisplayMode {
+            id: 16,
+            resolution: (1920, 1080),
+            refresh_rate: 60.0,
+        };
+        let mode_2160p = DisplayMode {
+            id: 97,
+            resolution: (3840, 2160),
+            refresh_rate: 23.976,
+        };
+        let pgen_info = PGenInfo {
+            current_display_mode: mode_1080p,
+            display_modes: vec![mode_1080p, mode_2160p],
+            ..Default::default()
+        };
+
+        let mut preset = OutputPreset::from_info("HDR10".to_string(), &pgen_info);
+        assert!(preset.differences(&pgen_info).is_empty());
+
+        // Same timing under another ID, from another display
+        preset.display_mode = DisplayMode {
+            id: 5,
+            ..mode_2160p
+        };
+        preset.output_config.bit_depth = BitDepth::Eight;
+
+        let diffs = preset.differences(&pgen_info);
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].setting, "Display mode");
+        assert!(matches!(
+            diffs[0].commands[..],
+            [PGenSetConfCommand::SetDisplayMode(DisplayMode {
+                id: 97,
+                ..
+            })]
+        ));
+        assert_eq!(diffs[1].current, "10-bit");
+        assert_eq!(diffs[1].new, "8-bit");
+    }
 }
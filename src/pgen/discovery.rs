@@ -0,0 +1,189 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::Duration;
+
+use futures::{StreamExt, stream};
+use tokio::{net::TcpStream, time::timeout};
+
+use super::{
+    client::PGenClient,
+    commands::{PGenCommand, PGenCommandResponse, PGenGetConfCommand},
+};
+
+const PROBE_CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+// Hosts that accept the connection but never answer
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_CONCURRENT_PROBES: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredDevice {
+    pub socket_addr: SocketAddr,
+    pub version: String,
+}
+
+/// Probes every host of the local /24 subnet on `port`
+pub async fn discover_devices(port: u16) -> Vec<DiscoveredDevice> {
+    let Some(local_ip) = local_ipv4() else {
+        log::error!("Discovery: failed finding the local IPv4 address");
+        return Vec::new();
+    };
+
+    log::info!("Discovering PGenerator devices from {local_ip} on port {port}");
+    probe_devices(subnet_socket_addrs(local_ip, port)).await
+}
+
+pub async fn probe_devices(socket_addrs: Vec<SocketAddr>) -> Vec<DiscoveredDevice> {
+    let mut devices: Vec<_> = stream::iter(socket_addrs)
+        .map(probe_device)
+        .buffer_unordered(MAX_CONCURRENT_PROBES)
+        .filter_map(|device| async move { device })
+        .collect()
+        .await;
+    devices.sort_by_key(|device| device.socket_addr);
+
+    devices
+}
+
+/// A device is only listed if it answers `IS_ALIVE`
+pub async fn probe_device(socket_addr: SocketAddr) -> Option<DiscoveredDevice> {
+    let res = timeout(PROBE_TIMEOUT, probe_pgenerator(socket_addr)).await;
+    if res.is_err() {
+        log::debug!("Discovery: {socket_addr} timed out");
+    }
+
+    res.ok().flatten()
+}
+
+async fn probe_pgenerator(socket_addr: SocketAddr) -> Option<DiscoveredDevice> {
+    let stream = timeout(PROBE_CONNECT_TIMEOUT, TcpStream::connect(socket_addr))
+        .await
+        .ok()?
+        .ok()?;
+    let mut client = PGenClient::with_stream(socket_addr, stream);
+
+    let is_alive = matches!(
+        client.send_generic_command(PGenCommand::IsAlive).await,
+        PGenCommandResponse::Alive(true)
+    );
+    if !is_alive {
+        log::debug!("Discovery: {socket_addr} is not a PGenerator");
+        return None;
+    }
+
+    let version_cmd = PGenGetConfCommand::GetPGeneratorVersion;
+    let version = match client.send_multiple_get_conf_commands(&[version_cmd]).await {
+        PGenCommandResponse::MultipleGetConfRes(res) => res
+            .first()
+            .map(|(cmd, res)| cmd.parse_string_config(res).to_owned()),
+        _ => None,
+    }
+    .unwrap_or_else(|| "Unknown".to_string());
+
+    client.send_generic_command(PGenCommand::Quit).await;
+    log::info!("Discovery: found PGenerator {version} at {socket_addr}");
+
+    Some(DiscoveredDevice {
+        socket_addr,
+        version,
+    })
+}
+
+fn subnet_socket_addrs(local_ip: Ipv4Addr, port: u16) -> Vec<SocketAddr> {
+    let [a, b, c, _] = local_ip.octets();
+
+    (1..=254)
+        .map(|d| Ipv4Addr::new(a, b, c, d))
+        .filter(|ip| *ip != local_ip)
+        .map(|ip| SocketAddr::new(IpAddr::V4(ip), port))
+        .collect()
+}
+
+// Connecting a UDP socket sends nothing, only picks the outgoing interface
+fn local_ipv4() -> Option<Ipv4Addr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(8, 8, 8, 8), 80)).ok()?;
+
+    match socket.local_addr().ok()?.ip() {
+        IpAddr::V4(ip) if !ip.is_loopback() && !ip.is_unspecified() => Some(ip),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::{probe_devices, subnet_socket_addrs};
+
+    async fn mock_server(alive_response: &'static str) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let socket_addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 1024];
+
+            loop {
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                if n == 0 {
+                    break;
+                }
+
+                let cmd = String::from_utf8_lossy(&buf[..n]).to_string();
+                let res = if cmd.starts_with("IS_ALIVE") {
+                    alive_response.to_string()
+                } else if cmd.starts_with("CMD:MULTIPLE:GET_PGENERATOR_VERSION") {
+                    "OK:\nGET_PGENERATOR_VERSION:1.6.1".to_string()
+                } else {
+                    break;
+                };
+
+                stream
+                    .write_all(format!("{res}\x02\x0D").as_bytes())
+                    .await
+                    .unwrap();
+            }
+        });
+
+        socket_addr
+    }
+
+    // Accepts the connection but never answers
+    async fn silent_server() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let socket_addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (_stream, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        socket_addr
+    }
+
+    #[tokio::test]
+    async fn probe_mock_devices() {
+        let pgen_addr = mock_server("ALIVE").await;
+        let other_addr = mock_server("HELLO").await;
+        let silent_addr = silent_server().await;
+
+        let devices = probe_devices(vec![pgen_addr, other_addr, silent_addr]).await;
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].socket_addr, pgen_addr);
+        assert_eq!(devices[0].version, "1.6.1");
+    }
+
+    #[test]
+    fn subnet_addrs() {
+        let addrs = subnet_socket_addrs(Ipv4Addr::new(192, 168, 1, 10), 85);
+        assert_eq!(addrs.len(), 253);
+        assert_eq!(addrs[0].to_string(), "192.168.1.1:85");
+        assert!(
+            !addrs
+                .iter()
+                .any(|addr| addr.to_string() == "192.168.1.10:85")
+        );
+    }
+}
@@ -4,7 +4,10 @@ use strum::{AsRefStr, Display, EnumIter, FromRepr};
 pub mod client;
 pub mod commands;
 pub mod controller;
+pub mod discovery;
 pub mod pattern_config;
+pub mod response;
+pub mod ycbcr;
 
 #[derive(
     Display,
@@ -117,7 +120,7 @@ pub enum Colorimetry {
     Bt2020Rgb = 9,
 }
 
-#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[derive(Debug, Default, Clone, Deserialize, Serialize, PartialEq)]
 pub struct HdrMetadata {
     pub eotf: HdrEotf,
     pub primaries: Primaries,
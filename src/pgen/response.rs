@@ -0,0 +1,102 @@
+use std::fmt;
+
+/// Parsed response of the PGenerator to a command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PGenResponse {
+    /// `OK:` followed by the rest of the response
+    Ok(String),
+    Alive,
+    /// Connection closed without a response
+    Closed,
+    Other(String),
+}
+
+/// Why a command sent to the PGenerator failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PGenError {
+    NotConnected,
+    Timeout,
+    /// Response missing the end bytes
+    MalformedFraming(String),
+    /// Unexpected response from the device
+    Device(String),
+    Io(String),
+}
+
+impl PGenResponse {
+    pub fn parse(res: &str) -> Self {
+        if let Some(payload) = res.strip_prefix("OK:") {
+            Self::Ok(payload.to_owned())
+        } else if res == "ALIVE" {
+            Self::Alive
+        } else {
+            Self::Other(res.to_owned())
+        }
+    }
+
+    /// Payload of an `OK:` response
+    pub fn into_ok(self) -> Result<String, PGenError> {
+        match self {
+            Self::Ok(payload) => Ok(payload),
+            Self::Closed => Err(PGenError::NotConnected),
+            Self::Alive => Err(PGenError::Device("ALIVE".to_string())),
+            Self::Other(res) => Err(PGenError::Device(res)),
+        }
+    }
+}
+
+impl PGenError {
+    /// The connection can't be relied on anymore
+    pub fn is_connection_error(&self) -> bool {
+        !matches!(self, Self::Device(_))
+    }
+}
+
+impl fmt::Display for PGenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotConnected => write!(f, "Not connected to TCP socket"),
+            Self::Timeout => write!(f, "Timed out waiting for a response"),
+            Self::MalformedFraming(res) => write!(f, "Malformed response: {res:?}"),
+            Self::Device(res) if res.is_empty() => write!(f, "Device returned an empty response"),
+            Self::Device(res) => write!(f, "Device error: {res}"),
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for PGenError {}
+
+impl From<std::io::Error> for PGenError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e.to_string())
+    }
+}
+
+impl From<tokio::time::error::Elapsed> for PGenError {
+    fn from(_: tokio::time::error::Elapsed) -> Self {
+        Self::Timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PGenError, PGenResponse};
+
+    #[test]
+    fn parse_responses() {
+        assert_eq!(PGenResponse::parse("ALIVE"), PGenResponse::Alive);
+        assert_eq!(PGenResponse::parse("OK:"), PGenResponse::Ok(String::new()));
+        assert_eq!(
+            PGenResponse::parse("OK:\nGET_MODE:16").into_ok(),
+            Ok("\nGET_MODE:16".to_string())
+        );
+
+        let err = PGenResponse::parse("ERROR").into_ok().unwrap_err();
+        assert_eq!(err, PGenError::Device("ERROR".to_string()));
+        assert!(!err.is_connection_error());
+        assert_eq!(err.to_string(), "Device error: ERROR");
+
+        assert_eq!(PGenResponse::Closed.into_ok(), Err(PGenError::NotConnected));
+    }
+}
@@ -0,0 +1,222 @@
+use crate::utils::{Rgb, get_rgb_real_range};
+
+use super::{ColorFormat, Colorimetry, QuantRange, controller::PGenOutputConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YCbCrMatrix {
+    Bt709,
+    Bt2020,
+}
+
+/// RGB to YCbCr conversion done by the PGenerator for YCbCr outputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YCbCrEncoding {
+    pub format: ColorFormat,
+    pub matrix: YCbCrMatrix,
+    pub quant_range: QuantRange,
+    pub bit_depth: u8,
+}
+
+/// Code values emitted for an RGB triplet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YCbCrCodes {
+    /// Y, Cb, Cr
+    pub codes: [u16; 3],
+    /// Components outside of the valid code range before clamping
+    pub clipped: [bool; 3],
+    /// RGB decoded back from the codes, at the pattern bit depth
+    pub decoded_rgb: Rgb,
+}
+
+impl YCbCrMatrix {
+    pub fn from_colorimetry(colorimetry: Colorimetry) -> Self {
+        match colorimetry {
+            Colorimetry::Bt2020Rgb => Self::Bt2020,
+            Colorimetry::Default | Colorimetry::Bt709Ycc => Self::Bt709,
+        }
+    }
+
+    /// Kr, Kb
+    const fn coefficients(&self) -> (f64, f64) {
+        match self {
+            Self::Bt709 => (0.2126, 0.0722),
+            Self::Bt2020 => (0.2627, 0.0593),
+        }
+    }
+}
+
+impl YCbCrEncoding {
+    /// `None` when the output is RGB
+    pub fn from_output_config(cfg: &PGenOutputConfig) -> Option<Self> {
+        if cfg.format == ColorFormat::Rgb {
+            return None;
+        }
+
+        Some(Self {
+            format: cfg.format,
+            matrix: YCbCrMatrix::from_colorimetry(cfg.colorimetry),
+            quant_range: cfg.quant_range,
+            bit_depth: cfg.bit_depth as u8,
+        })
+    }
+
+    fn step(&self) -> f64 {
+        2.0_f64.powi(self.bit_depth as i32 - 8)
+    }
+
+    // Limited range reserves the lowest and highest codes
+    fn valid_code_range(&self) -> (f64, f64) {
+        let max_code = 2.0_f64.powi(self.bit_depth as i32) - 1.0;
+
+        match self.quant_range {
+            QuantRange::Limited => (self.step(), max_code - self.step()),
+            QuantRange::Full => (0.0, max_code),
+        }
+    }
+
+    fn encode_float(&self, ycc: [f64; 3]) -> [f64; 3] {
+        let [y, cb, cr] = ycc;
+
+        match self.quant_range {
+            QuantRange::Limited => {
+                let step = self.step();
+                [
+                    (219.0 * y + 16.0) * step,
+                    (224.0 * cb + 128.0) * step,
+                    (224.0 * cr + 128.0) * step,
+                ]
+            }
+            QuantRange::Full => {
+                let max_code = 2.0_f64.powi(self.bit_depth as i32) - 1.0;
+                let mid = 2.0_f64.powi(self.bit_depth as i32 - 1);
+                [y * max_code, cb * max_code + mid, cr * max_code + mid]
+            }
+        }
+    }
+
+    fn decode_float(&self, codes: [u16; 3]) -> [f64; 3] {
+        let [y, cb, cr] = codes.map(f64::from);
+
+        match self.quant_range {
+            QuantRange::Limited => {
+                let step = self.step();
+                [
+                    (y / step - 16.0) / 219.0,
+                    (cb / step - 128.0) / 224.0,
+                    (cr / step - 128.0) / 224.0,
+                ]
+            }
+            QuantRange::Full => {
+                let max_code = 2.0_f64.powi(self.bit_depth as i32) - 1.0;
+                let mid = 2.0_f64.powi(self.bit_depth as i32 - 1);
+                [y / max_code, (cb - mid) / max_code, (cr - mid) / max_code]
+            }
+        }
+    }
+
+    /// `rgb` is in the pattern's bit depth and range
+    pub fn encode(&self, rgb: Rgb, rgb_limited: bool, rgb_depth: u8) -> YCbCrCodes {
+        let (min, real_max) = get_rgb_real_range(rgb_limited, rgb_depth);
+        let (min, real_max) = (min as f64, real_max as f64);
+        let [r, g, b] = rgb.map(|c| (c as f64 - min) / real_max);
+
+        let (kr, kb) = self.matrix.coefficients();
+        let kg = 1.0 - kr - kb;
+        let y = kr * r + kg * g + kb * b;
+        let cb = (b - y) / (2.0 * (1.0 - kb));
+        let cr = (r - y) / (2.0 * (1.0 - kr));
+
+        let (min_code, max_code) = self.valid_code_range();
+        let ycc_codes = self.encode_float([y, cb, cr]).map(f64::round);
+        let clipped = ycc_codes.map(|c| c < min_code || c > max_code);
+        let codes = ycc_codes.map(|c| c.clamp(min_code, max_code) as u16);
+
+        let [y, cb, cr] = self.decode_float(codes);
+        let r = y + 2.0 * (1.0 - kr) * cr;
+        let b = y + 2.0 * (1.0 - kb) * cb;
+        let g = (y - kr * r - kb * b) / kg;
+
+        let max_rgb = 2.0_f64.powi(rgb_depth as i32) - 1.0;
+        let decoded_rgb =
+            [r, g, b].map(|c| (c * real_max + min).round().clamp(0.0, max_rgb) as u16);
+
+        YCbCrCodes {
+            codes,
+            clipped,
+            decoded_rgb,
+        }
+    }
+}
+
+impl YCbCrCodes {
+    pub fn warnings(&self, rgb: Rgb) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        let clipped: Vec<_> = ["Y", "Cb", "Cr"]
+            .into_iter()
+            .zip(self.clipped)
+            .filter_map(|(name, clipped)| clipped.then_some(name))
+            .collect();
+        if !clipped.is_empty() {
+            warnings.push(format!("{} clipped", clipped.join(", ")));
+        }
+
+        if self.decoded_rgb != rgb {
+            let [r, g, b] = self.decoded_rgb;
+            warnings.push(format!("Decodes to RGB {r}, {g}, {b}"));
+        }
+
+        warnings
+    }
+}
+
+impl std::fmt::Display for YCbCrCodes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [y, cb, cr] = self.codes;
+        write!(f, "{y}, {cb}, {cr}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{YCbCrEncoding, YCbCrMatrix};
+    use crate::pgen::{ColorFormat, QuantRange};
+
+    #[test]
+    fn encode_ycbcr_codes() {
+        let encoding = YCbCrEncoding {
+            format: ColorFormat::YCbCr444,
+            matrix: YCbCrMatrix::Bt709,
+            quant_range: QuantRange::Limited,
+            bit_depth: 10,
+        };
+
+        let white = encoding.encode([940, 940, 940], true, 10);
+        assert_eq!(white.codes, [940, 512, 512]);
+        assert!(white.warnings([940, 940, 940]).is_empty());
+
+        let red = encoding.encode([940, 64, 64], true, 10);
+        assert_eq!(red.codes, [250, 409, 960]);
+        assert_eq!(red.clipped, [false; 3]);
+
+        // Full range chroma overshoots by half a code
+        let full = YCbCrEncoding {
+            quant_range: QuantRange::Full,
+            bit_depth: 8,
+            ..encoding
+        };
+        let blue = full.encode([0, 0, 255], false, 8);
+        assert_eq!(blue.codes[1], 255);
+        assert_eq!(blue.clipped, [false, true, false]);
+        assert_eq!(blue.warnings([0, 0, 255])[0], "Cb clipped");
+
+        // 10 bit pattern at 8 bit output
+        let eight_bit = YCbCrEncoding {
+            bit_depth: 8,
+            ..encoding
+        };
+        let grey = eight_bit.encode([502, 502, 502], true, 10);
+        assert_eq!(grey.codes, [126, 128, 128]);
+        assert_eq!(grey.decoded_rgb, [504, 504, 504]);
+    }
+}
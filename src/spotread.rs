@@ -1,4 +1,8 @@
-use std::{iter::once, process::Stdio, time::Duration};
+use std::{
+    iter::once,
+    process::Stdio,
+    time::{Duration, Instant},
+};
 
 use anyhow::{Result, anyhow, bail};
 use futures::{FutureExt, StreamExt};
@@ -13,7 +17,11 @@ use crate::{
     app::PGenAppUpdate,
     calibration::{CalibrationTarget, PatternInsertionConfig, ReadingResult},
     external::ExternalJobCmd,
-    pgen::{controller::PGenControllerHandle, pattern_config::PGenPatternConfig},
+    pgen::{
+        controller::{ConnectionStatus, PGenControllerHandle},
+        pattern_config::PGenPatternConfig,
+        response::PGenError,
+    },
     utils::pattern_cfg_set_colour_from_float_level,
 };
 
@@ -21,6 +29,10 @@ const EXPECTED_INIT_LINE: &str = "Place instrument on spot to be measured";
 const READING_READY_SUBSTR: &str = "take a reading:";
 const READING_RESULT_SUBSTR: &str = "Result is XYZ";
 
+// The reconnect backoff reaches its 60 s maximum after about a minute
+const RECONNECT_DEADLINE: Duration = Duration::from_secs(120);
+const RECONNECT_POLL_PERIOD: Duration = Duration::from_millis(250);
+
 #[derive(Debug)]
 struct SpotreadProc {
     child: Child,
@@ -110,41 +122,31 @@ pub fn start_spotread_worker(
                 }
                 msg = rx.select_next_some() => {
                     match msg {
-                        SpotreadCmd::DoReading(SpotreadReadingConfig { target, pattern_cfg, pattern_insertion_cfg }) => {
+                        SpotreadCmd::DoReading(reading_cfg) => {
                             // ready process stdout before sending patch
                             // because the result must be sent asap and flushing stdout would delay result handling
                             spotread_proc.read_until_take_reading_ready().await.ok();
 
-                            {
-                                let mut controller = controller_handle.lock().await;
-
-                                let wait_duration = if pattern_insertion_cfg.enabled {
-                                    let mut inserted_pattern_cfg = pattern_cfg;
-                                    pattern_cfg_set_colour_from_float_level(&mut inserted_pattern_cfg, pattern_insertion_cfg.level);
-
-                                    controller.send_pattern_and_wait(inserted_pattern_cfg, pattern_insertion_cfg.duration).await;
-
-                                    // Leave more time for the display to adjust after inserted pattern
-                                    Duration::from_secs_f64(1.5)
-                                } else {
-                                    Duration::from_secs_f64(0.5)
-                                };
-
-                                controller.send_pattern_and_wait(pattern_cfg, wait_duration).await;
-                            }
-
-                            let res = tokio::time::timeout(Duration::from_secs(30), spotread_proc.try_measure(target)).await;
+                            let target = reading_cfg.target;
+                            let res = display_and_measure(&controller_handle, reading_cfg, async || {
+                                tokio::time::timeout(Duration::from_secs(30), spotread_proc.try_measure(target)).await
+                            })
+                            .await;
 
                             match res {
-                                Ok(res) => {
-                                    if let Err(e) = res {
-                                        app_tx.try_send(PGenAppUpdate::SpotreadRes(None)).ok();
-                                        log::error!("Failed taking measure {e}");
-                                    }
+                                Ok(Ok(Ok(()))) => (),
+                                Ok(Ok(Err(e))) => {
+                                    app_tx.try_send(PGenAppUpdate::SpotreadRes(None)).ok();
+                                    log::error!("Failed taking measure {e}");
                                 }
-                                Err(_) => {
+                                Ok(Err(_)) => {
                                     log::error!("Timed out trying to measure patch");
                                 }
+                                Err(e) => {
+                                    // Don't measure whatever is on screen, the run stops
+                                    log::error!("Failed sending pattern, not measuring: {e}");
+                                    app_tx.try_send(PGenAppUpdate::SpotreadRes(None)).ok();
+                                }
                             }
 
                             external_tx.try_send(ExternalJobCmd::SpotreadDoneMeasuring).ok();
@@ -165,6 +167,79 @@ pub fn start_spotread_worker(
     Ok(tx)
 }
 
+/// Displays the reading pattern then measures.
+/// When the pattern fails, waits for the controller to restore the connection and sends it again.
+async fn display_and_measure<T>(
+    controller_handle: &PGenControllerHandle,
+    reading_cfg: SpotreadReadingConfig,
+    measure: impl AsyncFnOnce() -> T,
+) -> Result<T, PGenError> {
+    if let Err(e) = send_reading_pattern(controller_handle, reading_cfg).await {
+        log::warn!("Failed sending pattern, waiting for the connection to be restored: {e}");
+
+        if !wait_for_reconnect(controller_handle).await {
+            return Err(e);
+        }
+        send_reading_pattern(controller_handle, reading_cfg).await?;
+    }
+
+    Ok(measure().await)
+}
+
+async fn send_reading_pattern(
+    controller_handle: &PGenControllerHandle,
+    reading_cfg: SpotreadReadingConfig,
+) -> Result<(), PGenError> {
+    let SpotreadReadingConfig {
+        pattern_cfg,
+        pattern_insertion_cfg,
+        ..
+    } = reading_cfg;
+    let mut controller = controller_handle.lock().await;
+
+    let wait_duration = if pattern_insertion_cfg.enabled {
+        let mut inserted_pattern_cfg = pattern_cfg;
+        pattern_cfg_set_colour_from_float_level(
+            &mut inserted_pattern_cfg,
+            pattern_insertion_cfg.level,
+        );
+
+        controller
+            .send_pattern_and_wait(inserted_pattern_cfg, pattern_insertion_cfg.duration)
+            .await?;
+
+        // Leave more time for the display to adjust after inserted pattern
+        Duration::from_secs_f64(1.5)
+    } else {
+        Duration::from_secs_f64(0.5)
+    };
+
+    controller
+        .send_pattern_and_wait(pattern_cfg, wait_duration)
+        .await
+}
+
+/// Returns whether the controller reconnected before the deadline.
+/// The controller is only locked to check the status, its reconnect tick needs it.
+async fn wait_for_reconnect(controller_handle: &PGenControllerHandle) -> bool {
+    let deadline = Instant::now() + RECONNECT_DEADLINE;
+
+    while Instant::now() < deadline {
+        let status = controller_handle.lock().await.state.connection_status;
+        match status {
+            ConnectionStatus::Connected => return true,
+            status if status.is_recovering() => (),
+            // Disconnected by the user
+            _ => return false,
+        }
+
+        tokio::time::sleep(RECONNECT_POLL_PERIOD).await;
+    }
+
+    log::error!("Connection not restored after {RECONNECT_DEADLINE:?}");
+    false
+}
+
 impl SpotreadProc {
     pub fn new(
         app_tx: Sender<PGenAppUpdate>,
@@ -324,3 +399,58 @@ impl SpotreadProc {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::sync::Mutex;
+
+    use crate::pgen::controller::handler::{
+        PGenController,
+        tests::{mock_server, pattern},
+    };
+
+    use super::{SpotreadReadingConfig, display_and_measure};
+
+    #[tokio::test]
+    async fn measures_after_reconnect() {
+        let (socket_addr, received) = mock_server().await;
+
+        let mut controller = PGenController::new(None);
+        controller.update_socket(socket_addr).await;
+        controller.initial_connect().await;
+        // The connection is closed after this pattern
+        controller
+            .send_pattern_from_cfg(pattern([100, 100, 100]))
+            .await
+            .unwrap();
+        let controller_handle = Arc::new(Mutex::new(controller));
+
+        // Stands in for the daemon reconnect tick
+        let reconnect_handle = controller_handle.clone();
+        let reconnect_task = tokio::spawn(async move {
+            loop {
+                reconnect_handle.lock().await.try_reconnect().await;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        });
+
+        let reading_cfg = SpotreadReadingConfig {
+            target: Default::default(),
+            pattern_cfg: pattern([512, 256, 128]),
+            pattern_insertion_cfg: Default::default(),
+        };
+        let pattern_displayed = async || {
+            let connections = received.lock().unwrap();
+            connections.get(1).is_some_and(|cmds| {
+                cmds.iter()
+                    .any(|cmd| cmd.starts_with("RGB=") && cmd.contains(";512,256,128;"))
+            })
+        };
+        let res = display_and_measure(&controller_handle, reading_cfg, pattern_displayed).await;
+        reconnect_task.abort();
+
+        assert!(matches!(res, Ok(true)));
+    }
+}